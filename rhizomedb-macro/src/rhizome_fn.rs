@@ -4,7 +4,7 @@ use syn::{
     parenthesized,
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
-    Attribute, Generics, Ident, Token, Type,
+    Attribute, Expr, Generics, Ident, Token, Type,
 };
 
 pub(crate) fn expand(input: RhizomeFunctionDecl) -> TokenStream {
@@ -78,13 +78,28 @@ pub(crate) fn expand(input: RhizomeFunctionDecl) -> TokenStream {
     if let Some(agg_expr) = is_aggregate {
         let agg_expr = agg_expr.meta.require_name_value().unwrap().value.clone();
 
+        // Aggregates that are generic over more than their output type name their
+        // type arguments explicitly, e.g. `#[aggregate = Variance::<T>]`
+        let agg_type = match &agg_expr {
+            Expr::Path(path)
+                if path
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|segment| !segment.arguments.is_empty()) =>
+            {
+                quote!(#agg_expr)
+            }
+            _ => quote!(#agg_expr<#return_type>),
+        };
+
         tokens = quote! {
             #tokens
 
             impl #impl_generics ::rhizomedb::aggregation::AggregateGroupBy<#input_type #return_type> for HelperType #ty_generics
             #where_clause
             {
-                type Aggregate = #agg_expr<#return_type>;
+                type Aggregate = #agg_type;
 
                 fn as_args(&self) -> Vec<Var> {
                     let mut result = Vec::default();
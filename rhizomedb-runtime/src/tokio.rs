@@ -9,6 +9,8 @@ use std::{
     thread,
 };
 
+pub use std::time::Instant;
+
 use futures::{
     channel::mpsc::{unbounded, UnboundedSender},
     stream::StreamExt,
@@ -22,7 +24,7 @@ type SpawnTask = Box<dyn Send + FnOnce()>;
 static DEFAULT_WORKER_NAME: &str = "rhizomedb-runtime-worker";
 
 thread_local! {
-    static TASK_COUNT: RefCell<Option<Arc<AtomicUsize>>> = RefCell::new(None);
+    static TASK_COUNT: RefCell<Option<Arc<AtomicUsize>>> = const { RefCell::new(None) };
     static LOCAL_SET: LocalSet = LocalSet::new()
 }
 
@@ -219,6 +221,11 @@ impl LocalHandle {
     }
 }
 
+/// Yields control back to the executor, allowing other tasks to run.
+pub async fn yield_now() {
+    tokio::task::yield_now().await
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
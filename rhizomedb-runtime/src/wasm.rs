@@ -1,4 +1,4 @@
-use std::{future::Future, marker::PhantomData};
+use std::{future::Future, marker::PhantomData, time::Duration};
 
 use wasm_bindgen_futures::spawn_local;
 
@@ -53,3 +53,23 @@ impl LocalHandle {
         spawn_local(f);
     }
 }
+
+/// Yields control back to the browser's event loop, allowing other tasks to run.
+pub async fn yield_now() {
+    gloo::timers::future::TimeoutFuture::new(0).await
+}
+
+/// A monotonic clock reading, backed by `Date.now()` since `std::time::Instant`
+/// is unavailable on wasm32-unknown-unknown.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Instant(f64);
+
+impl Instant {
+    pub fn now() -> Self {
+        Self(js_sys::Date::now())
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_secs_f64((Self::now().0 - self.0).max(0.0) / 1000.0)
+    }
+}
@@ -9,12 +9,14 @@ mod tests {
         sync::{Arc, Mutex},
     };
 
-    use futures::{sink::unfold, StreamExt};
+    use futures::{channel::oneshot, sink::unfold, StreamExt};
     use tokio::{spawn, test};
 
     use rhizomedb::{
-        runtime::client::Client,
+        error::Error,
+        runtime::{client::Client, ClientEvent, Limits},
         tuple::{InputTuple, Tuple},
+        value::Val,
     };
 
     #[test]
@@ -112,4 +114,306 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    async fn test_sink_consumed() -> Result<()> {
+        let buf1 = Arc::new(Mutex::new(RefCell::new(BTreeSet::new())));
+        let buf2 = Arc::clone(&buf1);
+
+        let (mut client, mut rx, reactor) = Client::new();
+
+        spawn(async move {
+            reactor
+                .async_run(|p| {
+                    p.output("edge", |h| h.column::<i32>("from").column::<i32>("to"))?;
+                    p.output("node", |h| h.column::<i32>("id"))?;
+
+                    p.rule::<(i32, i32)>("edge", &|h, b, (x, y)| {
+                        h.bind((("from", x), ("to", y)))?;
+                        b.search("evac", (("entity", x), ("attribute", "to"), ("value", y)))?;
+
+                        Ok(())
+                    })?;
+
+                    p.rule::<(i32, i32)>("node", &|h, b, (x, y)| {
+                        h.bind((("id", x),))?;
+                        b.search("edge", (("from", x), ("to", y)))?;
+
+                        Ok(())
+                    })?;
+
+                    // Sinks must consume their relations, or they're pruned
+                    p.consume("edge")?;
+
+                    Ok(p)
+                })
+                .await
+                .unwrap()
+        });
+
+        spawn(async move {
+            loop {
+                let _ = rx.next().await;
+            }
+        });
+
+        let mut registered = Vec::default();
+
+        for id in ["edge", "node"] {
+            let buf = Arc::clone(&buf1);
+
+            let result = client
+                .register_sink(
+                    id,
+                    Box::new(move || {
+                        Box::new(unfold((), move |(), tuple| {
+                            let b = Arc::clone(&buf);
+                            async move {
+                                b.lock().unwrap().borrow_mut().insert(tuple);
+                                Ok(())
+                            }
+                        }))
+                    }),
+                )
+                .await;
+
+            registered.push(result.map_err(|e| e.downcast::<Error>().unwrap()));
+        }
+
+        // The sink on node is rejected, since node is pruned
+        assert_eq!(
+            vec![Ok(()), Err(Error::UnrecognizedSink("node".into()))],
+            registered
+        );
+
+        client
+            .insert_tuple(InputTuple::new(0, "to", 1, vec![]))
+            .await?;
+        client.flush().await?;
+
+        assert_eq!(
+            *buf2.lock().unwrap().borrow(),
+            BTreeSet::from_iter([Tuple::new("edge", [("from", 0), ("to", 1)], None)])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    async fn test_rejected_once() -> Result<()> {
+        let (mut client, mut rx, reactor) = Client::new();
+
+        spawn(async move {
+            reactor
+                .with_strict(true)
+                .async_run(|p| {
+                    p.output("edge", |h| h.column::<i32>("from").column::<i32>("to"))?;
+                    p.output("source", |h| h.column::<i32>("id"))?;
+
+                    p.rule::<(i32, i32)>("edge", &|h, b, (x, y)| {
+                        h.bind((("from", x), ("to", y)))?;
+                        b.search("evac", (("entity", x), ("attribute", "to"), ("value", y)))?;
+
+                        Ok(())
+                    })?;
+
+                    // Negation makes the program non-monotonic, so that every epoch
+                    // reloads the tuples of those before it
+                    p.rule::<(i32, i32)>("source", &|h, b, (x, y)| {
+                        h.bind((("id", x),))?;
+                        b.search("edge", (("from", x), ("to", y)))?;
+                        b.except("edge", (("to", x),))?;
+
+                        Ok(())
+                    })?;
+
+                    Ok(p)
+                })
+                .await
+                .unwrap()
+        });
+
+        let (events_tx, mut events_rx) = futures::channel::mpsc::unbounded();
+
+        spawn(async move {
+            while let Some(event) = rx.next().await {
+                match event {
+                    ClientEvent::TupleRejected(tuple, _) => {
+                        let _ = events_tx.unbounded_send(Some(tuple));
+                    }
+                    ClientEvent::ReachedFixedpoint(_, _) => {
+                        let _ = events_tx.unbounded_send(None);
+                    }
+                    _ => (),
+                }
+            }
+        });
+
+        let mut rejected = Vec::default();
+
+        for input in [
+            InputTuple::new(0, "to", "a", vec![]),
+            InputTuple::new(1, "to", 2, vec![]),
+        ] {
+            client.insert_tuple(input).await?;
+
+            while let Some(Some(tuple)) = events_rx.next().await {
+                rejected.push(tuple);
+            }
+        }
+
+        assert_eq!(
+            vec![Tuple::new(
+                "edge",
+                [("from", Val::from(0)), ("to", Val::from("a"))],
+                None
+            )],
+            rejected
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    async fn test_rejected_once_rewound() -> Result<()> {
+        let (mut client, mut rx, reactor) = Client::new();
+
+        spawn(async move {
+            reactor
+                .with_strict(true)
+                .async_run(|p| {
+                    p.output("edge", |h| h.column::<i32>("from").column::<i32>("to"))?;
+
+                    p.rule::<(i32, i32)>("edge", &|h, b, (x, y)| {
+                        h.bind((("from", x), ("to", y)))?;
+                        b.search("evac", (("entity", x), ("attribute", "to"), ("value", y)))?;
+
+                        Ok(())
+                    })?;
+
+                    Ok(p)
+                })
+                .await
+                .unwrap()
+        });
+
+        let (events_tx, mut events_rx) = futures::channel::mpsc::unbounded();
+
+        spawn(async move {
+            while let Some(event) = rx.next().await {
+                match event {
+                    ClientEvent::TupleRejected(tuple, _) => {
+                        let _ = events_tx.unbounded_send(Some(tuple));
+                    }
+                    ClientEvent::ReachedFixedpoint(_, _) => {
+                        let _ = events_tx.unbounded_send(None);
+                    }
+                    _ => (),
+                }
+            }
+        });
+
+        let mut rejected = Vec::default();
+
+        for input in [
+            InputTuple::new(0, "to", "a", vec![]),
+            InputTuple::new(1, "to", "b", vec![]),
+        ] {
+            client.insert_tuple(input).await?;
+
+            while let Some(Some(tuple)) = events_rx.next().await {
+                rejected.push(tuple);
+            }
+        }
+
+        // Rewinding recomputes the first epoch, whose rejection was already reported
+        client.rewind_epoch().await?;
+
+        while let Some(Some(tuple)) = events_rx.next().await {
+            rejected.push(tuple);
+        }
+
+        assert_eq!(
+            vec![
+                Tuple::new(
+                    "edge",
+                    [("from", Val::from(0)), ("to", Val::from("a"))],
+                    None
+                ),
+                Tuple::new(
+                    "edge",
+                    [("from", Val::from(1)), ("to", Val::from("b"))],
+                    None
+                ),
+            ],
+            rejected
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    async fn test_epoch_aborted() -> Result<()> {
+        let (mut client, mut rx, reactor) = Client::new();
+
+        spawn(async move {
+            reactor
+                .with_limits(Limits::default().iterations(2))
+                .async_run(|p| {
+                    p.output("edge", |h| h.column::<i32>("from").column::<i32>("to"))?;
+                    p.output("path", |h| h.column::<i32>("from").column::<i32>("to"))?;
+
+                    p.rule::<(i32, i32)>("edge", &|h, b, (x, y)| {
+                        h.bind((("from", x), ("to", y)))?;
+                        b.search("evac", (("entity", x), ("attribute", "to"), ("value", y)))?;
+
+                        Ok(())
+                    })?;
+
+                    p.rule::<(i32, i32)>("path", &|h, b, (x, y)| {
+                        h.bind((("from", x), ("to", y)))?;
+                        b.search("edge", (("from", x), ("to", y)))?;
+
+                        Ok(())
+                    })?;
+
+                    p.rule::<(i32, i32, i32)>("path", &|h, b, (x, y, z)| {
+                        h.bind((("from", x), ("to", z)))?;
+
+                        b.search("edge", (("from", x), ("to", y)))?;
+                        b.search("path", (("from", y), ("to", z)))?;
+
+                        Ok(())
+                    })?;
+
+                    Ok(p)
+                })
+                .await
+                .unwrap()
+        });
+
+        let (aborted_tx, aborted_rx) = oneshot::channel();
+
+        spawn(async move {
+            let mut aborted_tx = Some(aborted_tx);
+
+            while let Some(event) = rx.next().await {
+                if let ClientEvent::EpochAborted(_, err) = event {
+                    if let Some(tx) = aborted_tx.take() {
+                        let _ = tx.send(err);
+                    }
+                }
+            }
+        });
+
+        for i in 0..4 {
+            client
+                .insert_tuple(InputTuple::new(i, "to", i + 1, vec![]))
+                .await?;
+        }
+
+        assert_eq!(Error::IterationLimitExceeded(2), aborted_rx.await?);
+
+        Ok(())
+    }
 }
@@ -7,7 +7,7 @@
 use futures::{sink::unfold, StreamExt};
 
 use js_sys::AsyncIterator;
-use rhizomedb::{runtime::ClientEvent, tuple::Tuple, value::Val};
+use rhizomedb::{runtime::ClientEvent, tuple::Tuple};
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 use wasm_bindgen_downcast::DowncastJS;
@@ -65,6 +65,12 @@ impl Rhizome {
                             &serde_wasm_bindgen::to_value(&Cid(new_epoch)).unwrap(),
                         )
                         .unwrap(),
+                    Some(ClientEvent::EpochAborted(_, err)) => {
+                        gloo_console::error!(err.to_string())
+                    }
+                    Some(ClientEvent::TupleRejected(tuple, err)) => {
+                        gloo_console::warn!(format!("Rejected {tuple}: {err}"))
+                    }
                     None => continue,
                 };
             }
@@ -119,33 +125,12 @@ impl Rhizome {
                         let js_tuple = js_sys::Object::new();
 
                         for col in tuple.cols() {
-                            match tuple.col(&col).unwrap() {
-                                Val::Bool(v) => js_sys::Reflect::set(
-                                    &js_tuple,
-                                    &col.resolve().into(),
-                                    &serde_wasm_bindgen::to_value(&v).unwrap(),
-                                )
-                                .unwrap(),
-                                Val::S64(v) => js_sys::Reflect::set(
-                                    &js_tuple,
-                                    &col.resolve().into(),
-                                    &serde_wasm_bindgen::to_value(&v).unwrap(),
-                                )
-                                .unwrap(),
-                                Val::String(v) => js_sys::Reflect::set(
-                                    &js_tuple,
-                                    &col.resolve().into(),
-                                    &serde_wasm_bindgen::to_value(&v).unwrap(),
-                                )
-                                .unwrap(),
-                                Val::Cid(v) => js_sys::Reflect::set(
-                                    &js_tuple,
-                                    &col.resolve().into(),
-                                    &serde_wasm_bindgen::to_value(&Cid(v)).unwrap(),
-                                )
-                                .unwrap(),
-                                _ => panic!("unsupported type"),
-                            };
+                            js_sys::Reflect::set(
+                                &js_tuple,
+                                &col.resolve().into(),
+                                &tuple::to_js(&tuple.col(&col).unwrap()),
+                            )
+                            .unwrap();
                         }
 
                         f.call1(&JsValue::NULL, &js_tuple).unwrap();
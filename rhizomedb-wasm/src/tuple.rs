@@ -1,7 +1,8 @@
-use std::rc::Rc;
+use std::{collections::BTreeMap, rc::Rc, sync::Arc};
 
-use rhizomedb::tuple::Tuple;
-use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+use js_sys::{Array, Date, Object, Reflect, Uint8Array};
+use rhizomedb::{tuple::Tuple, value::Val};
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
 use wasm_bindgen_downcast::DowncastJS;
 
 use crate::Cid;
@@ -34,30 +35,86 @@ impl InputTuple {
             }
         }
 
-        if let Some(val) = value.as_bool() {
-            Self(rhizomedb::tuple::InputTuple::new(
-                entity, attribute, val, links,
-            ))
-        } else if let Some(val) = value.as_f64() {
-            Self(rhizomedb::tuple::InputTuple::new(
-                entity, attribute, val as i64, links,
-            ))
-        } else if let Some(val) = value.as_string() {
-            Self(rhizomedb::tuple::InputTuple::new(
-                entity,
-                attribute,
-                val.as_ref(),
-                links,
-            ))
-        } else if let Ok(val) = serde_wasm_bindgen::from_value::<Cid>(value) {
-            Self(rhizomedb::tuple::InputTuple::new(
-                entity,
-                attribute,
-                val.inner(),
-                links,
-            ))
-        } else {
+        let Some(val) = from_js(value) else {
             panic!("unknown type")
+        };
+
+        Self(rhizomedb::tuple::InputTuple::new(
+            entity, attribute, val, links,
+        ))
+    }
+}
+
+/// Converts a JS value into a value, recursing into arrays and plain objects. Byte
+/// arrays are only taken to be bytes when they don't decode as a CID, as that's
+/// how CIDs are passed from JS.
+fn from_js(value: JsValue) -> Option<Val> {
+    if let Some(val) = value.as_bool() {
+        Some(Val::from(val))
+    } else if let Some(val) = value.as_f64() {
+        Some(Val::from(val as i64))
+    } else if let Some(val) = value.as_string() {
+        Some(Val::from(val))
+    } else if Array::is_array(&value) {
+        let vals = Array::from(&value)
+            .iter()
+            .map(from_js)
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Val::from(vals))
+    } else if let Ok(val) = serde_wasm_bindgen::from_value::<Cid>(value.clone()) {
+        Some(Val::from(val.inner()))
+    } else if let Some(val) = value.dyn_ref::<Uint8Array>() {
+        Some(Val::from(val.to_vec()))
+    } else if value.is_object() && !value.is_function() {
+        let entries = Object::entries(value.unchecked_ref())
+            .iter()
+            .map(|entry| {
+                let entry = Array::from(&entry);
+
+                Some((Arc::from(entry.get(0).as_string()?), from_js(entry.get(1))?))
+            })
+            .collect::<Option<BTreeMap<_, _>>>()?;
+
+        Some(Val::from(entries))
+    } else {
+        None
+    }
+}
+
+/// Converts a value into a JS value, the inverse of `from_js`. 128-bit integers and
+/// decimals are converted into strings, as a JS number can't represent them exactly.
+pub(crate) fn to_js(val: &Val) -> JsValue {
+    match val {
+        Val::Null => JsValue::NULL,
+        Val::Bool(v) => JsValue::from_bool(*v),
+        Val::S8(v) => JsValue::from(*v),
+        Val::U8(v) => JsValue::from(*v),
+        Val::S16(v) => JsValue::from(*v),
+        Val::U16(v) => JsValue::from(*v),
+        Val::S32(v) => JsValue::from(*v),
+        Val::U32(v) => JsValue::from(*v),
+        Val::F32(v) => JsValue::from(v.into_inner()),
+        Val::S64(v) => serde_wasm_bindgen::to_value(v).unwrap(),
+        Val::U64(v) => serde_wasm_bindgen::to_value(v).unwrap(),
+        Val::F64(v) => JsValue::from(v.into_inner()),
+        Val::S128(v) => JsValue::from_str(&v.to_string()),
+        Val::U128(v) => JsValue::from_str(&v.to_string()),
+        Val::Decimal(v) => JsValue::from_str(&v.to_string()),
+        Val::Timestamp(v) => Date::new(&JsValue::from_f64(v.nanos() as f64 / 1e6)).into(),
+        Val::Char(v) => JsValue::from_str(&v.to_string()),
+        Val::String(v) => JsValue::from_str(v),
+        Val::Cid(v) => serde_wasm_bindgen::to_value(&Cid(*v)).unwrap(),
+        Val::Bytes(v) => Uint8Array::from(&v[..]).into(),
+        Val::List(v) => v.iter().map(to_js).collect::<Array>().into(),
+        Val::Map(v) => {
+            let object = Object::new();
+
+            for (key, val) in v.iter() {
+                Reflect::set(&object, &JsValue::from_str(key), &to_js(val)).unwrap();
+            }
+
+            object.into()
         }
     }
 }
@@ -1,4 +1,11 @@
-use crate::{args::Args, types::IntoColType, value::Val, var::Var};
+use std::sync::Arc;
+
+use crate::{
+    args::{Args, Row},
+    types::{ColType, IntoColType},
+    value::Val,
+    var::Var,
+};
 
 pub trait AggAcc:
     IntoColType + Into<Val> + TryFrom<Val, Error = ()> + Send + Sync + 'static
@@ -12,28 +19,106 @@ where
 {
 }
 
+/// The values that an aggregate binds its targets to for each group, as zero or
+/// more rows of one value per target.
+pub trait AggOutput: Send + Sync + 'static {
+    fn col_types() -> Vec<ColType>;
+    fn into_rows(self) -> Vec<Vec<Val>>;
+}
+
+impl<T> AggOutput for T
+where
+    T: AggAcc,
+{
+    fn col_types() -> Vec<ColType> {
+        vec![T::into_col_type()]
+    }
+
+    fn into_rows(self) -> Vec<Vec<Val>> {
+        vec![vec![self.into()]]
+    }
+}
+
+/// The rows selected from a group, each binding several targets.
+#[derive(Debug, Clone)]
+pub struct Rows<R>(pub Vec<R>);
+
+impl<R> AggOutput for Rows<R>
+where
+    R: Row,
+{
+    fn col_types() -> Vec<ColType> {
+        R::col_types()
+    }
+
+    fn into_rows(self) -> Vec<Vec<Val>> {
+        self.0.into_iter().map(Row::into_vals).collect()
+    }
+}
+
+/// The input to aggregates that select rows by a key.
+#[derive(Debug, Clone)]
+pub struct Keyed<K, R>(pub K, pub R);
+
+impl<K, R> Args for Keyed<K, R>
+where
+    K: IntoColType + TryFrom<Val, Error = ()> + Send + Sync + 'static,
+    R: Row,
+{
+    fn instantiate(mut bindings: Vec<Val>) -> Result<Self, ()> {
+        if bindings.is_empty() {
+            return Err(());
+        }
+
+        let row = bindings.split_off(1);
+        let key = bindings.remove(0).try_into()?;
+
+        Ok(Self(key, R::instantiate(row)?))
+    }
+
+    fn col_types() -> Vec<ColType> {
+        let mut col_types = vec![K::into_col_type()];
+        col_types.extend(R::col_types());
+
+        col_types
+    }
+}
+
 pub trait AggregateGroupBy<I, O> {
     type Aggregate: Aggregate<Input = I, Output = O>;
 
     // TODO: support arbitrary expressions here, instead of just vars
     fn as_args(&self) -> Vec<Var>;
+
+    fn aggregate(&self) -> Self::Aggregate {
+        Self::Aggregate::default()
+    }
 }
 
 pub trait Aggregate: Sized + Default {
     type Input;
     type Output;
 
+    /// Creates a fresh aggregate for a group, carrying over any parameters.
+    fn init(&self) -> Self {
+        Self::default()
+    }
+
     fn step(&mut self, args: Self::Input);
     fn finalize(&self) -> Option<Self::Output>;
 }
 
-impl<T: ?Sized> Aggregate for Box<T>
+impl<T> Aggregate for Box<T>
 where
     T: Aggregate,
 {
     type Input = T::Input;
     type Output = T::Output;
 
+    fn init(&self) -> Self {
+        Box::new(T::init(self))
+    }
+
     fn step(&mut self, args: Self::Input) {
         (**self).step(args);
     }
@@ -46,17 +131,19 @@ where
 pub trait AggregateWrapper: Send + Sync + 'static {
     fn init(&self) -> Box<dyn AggregateWrapper>;
     fn step(&mut self, args: Vec<Val>);
-    fn finalize(&self) -> Option<Val>;
+    fn finalize(&self) -> Vec<Vec<Val>>;
+    fn arg_types(&self) -> Vec<ColType>;
+    fn output_types(&self) -> Vec<ColType>;
 }
 
 impl<T, I, O> AggregateWrapper for T
 where
     T: Aggregate<Input = I, Output = O> + Send + Sync + 'static,
     I: Args,
-    O: AggAcc,
+    O: AggOutput,
 {
     fn init(&self) -> Box<dyn AggregateWrapper> {
-        Box::<T>::default()
+        Box::new(T::init(self))
     }
 
     fn step(&mut self, args: Vec<Val>) {
@@ -65,7 +152,69 @@ where
         T::step(self, args);
     }
 
-    fn finalize(&self) -> Option<Val> {
-        T::finalize(self).map(Into::into)
+    fn finalize(&self) -> Vec<Vec<Val>> {
+        T::finalize(self)
+            .map(AggOutput::into_rows)
+            .unwrap_or_default()
+    }
+
+    fn arg_types(&self) -> Vec<ColType> {
+        <T::Input as Args>::col_types()
+    }
+
+    fn output_types(&self) -> Vec<ColType> {
+        O::col_types()
+    }
+}
+
+/// Several aggregates over the same group, stepped together so that the group is
+/// only scanned once. Each aggregate takes its arguments in turn, and the rows of
+/// their outputs are joined together.
+pub(crate) struct Aggregates(Vec<Box<dyn AggregateWrapper>>);
+
+impl Aggregates {
+    pub(crate) fn new(aggs: &[Arc<dyn AggregateWrapper>]) -> Self {
+        Self(aggs.iter().map(|agg| agg.init()).collect())
+    }
+}
+
+impl AggregateWrapper for Aggregates {
+    fn init(&self) -> Box<dyn AggregateWrapper> {
+        Box::new(Self(self.0.iter().map(|agg| agg.init()).collect()))
+    }
+
+    fn step(&mut self, mut args: Vec<Val>) {
+        for agg in &mut self.0 {
+            let rest = args.split_off(agg.arg_types().len().min(args.len()));
+
+            agg.step(args);
+
+            args = rest;
+        }
+    }
+
+    fn finalize(&self) -> Vec<Vec<Val>> {
+        self.0.iter().fold(vec![vec![]], |rows, agg| {
+            let finalized = agg.finalize();
+
+            rows.iter()
+                .flat_map(|row| {
+                    finalized.iter().map(move |other| {
+                        let mut row = row.clone();
+                        row.extend(other.iter().cloned());
+
+                        row
+                    })
+                })
+                .collect()
+        })
+    }
+
+    fn arg_types(&self) -> Vec<ColType> {
+        self.0.iter().flat_map(|agg| agg.arg_types()).collect()
+    }
+
+    fn output_types(&self) -> Vec<ColType> {
+        self.0.iter().flat_map(|agg| agg.output_types()).collect()
     }
 }
@@ -1,15 +1,30 @@
-use crate::{types::IntoColType, value::Val};
+use crate::{
+    types::{ColType, IntoColType},
+    value::Val,
+};
 
 pub trait Args: Sized + Send + Sync + 'static {
     // TODO: return an InternalRhizomeError instead of ()
     #[allow(clippy::result_unit_err)]
     fn instantiate(bindings: Vec<Val>) -> Result<Self, ()>;
+
+    fn col_types() -> Vec<ColType>;
+}
+
+/// Arguments that can be converted back into values, so that they can bind the
+/// targets of an aggregate.
+pub trait Row: Args + Clone {
+    fn into_vals(self) -> Vec<Val>;
 }
 
 impl Args for () {
     fn instantiate(_bindings: Vec<Val>) -> Result<Self, ()> {
         Ok(())
     }
+
+    fn col_types() -> Vec<ColType> {
+        vec![]
+    }
 }
 
 macro_rules! impl_args {
@@ -30,6 +45,10 @@ macro_rules! impl_args {
                         )*
                     ))
                 }
+
+                fn col_types() -> Vec<ColType> {
+                    vec![$([< T $Ts >]::into_col_type(),)*]
+                }
             }
         }
     };
@@ -43,3 +62,30 @@ impl_args!(0, 1, 2, 3, 4);
 impl_args!(0, 1, 2, 3, 4, 5);
 impl_args!(0, 1, 2, 3, 4, 5, 6);
 impl_args!(0, 1, 2, 3, 4, 5, 6, 7);
+
+macro_rules! impl_row {
+    ($($Ts:tt),*) => {
+        paste::item! {
+            impl<$([< T $Ts >],)*> Row for ($([< T $Ts >],)*)
+            where
+                $(
+                    [< T $Ts >]: IntoColType + TryFrom<Val, Error = ()> + Into<Val>,
+                    [< T $Ts >]: Clone + Send + Sync + 'static,
+                )*
+            {
+                fn into_vals(self) -> Vec<Val> {
+                    vec![$(self.$Ts.into(),)*]
+                }
+            }
+        }
+    };
+}
+
+impl_row!(0);
+impl_row!(0, 1);
+impl_row!(0, 1, 2);
+impl_row!(0, 1, 2, 3);
+impl_row!(0, 1, 2, 3, 4);
+impl_row!(0, 1, 2, 3, 4, 5);
+impl_row!(0, 1, 2, 3, 4, 5, 6);
+impl_row!(0, 1, 2, 3, 4, 5, 6, 7);
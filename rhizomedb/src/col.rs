@@ -1,21 +1,49 @@
-use crate::{id::ColId, types::ColType};
+use anyhow::Result;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+use crate::{id::ColId, types::ColType, value::Val};
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Col {
     id: ColId,
     col_type: ColType,
+    is_optional: bool,
 }
 
 impl Col {
     pub fn new(id: ColId, col_type: ColType) -> Self {
-        Self { id, col_type }
+        Self {
+            id,
+            col_type,
+            is_optional: false,
+        }
+    }
+
+    /// A column that may hold null, in place of a value of its type.
+    pub fn optional(id: ColId, col_type: ColType) -> Self {
+        Self {
+            id,
+            col_type,
+            is_optional: true,
+        }
     }
 
     pub fn id(&self) -> ColId {
-        self.id
+        self.id.clone()
     }
 
     pub fn col_type(&self) -> &ColType {
         &self.col_type
     }
+
+    pub fn is_optional(&self) -> bool {
+        self.is_optional
+    }
+
+    pub fn check(&self, value: &Val) -> Result<()> {
+        if self.is_optional && *value == Val::Null {
+            return Ok(());
+        }
+
+        self.col_type.check(value)
+    }
 }
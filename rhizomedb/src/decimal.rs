@@ -0,0 +1,332 @@
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display},
+    ops::{Add, AddAssign, Div, Mul, Neg, Sub},
+    str::FromStr,
+};
+
+use num_traits::{One, Zero};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A fixed-point decimal number, represented as an integer mantissa scaled by a
+/// power of ten. Decimals are kept normalized, without trailing zeros, so that
+/// equal numbers have equal representations.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u8,
+}
+
+impl Decimal {
+    /// The largest number of digits following the decimal point.
+    pub const MAX_SCALE: u8 = 38;
+
+    pub const ZERO: Self = Self {
+        mantissa: 0,
+        scale: 0,
+    };
+
+    pub const ONE: Self = Self {
+        mantissa: 1,
+        scale: 0,
+    };
+
+    // The number of fractional digits to compute when dividing
+    const DIV_SCALE: u8 = 18;
+
+    /// Returns `mantissa * 10^-scale`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scale exceeds [`Decimal::MAX_SCALE`].
+    pub fn new(mantissa: i128, scale: u8) -> Self {
+        assert!(scale <= Self::MAX_SCALE, "decimal scale out of range");
+
+        let mut result = Self { mantissa, scale };
+
+        while result.scale > 0 && result.mantissa % 10 == 0 {
+            result.mantissa /= 10;
+            result.scale -= 1;
+        }
+
+        result
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let lhs = self.rescale(scale)?;
+        let rhs = other.rescale(scale)?;
+
+        Some(Self::new(lhs.checked_add(rhs)?, scale))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.checked_add(Self::new(other.mantissa.checked_neg()?, other.scale))
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        let mut mantissa = self.mantissa.checked_mul(other.mantissa)?;
+        let mut scale = self.scale + other.scale;
+
+        while scale > Self::MAX_SCALE {
+            mantissa /= 10;
+            scale -= 1;
+        }
+
+        Some(Self::new(mantissa, scale))
+    }
+
+    /// Divides, truncating the quotient to at most 18 fractional digits, or
+    /// fewer if the dividend can't be scaled that far.
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        if other.mantissa == 0 {
+            return None;
+        }
+
+        let target = self.scale.max(other.scale).saturating_add(Self::DIV_SCALE);
+
+        for scale in (0..=target.min(Self::MAX_SCALE)).rev() {
+            let shift = i32::from(scale) + i32::from(other.scale) - i32::from(self.scale);
+
+            let (numerator, denominator) = if shift >= 0 {
+                (
+                    10_i128
+                        .checked_pow(shift as u32)
+                        .and_then(|unit| self.mantissa.checked_mul(unit)),
+                    Some(other.mantissa),
+                )
+            } else {
+                (
+                    Some(self.mantissa),
+                    other.mantissa.checked_mul(10_i128.pow(-shift as u32)),
+                )
+            };
+
+            if let (Some(numerator), Some(denominator)) = (numerator, denominator) {
+                return Some(Self::new(numerator / denominator, scale));
+            }
+        }
+
+        None
+    }
+
+    fn rescale(&self, scale: u8) -> Option<i128> {
+        self.mantissa
+            .checked_mul(10_i128.checked_pow(u32::from(scale - self.scale))?)
+    }
+
+    fn split(&self) -> (i128, i128) {
+        let unit = 10_i128.pow(u32::from(self.scale));
+
+        (self.mantissa / unit, self.mantissa % unit)
+    }
+}
+
+impl Default for Decimal {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (lhs_int, lhs_frac) = self.split();
+        let (rhs_int, rhs_frac) = other.split();
+
+        // Fractional parts are below 10^scale, so rescaling them can't overflow
+        let scale = self.scale.max(other.scale);
+        let lhs_frac = lhs_frac * 10_i128.pow(u32::from(scale - self.scale));
+        let rhs_frac = rhs_frac * 10_i128.pow(u32::from(scale - other.scale));
+
+        lhs_int.cmp(&rhs_int).then(lhs_frac.cmp(&rhs_frac))
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for Decimal {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.checked_add(other)
+            .expect("attempt to add with overflow")
+    }
+}
+
+impl AddAssign for Decimal {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Decimal {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self.checked_sub(other)
+            .expect("attempt to subtract with overflow")
+    }
+}
+
+impl Mul for Decimal {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        self.checked_mul(other)
+            .expect("attempt to multiply with overflow")
+    }
+}
+
+impl Div for Decimal {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        self.checked_div(other)
+            .expect("attempt to divide by zero or with overflow")
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.mantissa, self.scale)
+    }
+}
+
+impl Zero for Decimal {
+    fn zero() -> Self {
+        Self::ZERO
+    }
+
+    fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+}
+
+impl One for Decimal {
+    fn one() -> Self {
+        Self::ONE
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        Self::new(value.into(), 0)
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        let digits = int.trim_start_matches(['-', '+']);
+
+        if digits.is_empty() && frac.is_empty()
+            || !digits
+                .chars()
+                .chain(frac.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(());
+        }
+
+        let scale = u8::try_from(frac.len()).map_err(|_| ())?;
+
+        if scale > Self::MAX_SCALE {
+            return Err(());
+        }
+
+        let mantissa = format!("{int}{frac}").parse::<i128>().map_err(|_| ())?;
+
+        Ok(Self::new(mantissa, scale))
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let scale = usize::from(self.scale);
+
+        if self.mantissa < 0 {
+            f.write_str("-")?;
+        }
+
+        if scale == 0 {
+            return f.write_str(&digits);
+        }
+
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (int, frac) = digits.split_at(digits.len() - scale);
+
+        f.write_fmt(format_args!("{int}.{frac}"))
+    }
+}
+
+/// Decimals are encoded as strings, so that they survive the round trip through
+/// formats without fixed-point numbers.
+impl Serialize for Decimal {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        s.parse()
+            .map_err(|_| de::Error::invalid_value(de::Unexpected::Str(&s), &"a decimal"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Decimal;
+
+    #[test]
+    fn test_decimal() {
+        let a: Decimal = "12.50".parse().unwrap();
+        let b: Decimal = "-0.25".parse().unwrap();
+
+        assert_eq!(Decimal::new(125, 1), a);
+        assert_eq!("12.5", a.to_string());
+        assert_eq!("-0.25", b.to_string());
+        assert_eq!("12.25", (a + b).to_string());
+        assert_eq!("12.75", (a - b).to_string());
+        assert_eq!("-3.125", (a * b).to_string());
+        assert_eq!("-50", (a / b).to_string());
+        assert_eq!(
+            "0.333333333333333333",
+            (Decimal::ONE / Decimal::from(3)).to_string()
+        );
+
+        assert!(b < Decimal::ZERO);
+        assert!(a > Decimal::from(12));
+        assert!(Decimal::new(-15, 1) < Decimal::new(-14, 1));
+        assert!(Decimal::new(1, 38) > Decimal::ZERO);
+
+        assert!("1.2.3".parse::<Decimal>().is_err());
+        assert!("-".parse::<Decimal>().is_err());
+        assert_eq!(None, a.checked_div(Decimal::ZERO));
+    }
+}
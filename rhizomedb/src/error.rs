@@ -1,5 +1,7 @@
 //! Rhizome errors
 
+use std::time::Duration;
+
 use anyhow::Result;
 use thiserror::Error;
 
@@ -46,8 +48,35 @@ pub enum Error {
     AggregationUnboundGroupBy(VarId, ColId, RelationId),
     #[error("Attempted to aggregate into a bound variable {0}")]
     AggregationBoundTarget(VarId),
+    #[error("Conflicting types inferred for variable {0}: {1} and {2}")]
+    VarTypeConflict(VarId, Type, Type),
+    #[error("Variable {2} may be null, but is bound to non-optional column {1} of relation {0}")]
+    NullableColumnBinding(RelationId, ColId, VarId),
+    #[error("Column {1} of relation {0} can't both determine and be chosen by a choice")]
+    ConflictingChoiceColumn(RelationId, ColId),
     #[error("Attempted to bind to CID of IDB relation {0}")]
     ContentAddressedIDB(RelationId),
+    #[error("Demand must be on an output relation: {0}")]
+    DemandEDB(RelationId),
+    #[error("Sink must be on an output relation that isn't pruned: {0}")]
+    UnrecognizedSink(RelationId),
+    #[error("Fixpoint not reached within {0} iterations")]
+    IterationLimitExceeded(usize),
+    #[error("Relation {0} exceeded the limit of {1} tuples")]
+    RelationSizeLimitExceeded(RelationId, usize),
+    #[error("Epoch exceeded the time limit of {0:?}")]
+    EpochTimeLimitExceeded(Duration),
+}
+
+impl Error {
+    pub fn is_limit_exceeded(&self) -> bool {
+        matches!(
+            self,
+            Error::IterationLimitExceeded(_)
+                | Error::RelationSizeLimitExceeded(_, _)
+                | Error::EpochTimeLimitExceeded(_)
+        )
+    }
 }
 
 pub fn error<T>(err: impl std::error::Error + Send + Sync + 'static) -> Result<T> {
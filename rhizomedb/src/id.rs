@@ -2,21 +2,21 @@ use core::fmt::{self, Debug};
 use serde::{Deserialize, Serialize};
 use std::{borrow::Borrow, fmt::Display, marker::PhantomData};
 
-use crate::interner::Symbol;
+use crate::interner::{HashedSymbol, Name, Symbol};
 
 #[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 #[serde(transparent)]
-pub struct Id<T, U>(Symbol, PhantomData<(T, U)>);
+pub struct Id<T, U, S = Symbol>(S, PhantomData<(T, U)>);
 
-impl<T, U> Debug for Id<T, U> {
+impl<T, U, S: Debug> Debug for Id<T, U, S> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         Debug::fmt(&self.0, f)
     }
 }
 
-impl<T, U> Id<T, U> {
-    pub fn new<S: AsRef<str>>(id: S) -> Self {
-        let symbol = Symbol::get_or_intern(id.as_ref());
+impl<T, U, S: Name> Id<T, U, S> {
+    pub fn new<N: AsRef<str>>(id: N) -> Self {
+        let symbol = S::new(id.as_ref());
 
         Self(symbol, PhantomData)
     }
@@ -26,17 +26,18 @@ impl<T, U> Id<T, U> {
     }
 }
 
-impl<T, U> Display for Id<T, U> {
+impl<T, U, S: Name> Display for Id<T, U, S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.resolve())
     }
 }
 
-impl<T, U, S> From<S> for Id<T, U>
+impl<T, U, S, N> From<N> for Id<T, U, S>
 where
-    S: Borrow<str>,
+    S: Name,
+    N: Borrow<str>,
 {
-    fn from(id: S) -> Self {
+    fn from(id: N) -> Self {
         Self::new(id.borrow())
     }
 }
@@ -64,4 +65,10 @@ macro_rules! new_id {
 
 new_id!(ColId);
 new_id!(RelationId);
-new_id!(VarId);
+
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub enum VarIdMarker {}
+
+/// Variables are copied into rules by value, so their ids hash long names rather than
+/// reference counting them.
+pub(crate) type VarId = Id<VarIdMarker, (), HashedSymbol>;
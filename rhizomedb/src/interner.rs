@@ -1,43 +1,102 @@
 use anyhow::Result;
-use once_cell::sync::Lazy;
 use serde::{
     de::{self, Visitor},
     Deserialize, Serialize,
 };
 use std::{
+    cmp::Ordering,
+    collections::hash_map::DefaultHasher,
     fmt::{self, Debug},
-    sync::Mutex,
+    hash::{Hash, Hasher},
+    sync::Arc,
 };
-use string_interner::{DefaultSymbol, StringInterner};
 
-static INSTANCE: Lazy<Mutex<StringInterner>> = Lazy::new(|| Mutex::new(StringInterner::default()));
+/// The longest name stored inline in a symbol. Shorter names, which cover nearly
+/// every relation, column and variable, are never allocated.
+const INLINE: usize = 22;
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub(crate) struct Symbol(DefaultSymbol);
+/// The number of leading bytes of a long name that a hashed symbol keeps for display.
+const PREFIX: usize = 6;
+
+/// A way of storing the name of an id.
+pub trait Name: Sized {
+    fn new(s: &str) -> Self;
+
+    fn resolve(&self) -> String;
+}
+
+/// A name. Names of up to `INLINE` bytes are stored in the symbol itself, and longer
+/// names are reference counted, so that several databases in one process share
+/// nothing, and a name is freed along with the last symbol holding it.
+#[derive(Clone)]
+pub struct Symbol(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Inline { len: u8, bytes: [u8; INLINE] },
+    Long(Arc<str>),
+}
+
+impl Name for Symbol {
+    fn new(s: &str) -> Symbol {
+        match inline(s) {
+            Some((len, bytes)) => Self(Repr::Inline { len, bytes }),
+            None => Self(Repr::Long(Arc::from(s))),
+        }
+    }
+
+    fn resolve(&self) -> String {
+        self.as_str().to_string()
+    }
+}
 
 impl Symbol {
-    pub(crate) fn get_or_intern(s: &str) -> Symbol {
-        let symbol = INSTANCE
-            .lock()
-            .expect("interner lock poisoned")
-            .get_or_intern(s);
+    fn as_str(&self) -> &str {
+        match &self.0 {
+            Repr::Inline { len, bytes } => {
+                std::str::from_utf8(&bytes[..*len as usize]).expect("symbols are valid UTF-8")
+            }
+            Repr::Long(s) => s,
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        self.as_str().as_bytes()
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Repr::Long(a), Repr::Long(b)) if Arc::ptr_eq(a, b) => true,
+            _ => self.as_bytes() == other.as_bytes(),
+        }
+    }
+}
+
+impl Eq for Symbol {}
 
-        Self(symbol)
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state);
     }
+}
 
-    pub(crate) fn resolve(&self) -> String {
-        INSTANCE
-            .lock()
-            .expect("interner lock poisoned")
-            .resolve(self.0)
-            .expect("symbol not found")
-            .to_string()
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
 impl Debug for Symbol {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(self.resolve().as_str())
+        f.write_str(self.as_str())
     }
 }
 
@@ -46,7 +105,7 @@ impl Serialize for Symbol {
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(self.resolve().as_str())
+        serializer.serialize_str(self.as_str())
     }
 }
 
@@ -73,6 +132,228 @@ impl<'de> Visitor<'de> for SymbolVisitor {
     where
         E: de::Error,
     {
-        Ok(Symbol::get_or_intern(value))
+        Ok(Symbol::new(value))
+    }
+}
+
+/// A name that's `Copy`, for variables, which rules take by value and which are
+/// never serialized. Names too long to store inline are kept as their first bytes,
+/// for display, and a 128-bit hash of the whole name that tells them apart.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct HashedSymbol(HashedRepr);
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+enum HashedRepr {
+    Inline {
+        len: u8,
+        bytes: [u8; INLINE],
+    },
+    Hashed {
+        len: u8,
+        prefix: [u8; PREFIX],
+        hash: [u64; 2],
+    },
+}
+
+impl Name for HashedSymbol {
+    fn new(s: &str) -> Self {
+        if let Some((len, bytes)) = inline(s) {
+            return Self(HashedRepr::Inline { len, bytes });
+        }
+
+        let len = (0..=PREFIX)
+            .rev()
+            .find(|len| s.is_char_boundary(*len))
+            .unwrap_or_default();
+
+        let mut prefix = [0; PREFIX];
+        prefix[..len].copy_from_slice(&s.as_bytes()[..len]);
+
+        let hash = [0_u8, 1].map(|seed| {
+            let mut hasher = DefaultHasher::new();
+            seed.hash(&mut hasher);
+            s.hash(&mut hasher);
+            hasher.finish()
+        });
+
+        Self(HashedRepr::Hashed {
+            len: len as u8,
+            prefix,
+            hash,
+        })
+    }
+
+    fn resolve(&self) -> String {
+        match &self.0 {
+            HashedRepr::Inline { len, bytes } => std::str::from_utf8(&bytes[..*len as usize])
+                .expect("symbols are valid UTF-8")
+                .to_string(),
+            HashedRepr::Hashed { len, prefix, hash } => format!(
+                "{}#{:016x}",
+                std::str::from_utf8(&prefix[..*len as usize]).expect("symbols are valid UTF-8"),
+                hash[0]
+            ),
+        }
+    }
+}
+
+impl Debug for HashedSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.resolve())
+    }
+}
+
+fn inline(s: &str) -> Option<(u8, [u8; INLINE])> {
+    if s.len() > INLINE {
+        return None;
+    }
+
+    let mut bytes = [0; INLINE];
+    bytes[..s.len()].copy_from_slice(s.as_bytes());
+
+    Some((s.len() as u8, bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, sync::Arc, thread};
+
+    use anyhow::Result;
+
+    use crate::{
+        build, id::RelationId, runtime::vm::VM, storage::memory::MemoryBlockstore, tuple::Tuple,
+    };
+
+    use super::{HashedSymbol, Name, Repr, Symbol};
+
+    #[test]
+    fn test_intern_symbols() {
+        let symbols = thread::scope(|s| {
+            let handles = (0..4)
+                .map(|_| s.spawn(|| Symbol::new("edge")))
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        assert!(symbols.iter().all(|symbol| *symbol == symbols[0]));
+        assert_ne!(symbols[0], Symbol::new("path"));
+        assert!(Symbol::new("edge") < Symbol::new("path"));
+        assert_eq!("edge", symbols[0].resolve());
+    }
+
+    #[test]
+    fn test_symbol_round_trip() {
+        let id = RelationId::new("round_trip");
+
+        let bytes = serde_ipld_dagcbor::to_vec(&id).unwrap();
+        let decoded: RelationId = serde_ipld_dagcbor::from_slice(&bytes).unwrap();
+
+        assert_eq!(id, decoded);
+        assert_eq!("round_trip", decoded.resolve());
+    }
+
+    #[test]
+    fn test_long_symbols() {
+        let name = "a_relation_name_too_long_to_store_inline";
+
+        assert_eq!(Symbol::new(name), Symbol::new(name));
+        assert_ne!(Symbol::new(name), Symbol::new("a_relation"));
+        assert!(Symbol::new("a_relation") < Symbol::new(name));
+        assert_eq!(name, Symbol::new(name).resolve());
+
+        let symbol = Symbol::new(name);
+        let Repr::Long(long) = &symbol.0 else {
+            panic!("expected {name} to be stored out of line");
+        };
+        let weak = Arc::downgrade(long);
+
+        let cloned = symbol.clone();
+        drop(symbol);
+        assert!(weak.upgrade().is_some());
+
+        drop(cloned);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_long_hashed_symbols() {
+        let name = "a_variable_name_too_long_to_store_inline";
+
+        assert_eq!(HashedSymbol::new(name), HashedSymbol::new(name));
+        assert_ne!(
+            HashedSymbol::new(name),
+            HashedSymbol::new("a_variable_name_too_long_to_store_inline_2")
+        );
+        assert_eq!("edge", HashedSymbol::new("edge").resolve());
+        assert!(HashedSymbol::new(name).resolve().starts_with("a_vari#"));
+    }
+
+    #[test]
+    fn test_independent_databases() -> Result<()> {
+        let run = |db: usize| -> Result<BTreeSet<Tuple>> {
+            let edge = format!("database_{db}_edge_relation");
+            let path = format!("database_{db}_path_relation");
+
+            let program = build(|p| {
+                p.input(&edge, |h| h.column::<i32>("from").column::<i32>("to"))?;
+                p.output(&path, |h| h.column::<i32>("from").column::<i32>("to"))?;
+
+                p.rule::<(i32, i32)>(&path, &|h, b, (x, y)| {
+                    h.bind((("from", x), ("to", y)))?;
+                    b.search(&edge, (("from", x), ("to", y)))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            })?;
+
+            let bs = MemoryBlockstore::default();
+            let mut vm = <VM>::new(program);
+
+            vm.push(Tuple::new(
+                edge.as_str(),
+                [("from", 0), ("to", db as i32)],
+                None,
+            ))?;
+            vm.step_epoch(&bs)?;
+
+            let mut actual = BTreeSet::default();
+            while let Some(tuple) = vm.pop()? {
+                actual.insert(tuple);
+            }
+
+            Ok(actual)
+        };
+
+        let derived = thread::scope(|s| {
+            let handles = (0..2)
+                .map(|db| s.spawn(move || run(db)))
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        for (db, actual) in derived.into_iter().enumerate() {
+            let path = format!("database_{db}_path_relation");
+
+            assert_eq!(
+                BTreeSet::from_iter([Tuple::new(
+                    path.as_str(),
+                    [("from", 0), ("to", db as i32)],
+                    None
+                )]),
+                actual
+            );
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use crate::{
+    predicate::{PredicateWhere, PredicateWrapper},
+    types::IntoColType,
+    value::{List, Map, Val},
+    var::TypedVar,
+};
+
+use super::when;
+
+/// Holds when the list contains the value.
+pub fn contains<T>(
+    list: TypedVar<List>,
+    val: TypedVar<T>,
+) -> impl PredicateWhere<(List, T), Predicate = impl PredicateWrapper>
+where
+    T: IntoColType + TryFrom<Val, Error = ()> + Send + Sync + 'static,
+    Val: From<T>,
+{
+    when((list, val), |(list, val): (List, T)| {
+        list.contains(&Val::from(val))
+    })
+}
+
+/// Holds when the value is at the given index of the list.
+pub fn nth<T>(
+    list: TypedVar<List>,
+    idx: usize,
+    val: TypedVar<T>,
+) -> impl PredicateWhere<(List, T), Predicate = impl PredicateWrapper>
+where
+    T: IntoColType + TryFrom<Val, Error = ()> + Send + Sync + 'static,
+    Val: From<T>,
+{
+    when((list, val), move |(list, val): (List, T)| {
+        list.get(idx) == Some(&Val::from(val))
+    })
+}
+
+/// Holds when the map has an entry for the key.
+pub fn has_key(
+    map: TypedVar<Map>,
+    key: &str,
+) -> impl PredicateWhere<(Map,), Predicate = impl PredicateWrapper> {
+    let key: Arc<str> = Arc::from(key);
+
+    when((map,), move |(map,): (Map,)| map.contains_key(&key))
+}
+
+/// Holds when the value is the map's entry for the key.
+pub fn get<T>(
+    map: TypedVar<Map>,
+    key: &str,
+    val: TypedVar<T>,
+) -> impl PredicateWhere<(Map, T), Predicate = impl PredicateWrapper>
+where
+    T: IntoColType + TryFrom<Val, Error = ()> + Send + Sync + 'static,
+    Val: From<T>,
+{
+    let key: Arc<str> = Arc::from(key);
+
+    when((map, val), move |(map, val): (Map, T)| {
+        map.get(&key) == Some(&Val::from(val))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use crate::{
+        assert_derives,
+        kernel::collection,
+        tuple::Tuple,
+        value::{List, Map, Val},
+    };
+
+    #[test]
+    fn test_collections() {
+        assert_derives!(
+            |p| {
+                p.output("doc", |h| {
+                    h.column::<i32>("id")
+                        .column::<List>("tags")
+                        .column::<Map>("meta")
+                })?;
+                p.output("wanted", |h| h.column::<i32>("tag"))?;
+                p.output("tagged", |h| h.column::<i32>("id"))?;
+                p.output("second", |h| h.column::<i32>("id"))?;
+                p.output("authored", |h| h.column::<i32>("id"))?;
+                p.output("author", |h| h.column::<&str>("name"))?;
+                p.output("by", |h| h.column::<i32>("id"))?;
+
+                let author = BTreeMap::from([(Arc::from("author"), Val::from("ann"))]);
+
+                p.fact("doc", |f| {
+                    f.bind((
+                        ("id", 1),
+                        ("tags", vec![Val::from(1), Val::from(2)]),
+                        ("meta", author),
+                    ))
+                })?;
+                p.fact("doc", |f| {
+                    f.bind((
+                        ("id", 2),
+                        ("tags", vec![Val::from(2), Val::from(3)]),
+                        ("meta", BTreeMap::default()),
+                    ))
+                })?;
+                p.fact("wanted", |f| f.bind((("tag", 3),)))?;
+                p.fact("author", |f| f.bind((("name", "ann"),)))?;
+
+                p.rule::<(i32, List, i32)>("tagged", &|h, b, (id, tags, tag)| {
+                    h.bind((("id", id),))?;
+
+                    b.search("doc", (("id", id), ("tags", tags)))?;
+                    b.search("wanted", (("tag", tag),))?;
+                    b.predicate(collection::contains(tags, tag))?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(i32, List, i32)>("second", &|h, b, (id, tags, tag)| {
+                    h.bind((("id", id),))?;
+
+                    b.search("doc", (("id", id), ("tags", tags)))?;
+                    b.search("wanted", (("tag", tag),))?;
+                    b.predicate(collection::nth(tags, 1, tag))?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(i32, Map)>("authored", &|h, b, (id, meta)| {
+                    h.bind((("id", id),))?;
+
+                    b.search("doc", (("id", id), ("meta", meta)))?;
+                    b.predicate(collection::has_key(meta, "author"))?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(i32, Map, Arc<str>)>("by", &|h, b, (id, meta, name)| {
+                    h.bind((("id", id),))?;
+
+                    b.search("doc", (("id", id), ("meta", meta)))?;
+                    b.search("author", (("name", name),))?;
+                    b.predicate(collection::get(meta, "author", name))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [
+                ("tagged", vec![Tuple::new("tagged", [("id", 2)], None)]),
+                ("second", vec![Tuple::new("second", [("id", 2)], None)]),
+                ("authored", vec![Tuple::new("authored", [("id", 1)], None)]),
+                ("by", vec![Tuple::new("by", [("id", 1)], None)]),
+            ]
+        );
+    }
+}
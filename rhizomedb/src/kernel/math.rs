@@ -1,11 +1,27 @@
+mod any;
+mod collect;
 mod count;
+mod count_distinct;
 mod max;
 mod mean;
+mod median;
 mod min;
+mod percentile;
+mod select;
+mod string_agg;
 mod sum;
+mod variance;
 
+pub use any::*;
+pub use collect::*;
 pub use count::*;
+pub use count_distinct::*;
 pub use max::*;
 pub use mean::*;
+pub use median::*;
 pub use min::*;
+pub use percentile::*;
+pub use select::*;
+pub use string_agg::*;
 pub use sum::*;
+pub use variance::*;
@@ -0,0 +1,59 @@
+use rhizomedb_macro::rhizome_fn;
+
+use crate::aggregation::Aggregate;
+
+rhizome_fn! {
+    /// Whether any value in each group is true.
+    #[aggregate = AnyOf]
+    fn any(arg: bool) -> bool;
+}
+
+rhizome_fn! {
+    /// Whether every value in each group is true.
+    #[aggregate = AllOf]
+    fn all(arg: bool) -> bool;
+}
+
+#[derive(Debug)]
+pub struct AnyOf<T>(T);
+
+impl Default for AnyOf<bool> {
+    fn default() -> Self {
+        Self(false)
+    }
+}
+
+impl Aggregate for AnyOf<bool> {
+    type Input = (bool,);
+    type Output = bool;
+
+    fn step(&mut self, (t,): (bool,)) {
+        self.0 |= t;
+    }
+
+    fn finalize(&self) -> Option<Self::Output> {
+        Some(self.0)
+    }
+}
+
+#[derive(Debug)]
+pub struct AllOf<T>(T);
+
+impl Default for AllOf<bool> {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+impl Aggregate for AllOf<bool> {
+    type Input = (bool,);
+    type Output = bool;
+
+    fn step(&mut self, (t,): (bool,)) {
+        self.0 &= t;
+    }
+
+    fn finalize(&self) -> Option<Self::Output> {
+        Some(self.0)
+    }
+}
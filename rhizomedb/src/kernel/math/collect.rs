@@ -0,0 +1,83 @@
+use std::{collections::BTreeSet, marker::PhantomData};
+
+use rhizomedb_macro::rhizome_fn;
+
+use crate::{
+    aggregation::Aggregate,
+    types::RhizomeType,
+    value::{List, Val},
+};
+
+rhizome_fn! {
+    /// All of the values in each group, including duplicates, in sorted order.
+    #[aggregate = CollectList::<T>]
+    fn collect_list<T: RhizomeType + Into<Val>>(arg: T) -> List;
+}
+
+rhizome_fn! {
+    /// The distinct values in each group, in sorted order.
+    #[aggregate = CollectSet::<T>]
+    fn collect_set<T: RhizomeType + Into<Val>>(arg: T) -> List;
+}
+
+// Groups are stepped through in whatever order their tuples were found, so the
+// values are sorted to keep the output, and any CIDs derived from it, stable.
+
+#[derive(Debug)]
+pub struct CollectList<T>(Vec<Val>, PhantomData<T>);
+
+impl<T> Default for CollectList<T>
+where
+    T: RhizomeType + Into<Val>,
+{
+    fn default() -> Self {
+        Self(Vec::default(), PhantomData)
+    }
+}
+
+impl<T> Aggregate for CollectList<T>
+where
+    T: RhizomeType + Into<Val>,
+{
+    type Input = (T,);
+    type Output = List;
+
+    fn step(&mut self, (t,): (T,)) {
+        self.0.push(t.into());
+    }
+
+    fn finalize(&self) -> Option<Self::Output> {
+        let mut values = self.0.clone();
+        values.sort();
+
+        Some(List::from(values))
+    }
+}
+
+#[derive(Debug)]
+pub struct CollectSet<T>(BTreeSet<Val>, PhantomData<T>);
+
+impl<T> Default for CollectSet<T>
+where
+    T: RhizomeType + Into<Val>,
+{
+    fn default() -> Self {
+        Self(BTreeSet::default(), PhantomData)
+    }
+}
+
+impl<T> Aggregate for CollectSet<T>
+where
+    T: RhizomeType + Into<Val>,
+{
+    type Input = (T,);
+    type Output = List;
+
+    fn step(&mut self, (t,): (T,)) {
+        self.0.insert(t.into());
+    }
+
+    fn finalize(&self) -> Option<Self::Output> {
+        Some(self.0.iter().cloned().collect())
+    }
+}
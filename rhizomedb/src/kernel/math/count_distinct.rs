@@ -0,0 +1,50 @@
+use std::{collections::BTreeSet, ops::AddAssign};
+
+use num_traits::{One, Zero};
+use rhizomedb_macro::rhizome_fn;
+
+use crate::{aggregation::Aggregate, types::RhizomeType};
+
+rhizome_fn! {
+    #[aggregate = CountDistinct::<T, C>]
+    fn count_distinct<
+        T: RhizomeType + Ord,
+        C: RhizomeType + AddAssign + One + Zero
+    >(arg: T) -> C;
+}
+
+#[derive(Debug)]
+pub struct CountDistinct<T, C>(BTreeSet<T>, std::marker::PhantomData<C>);
+
+impl<T, C> Default for CountDistinct<T, C>
+where
+    T: RhizomeType + Ord,
+    C: RhizomeType + AddAssign + One + Zero,
+{
+    fn default() -> Self {
+        Self(BTreeSet::default(), std::marker::PhantomData)
+    }
+}
+
+impl<T, C> Aggregate for CountDistinct<T, C>
+where
+    T: RhizomeType + Ord,
+    C: RhizomeType + AddAssign + One + Zero,
+{
+    type Input = (T,);
+    type Output = C;
+
+    fn step(&mut self, (t,): (T,)) {
+        self.0.insert(t);
+    }
+
+    fn finalize(&self) -> Option<Self::Output> {
+        let mut count = C::zero();
+
+        for _ in &self.0 {
+            count += C::one();
+        }
+
+        Some(count)
+    }
+}
@@ -1,42 +1,46 @@
-use std::ops::{AddAssign, Div};
-
 use num_traits::{One, Zero};
 use rhizomedb_macro::rhizome_fn;
 
 use crate::{aggregation::Aggregate, types::RhizomeType};
 
+use super::sum::Checked;
+
 rhizome_fn! {
+    /// The mean of each group, or no result if its sum overflows.
     #[aggregate = Mean]
-    fn mean<
-        T: RhizomeType + AddAssign + Zero + One + Div<Output = T>
-    >(arg: T) -> <T as Div>::Output;
+    fn mean<T: RhizomeType + Checked + Zero + One>(arg: T) -> T;
 }
 
+/// The running sum and count, which are `None` once either has overflowed.
 #[derive(Debug)]
-pub struct Mean<T>(T, T);
+pub struct Mean<T>(Option<(T, T)>);
 
 impl<T> Default for Mean<T>
 where
-    T: RhizomeType + AddAssign + Zero + One + Div<Output = T>,
+    T: RhizomeType + Checked + Zero + One,
 {
     fn default() -> Self {
-        Self(Zero::zero(), Zero::zero())
+        Self(Some((Zero::zero(), Zero::zero())))
     }
 }
 
 impl<T> Aggregate for Mean<T>
 where
-    T: RhizomeType + AddAssign + Zero + One + Div<Output = T>,
+    T: RhizomeType + Checked + Zero + One,
 {
     type Input = (T,);
-    type Output = <T as Div>::Output;
+    type Output = T;
 
     fn step(&mut self, (t,): (T,)) {
-        self.0 += t;
-        self.1 += One::one();
+        self.0 = self
+            .0
+            .take()
+            .and_then(|(sum, count)| Some((sum.checked_add(t)?, count.checked_add(One::one())?)));
     }
 
     fn finalize(&self) -> Option<Self::Output> {
-        Some(self.0.clone() / self.1.clone())
+        let (sum, count) = self.0.clone()?;
+
+        sum.checked_div(count)
     }
 }
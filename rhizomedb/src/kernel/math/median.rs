@@ -0,0 +1,40 @@
+use rhizomedb_macro::rhizome_fn;
+
+use crate::{aggregation::Aggregate, types::RhizomeType, value::Val};
+
+use super::percentile::nearest_rank;
+
+rhizome_fn! {
+    /// The middle value of each group, or the lower of the two middle values when
+    /// the group has an even number of values.
+    #[aggregate = Median]
+    fn median<T: RhizomeType + PartialOrd + Into<Val>>(arg: T) -> T;
+}
+
+#[derive(Debug)]
+pub struct Median<T>(Vec<T>);
+
+impl<T> Default for Median<T>
+where
+    T: RhizomeType + PartialOrd + Into<Val>,
+{
+    fn default() -> Self {
+        Self(Vec::default())
+    }
+}
+
+impl<T> Aggregate for Median<T>
+where
+    T: RhizomeType + PartialOrd + Into<Val>,
+{
+    type Input = (T,);
+    type Output = T;
+
+    fn step(&mut self, (t,): (T,)) {
+        self.0.push(t);
+    }
+
+    fn finalize(&self) -> Option<Self::Output> {
+        nearest_rank(&self.0, 0.5)
+    }
+}
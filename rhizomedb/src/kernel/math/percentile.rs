@@ -0,0 +1,102 @@
+use std::marker::PhantomData;
+
+use crate::{
+    aggregation::{Aggregate, AggregateGroupBy},
+    types::RhizomeType,
+    value::Val,
+    var::{TypedVar, Var},
+};
+
+/// The value at the given fraction of the way through each group, using the
+/// nearest-rank method. The fraction is clamped to `[0, 1]`.
+pub fn percentile<T>(arg: TypedVar<T>, fraction: f64) -> PercentileGroupBy<T>
+where
+    T: RhizomeType + PartialOrd + Into<Val>,
+{
+    PercentileGroupBy {
+        arg: arg.into(),
+        fraction,
+        _marker: PhantomData,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PercentileGroupBy<T> {
+    arg: Var,
+    fraction: f64,
+    _marker: PhantomData<T>,
+}
+
+impl<T> AggregateGroupBy<(T,), T> for PercentileGroupBy<T>
+where
+    T: RhizomeType + PartialOrd + Into<Val>,
+{
+    type Aggregate = Percentile<T>;
+
+    fn as_args(&self) -> Vec<Var> {
+        vec![self.arg]
+    }
+
+    fn aggregate(&self) -> Self::Aggregate {
+        Percentile {
+            fraction: self.fraction,
+            values: Vec::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Percentile<T> {
+    fraction: f64,
+    values: Vec<T>,
+}
+
+impl<T> Default for Percentile<T>
+where
+    T: RhizomeType + PartialOrd + Into<Val>,
+{
+    fn default() -> Self {
+        Self {
+            fraction: 0.5,
+            values: Vec::default(),
+        }
+    }
+}
+
+impl<T> Aggregate for Percentile<T>
+where
+    T: RhizomeType + PartialOrd + Into<Val>,
+{
+    type Input = (T,);
+    type Output = T;
+
+    fn init(&self) -> Self {
+        Self {
+            fraction: self.fraction,
+            values: Vec::default(),
+        }
+    }
+
+    fn step(&mut self, (t,): (T,)) {
+        self.values.push(t);
+    }
+
+    fn finalize(&self) -> Option<Self::Output> {
+        nearest_rank(&self.values, self.fraction)
+    }
+}
+
+pub(super) fn nearest_rank<T>(values: &[T], fraction: f64) -> Option<T>
+where
+    T: Clone + Into<Val>,
+{
+    // Sorted as values, which order floats totally, placing NaN above every other float
+    let mut sorted = values.iter().collect::<Vec<_>>();
+    sorted.sort_by_cached_key(|value| (*value).clone().into());
+
+    let rank = (fraction.clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize;
+
+    sorted
+        .get(rank.saturating_sub(1))
+        .map(|value| (*value).clone())
+}
@@ -0,0 +1,162 @@
+use std::{cmp::Reverse, marker::PhantomData};
+
+use crate::{
+    aggregation::{Aggregate, AggregateGroupBy, Keyed, Rows},
+    args::Row,
+    typed_vars::TypedVars,
+    types::RhizomeType,
+    value::Val,
+    var::{TypedVar, Var},
+};
+
+/// The values of the row with the greatest key in each group.
+pub fn arg_max<K, V, R>(key: TypedVar<K>, values: V) -> SelectGroupBy<K, R>
+where
+    K: RhizomeType + Ord + TryFrom<Val, Error = ()> + Send + Sync,
+    V: TypedVars<Args = R>,
+    R: Row,
+{
+    SelectGroupBy::new(key, values, Order::Descending, 1)
+}
+
+/// The values of the row with the least key in each group.
+pub fn arg_min<K, V, R>(key: TypedVar<K>, values: V) -> SelectGroupBy<K, R>
+where
+    K: RhizomeType + Ord + TryFrom<Val, Error = ()> + Send + Sync,
+    V: TypedVars<Args = R>,
+    R: Row,
+{
+    SelectGroupBy::new(key, values, Order::Ascending, 1)
+}
+
+/// The values of the `k` rows with the greatest keys in each group.
+pub fn top_k<K, V, R>(k: usize, key: TypedVar<K>, values: V) -> SelectGroupBy<K, R>
+where
+    K: RhizomeType + Ord + TryFrom<Val, Error = ()> + Send + Sync,
+    V: TypedVars<Args = R>,
+    R: Row,
+{
+    SelectGroupBy::new(key, values, Order::Descending, k)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    Ascending,
+    Descending,
+}
+
+#[derive(Debug, Clone)]
+pub struct SelectGroupBy<K, R> {
+    key: Var,
+    values: Vec<Var>,
+    order: Order,
+    limit: usize,
+    _marker: PhantomData<(K, R)>,
+}
+
+impl<K, R> SelectGroupBy<K, R>
+where
+    K: RhizomeType + Ord + TryFrom<Val, Error = ()> + Send + Sync,
+    R: Row,
+{
+    fn new<V>(key: TypedVar<K>, values: V, order: Order, limit: usize) -> Self
+    where
+        V: TypedVars<Args = R>,
+    {
+        Self {
+            key: key.into(),
+            values: values.vars(),
+            order,
+            limit,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K, R> AggregateGroupBy<Keyed<K, R>, Rows<R>> for SelectGroupBy<K, R>
+where
+    K: RhizomeType + Ord + TryFrom<Val, Error = ()> + Send + Sync,
+    R: Row,
+{
+    type Aggregate = Select<K, R>;
+
+    fn as_args(&self) -> Vec<Var> {
+        let mut args = vec![self.key];
+        args.extend(self.values.iter().copied());
+
+        args
+    }
+
+    fn aggregate(&self) -> Self::Aggregate {
+        Select {
+            order: self.order,
+            limit: self.limit,
+            rows: Vec::default(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Keeps the rows of a group ordered by their key, breaking ties by their values
+/// so that the same rows are selected regardless of the order they're found in.
+#[derive(Debug)]
+pub struct Select<K, R> {
+    order: Order,
+    limit: usize,
+    rows: Vec<(K, Vec<Val>)>,
+    _marker: PhantomData<R>,
+}
+
+impl<K, R> Default for Select<K, R>
+where
+    K: RhizomeType + Ord + TryFrom<Val, Error = ()> + Send + Sync,
+    R: Row,
+{
+    fn default() -> Self {
+        Self {
+            order: Order::Descending,
+            limit: 1,
+            rows: Vec::default(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K, R> Aggregate for Select<K, R>
+where
+    K: RhizomeType + Ord + TryFrom<Val, Error = ()> + Send + Sync,
+    R: Row,
+{
+    type Input = Keyed<K, R>;
+    type Output = Rows<R>;
+
+    fn init(&self) -> Self {
+        Self {
+            order: self.order,
+            limit: self.limit,
+            rows: Vec::default(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn step(&mut self, Keyed(key, row): Keyed<K, R>) {
+        self.rows.push((key, row.into_vals()));
+    }
+
+    fn finalize(&self) -> Option<Self::Output> {
+        let mut rows = self.rows.iter().collect::<Vec<_>>();
+
+        match self.order {
+            Order::Ascending => rows.sort(),
+            Order::Descending => rows.sort_by_key(|(key, vals)| (Reverse(key), vals)),
+        }
+
+        let selected = rows
+            .into_iter()
+            .take(self.limit)
+            .filter_map(|(_, vals)| R::instantiate(vals.clone()).ok())
+            .collect();
+
+        Some(Rows(selected))
+    }
+}
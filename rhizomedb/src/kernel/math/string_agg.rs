@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use crate::{
+    aggregation::{Aggregate, AggregateGroupBy},
+    var::{TypedVar, Var},
+};
+
+/// The strings in each group, in sorted order, joined by the separator.
+pub fn string_agg(arg: TypedVar<Arc<str>>, separator: &str) -> StringAggGroupBy {
+    StringAggGroupBy {
+        arg: arg.into(),
+        separator: Arc::from(separator),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StringAggGroupBy {
+    arg: Var,
+    separator: Arc<str>,
+}
+
+impl AggregateGroupBy<(Arc<str>,), Arc<str>> for StringAggGroupBy {
+    type Aggregate = StringAgg;
+
+    fn as_args(&self) -> Vec<Var> {
+        vec![self.arg]
+    }
+
+    fn aggregate(&self) -> Self::Aggregate {
+        StringAgg {
+            separator: self.separator.clone(),
+            values: Vec::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct StringAgg {
+    separator: Arc<str>,
+    values: Vec<Arc<str>>,
+}
+
+impl Default for StringAgg {
+    fn default() -> Self {
+        Self {
+            separator: Arc::from(","),
+            values: Vec::default(),
+        }
+    }
+}
+
+impl Aggregate for StringAgg {
+    type Input = (Arc<str>,);
+    type Output = Arc<str>;
+
+    fn init(&self) -> Self {
+        Self {
+            separator: self.separator.clone(),
+            values: Vec::default(),
+        }
+    }
+
+    fn step(&mut self, (t,): (Arc<str>,)) {
+        self.values.push(t);
+    }
+
+    fn finalize(&self) -> Option<Self::Output> {
+        let mut values = self.values.clone();
+        values.sort();
+
+        Some(Arc::from(values.join(&self.separator)))
+    }
+}
@@ -1,39 +1,75 @@
-use std::ops::AddAssign;
-
 use num_traits::Zero;
 use rhizomedb_macro::rhizome_fn;
 
-use crate::{aggregation::Aggregate, types::RhizomeType};
+use crate::{aggregation::Aggregate, decimal::Decimal, types::RhizomeType};
 
 rhizome_fn! {
+    /// The sum of each group, or no result if it overflows.
     #[aggregate = Sum]
-    fn sum<T: RhizomeType + AddAssign + Zero>(arg: T) -> T;
+    fn sum<T: RhizomeType + Checked + Zero>(arg: T) -> T;
+}
+
+/// Arithmetic that reports overflow, so that aggregates can fail rather than
+/// panic. Floats overflow to infinity instead.
+pub trait Checked: Sized {
+    fn checked_add(self, other: Self) -> Option<Self>;
+
+    fn checked_div(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_checked {
+    ($($t:ty),*) => {
+        $(
+            impl Checked for $t {
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    <$t>::checked_add(self, other)
+                }
+
+                fn checked_div(self, other: Self) -> Option<Self> {
+                    <$t>::checked_div(self, other)
+                }
+            }
+        )*
+    };
+}
+
+impl_checked!(i8, u8, i16, u16, i32, u32, i64, u64, i128, u128, Decimal);
+
+impl Checked for f64 {
+    fn checked_add(self, other: Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn checked_div(self, other: Self) -> Option<Self> {
+        Some(self / other)
+    }
 }
 
+/// The running sum, which is `None` once it has overflowed.
 #[derive(Debug)]
-pub struct Sum<T>(T);
+pub struct Sum<T>(Option<T>);
 
 impl<T> Default for Sum<T>
 where
-    T: RhizomeType + AddAssign + Zero,
+    T: RhizomeType + Checked + Zero,
 {
     fn default() -> Self {
-        Self(Zero::zero())
+        Self(Some(Zero::zero()))
     }
 }
 
 impl<T> Aggregate for Sum<T>
 where
-    T: RhizomeType + AddAssign + Zero,
+    T: RhizomeType + Checked + Zero,
 {
     type Input = (T,);
     type Output = T;
 
     fn step(&mut self, (t,): (T,)) {
-        self.0 += t;
+        self.0 = self.0.take().and_then(|sum| sum.checked_add(t));
     }
 
     fn finalize(&self) -> Option<Self::Output> {
-        Some(self.0.clone())
+        self.0.clone()
     }
 }
@@ -0,0 +1,94 @@
+use std::marker::PhantomData;
+
+use num_traits::ToPrimitive;
+use rhizomedb_macro::rhizome_fn;
+
+use crate::{aggregation::Aggregate, types::RhizomeType};
+
+rhizome_fn! {
+    /// The population variance of each group.
+    #[aggregate = Variance::<T>]
+    fn variance<T: RhizomeType + ToPrimitive>(arg: T) -> f64;
+}
+
+rhizome_fn! {
+    /// The population standard deviation of each group.
+    #[aggregate = StdDev::<T>]
+    fn stddev<T: RhizomeType + ToPrimitive>(arg: T) -> f64;
+}
+
+/// Accumulates the variance using Welford's algorithm, to avoid the loss of
+/// precision from subtracting large sums of squares.
+#[derive(Debug)]
+pub struct Variance<T> {
+    count: u64,
+    mean: f64,
+    m2: f64,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Default for Variance<T>
+where
+    T: RhizomeType + ToPrimitive,
+{
+    fn default() -> Self {
+        Self {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Aggregate for Variance<T>
+where
+    T: RhizomeType + ToPrimitive,
+{
+    type Input = (T,);
+    type Output = f64;
+
+    fn step(&mut self, (t,): (T,)) {
+        let Some(x) = t.to_f64() else {
+            return;
+        };
+
+        self.count += 1;
+
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    fn finalize(&self) -> Option<Self::Output> {
+        (self.count > 0).then(|| self.m2 / self.count as f64)
+    }
+}
+
+#[derive(Debug)]
+pub struct StdDev<T>(Variance<T>);
+
+impl<T> Default for StdDev<T>
+where
+    T: RhizomeType + ToPrimitive,
+{
+    fn default() -> Self {
+        Self(Variance::default())
+    }
+}
+
+impl<T> Aggregate for StdDev<T>
+where
+    T: RhizomeType + ToPrimitive,
+{
+    type Input = (T,);
+    type Output = f64;
+
+    fn step(&mut self, args: (T,)) {
+        self.0.step(args);
+    }
+
+    fn finalize(&self) -> Option<Self::Output> {
+        self.0.finalize().map(f64::sqrt)
+    }
+}
@@ -6,6 +6,7 @@ use crate::{
     var::Var,
 };
 
+pub mod collection;
 pub mod math;
 
 pub fn when<F, V, I>(args: V, f: F) -> FnPredicate<F, V, I>
@@ -15,6 +15,7 @@ pub(crate) mod relation;
 
 pub mod aggregation;
 pub mod args;
+pub mod decimal;
 pub mod error;
 pub mod kernel;
 pub mod predicate;
@@ -28,7 +29,10 @@ pub mod types;
 pub mod value;
 pub mod var;
 
-pub use logic::{build, AtomBinding, AtomBindings, ProgramBuilder, RuleBodyBuilder, RuleVars};
+pub use logic::{
+    build, dependency_graph, explain, query, AtomBinding, AtomBindings, DependencyGraph, Polarity,
+    ProgramBuilder, QueryBuilder, RuleBodyBuilder, RuleVars,
+};
 
 /// Test utilities.
 #[cfg(any(test, feature = "test_utils"))]
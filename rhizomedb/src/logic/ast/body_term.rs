@@ -14,12 +14,16 @@ use crate::{
 use super::{CidValue, Declaration};
 use crate::col_val::ColVal;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum BodyTerm {
     VarPredicate(VarPredicate),
     RelPredicate(RelPredicate),
+    /// A search that binds its variables to null when nothing matches, as in an
+    /// outer join.
+    Optional(RelPredicate),
     Negation(Negation),
     Aggregation(Aggregation),
+    Cast(Cast),
 }
 
 #[derive(Debug, Clone)]
@@ -56,8 +60,8 @@ impl RelPredicate {
 
     pub fn vars(&self) -> HashSet<&Var> {
         self.args
-            .iter()
-            .filter_map(|(_, v)| match v {
+            .values()
+            .filter_map(|v| match v {
                 ColVal::Lit(_) => None,
                 ColVal::Binding(var) => Some(var),
             })
@@ -108,8 +112,8 @@ impl Negation {
 
     pub fn vars(&self) -> HashSet<&Var> {
         self.args
-            .iter()
-            .filter_map(|(_, v)| match v {
+            .values()
+            .filter_map(|v| match v {
                 ColVal::Lit(_) => None,
                 ColVal::Binding(var) => Some(var),
             })
@@ -117,6 +121,27 @@ impl Negation {
     }
 }
 
+/// Binds a variable to the value of another, widened to the type of the first.
+#[derive(Debug, Clone)]
+pub struct Cast {
+    from: Var,
+    to: Var,
+}
+
+impl Cast {
+    pub fn new(from: Var, to: Var) -> Self {
+        Self { from, to }
+    }
+
+    pub fn from(&self) -> &Var {
+        &self.from
+    }
+
+    pub fn to(&self) -> &Var {
+        &self.to
+    }
+}
+
 #[derive(Clone)]
 pub struct VarPredicate {
     vars: Vec<Var>,
@@ -151,7 +176,7 @@ impl Debug for VarPredicate {
 
 #[derive(Clone)]
 pub struct Aggregation {
-    target: Var,
+    targets: Vec<Var>,
     vars: Vec<Var>,
     relation: Arc<Declaration>,
     group_by_cols: HashMap<ColId, ColVal>,
@@ -160,14 +185,14 @@ pub struct Aggregation {
 
 impl Aggregation {
     pub fn new(
-        target: Var,
+        targets: Vec<Var>,
         vars: Vec<Var>,
         relation: Arc<Declaration>,
         group_by_cols: HashMap<ColId, ColVal>,
         f: Arc<dyn AggregateWrapper>,
     ) -> Self {
         Self {
-            target,
+            targets,
             vars,
             relation,
             group_by_cols,
@@ -175,8 +200,8 @@ impl Aggregation {
         }
     }
 
-    pub fn target(&self) -> &Var {
-        &self.target
+    pub fn targets(&self) -> &[Var] {
+        &self.targets
     }
 
     pub fn vars(&self) -> &Vec<Var> {
@@ -213,7 +238,7 @@ impl Aggregation {
 impl Debug for Aggregation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Aggregation")
-            .field("target", &self.target)
+            .field("targets", &self.targets)
             .field("vars", &self.vars)
             .field("relation", &self.relation)
             .field("group_by_cols", &self.group_by_cols)
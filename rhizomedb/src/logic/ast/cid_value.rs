@@ -23,12 +23,12 @@ where
     T: IntoColType,
 {
     fn from(value: TypedVar<T>) -> Self {
-        let var = if let ColType::Any = value.typ() {
-            Var::new::<Cid>(value.id().to_string().as_ref())
-        } else {
-            Var::new::<T>(value.id().to_string().as_ref())
-        };
+        let var = value.as_var();
 
-        Self::Var(var)
+        if let ColType::Any = var.typ() {
+            Self::Var(var.with_typ(Cid::into_col_type()))
+        } else {
+            Self::Var(var)
+        }
     }
 }
@@ -12,6 +12,7 @@ pub struct Declaration {
     schema: Arc<Schema>,
     source: Source,
     relation: Box<dyn Relation>,
+    is_internal: bool,
 }
 
 impl Declaration {
@@ -26,11 +27,19 @@ impl Declaration {
             schema,
             source,
             relation,
+            is_internal: false,
         }
     }
 
+    /// Marks the relation as generated by a rewrite, so that it's never sunk.
+    pub(crate) fn internal(mut self) -> Self {
+        self.is_internal = true;
+
+        self
+    }
+
     pub fn id(&self) -> RelationId {
-        self.id
+        self.id.clone()
     }
 
     pub fn schema(&self) -> Arc<Schema> {
@@ -41,6 +50,10 @@ impl Declaration {
         self.source
     }
 
+    pub fn is_internal(&self) -> bool {
+        self.is_internal
+    }
+
     pub fn relation(&self) -> Box<dyn Relation> {
         dyn_clone::clone_box(&*self.relation)
     }
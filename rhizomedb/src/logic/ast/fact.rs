@@ -17,7 +17,7 @@ impl Fact {
     }
 
     pub fn head(&self) -> RelationId {
-        self.head
+        self.head.clone()
     }
 
     pub fn args(&self) -> &HashMap<ColId, Val> {
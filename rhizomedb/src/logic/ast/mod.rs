@@ -4,6 +4,7 @@ pub(super) mod clause;
 pub(super) mod declaration;
 pub(super) mod fact;
 pub(super) mod program;
+pub(super) mod query;
 pub(super) mod rule;
 pub(super) mod schema;
 pub(super) mod stratum;
@@ -14,5 +15,6 @@ pub(super) use clause::*;
 pub(super) use declaration::*;
 pub(super) use fact::*;
 pub(super) use program::*;
+pub(super) use query::*;
 pub(super) use rule::*;
 pub(super) use schema::*;
@@ -1,11 +1,14 @@
 use std::sync::Arc;
 
+use crate::logic::rewrite::Rewrite;
+
 use super::{Clause, Declaration};
 
 #[derive(Debug)]
 pub struct Program {
     declarations: Vec<Arc<Declaration>>,
     clauses: Vec<Clause>,
+    rewrites: Vec<Rewrite>,
 }
 
 impl Program {
@@ -13,9 +16,16 @@ impl Program {
         Self {
             declarations,
             clauses,
+            rewrites: Vec::default(),
         }
     }
 
+    pub fn with_rewrites(mut self, rewrites: Vec<Rewrite>) -> Self {
+        self.rewrites = rewrites;
+
+        self
+    }
+
     pub fn declarations(&self) -> &[Arc<Declaration>] {
         &self.declarations
     }
@@ -23,4 +33,8 @@ impl Program {
     pub fn clauses(&self) -> &[Clause] {
         &self.clauses
     }
+
+    pub fn rewrites(&self) -> &[Rewrite] {
+        &self.rewrites
+    }
 }
@@ -0,0 +1,27 @@
+use std::collections::HashMap;
+
+use crate::{
+    id::{ColId, RelationId},
+    value::Val,
+};
+
+/// A pattern over an output relation, binding some of its columns to constants.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    relation: RelationId,
+    args: HashMap<ColId, Val>,
+}
+
+impl Query {
+    pub fn new(relation: RelationId, args: HashMap<ColId, Val>) -> Self {
+        Self { relation, args }
+    }
+
+    pub fn relation(&self) -> RelationId {
+        self.relation.clone()
+    }
+
+    pub fn args(&self) -> &HashMap<ColId, Val> {
+        &self.args
+    }
+}
@@ -1,26 +1,64 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
     col_val::ColVal,
-    id::{ColId, RelationId},
+    id::{ColId, RelationId, VarId},
 };
 
-use super::{Aggregation, BodyTerm, Negation, RelPredicate, VarPredicate};
+use super::{Aggregation, BodyTerm, Cast, CidValue, Negation, RelPredicate, VarPredicate};
 
-#[derive(Debug)]
+/// Columns of the head of a rule that functionally determine others, so that the
+/// rule derives a single fact for each of their values.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Choice {
+    keys: Vec<ColId>,
+    vals: Vec<ColId>,
+}
+
+impl Choice {
+    pub fn new(keys: Vec<ColId>, vals: Vec<ColId>) -> Self {
+        Self { keys, vals }
+    }
+
+    pub fn keys(&self) -> &[ColId] {
+        &self.keys
+    }
+
+    pub fn vals(&self) -> &[ColId] {
+        &self.vals
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Rule {
     head: RelationId,
     args: HashMap<ColId, ColVal>,
     body: Vec<BodyTerm>,
+    choices: Vec<Choice>,
 }
 
 impl Rule {
     pub fn new(head: RelationId, args: HashMap<ColId, ColVal>, body: Vec<BodyTerm>) -> Self {
-        Self { head, args, body }
+        Self {
+            head,
+            args,
+            body,
+            choices: Vec::default(),
+        }
+    }
+
+    pub fn with_choices(mut self, choices: Vec<Choice>) -> Self {
+        self.choices = choices;
+
+        self
+    }
+
+    pub fn choices(&self) -> &[Choice] {
+        &self.choices
     }
 
     pub fn head(&self) -> RelationId {
-        self.head
+        self.head.clone()
     }
 
     pub fn args(&self) -> &HashMap<ColId, ColVal> {
@@ -57,6 +95,19 @@ impl Rule {
             .collect()
     }
 
+    pub fn optional_terms(&self) -> Vec<&RelPredicate> {
+        self.body
+            .iter()
+            .filter_map(|term| {
+                if let BodyTerm::Optional(inner) = term {
+                    Some(inner)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
     pub fn negation_terms(&self) -> Vec<&Negation> {
         self.body
             .iter()
@@ -82,4 +133,58 @@ impl Rule {
             })
             .collect()
     }
+
+    pub fn cast_terms(&self) -> Vec<&Cast> {
+        self.body
+            .iter()
+            .filter_map(|term| {
+                if let BodyTerm::Cast(inner) = term {
+                    Some(inner)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// The variables that are bound only by optional terms, or cast from those that
+    /// are, and so may be null.
+    pub fn nullable_vars(&self) -> HashSet<VarId> {
+        let mut bound = HashSet::new();
+
+        for term in self.rel_predicate_terms() {
+            if let Some(CidValue::Var(var)) = term.cid() {
+                bound.insert(var.id());
+            }
+
+            bound.extend(term.vars().into_iter().map(|var| var.id()));
+        }
+
+        for term in self.aggregation_terms() {
+            bound.extend(term.targets().iter().map(|var| var.id()));
+        }
+
+        let mut nullable: HashSet<VarId> = self
+            .optional_terms()
+            .into_iter()
+            .flat_map(|term| {
+                let cid = match term.cid() {
+                    Some(CidValue::Var(var)) => Some(var),
+                    _ => None,
+                };
+
+                term.vars().into_iter().chain(cid)
+            })
+            .map(|var| var.id())
+            .filter(|var| !bound.contains(var))
+            .collect();
+
+        for cast in self.cast_terms() {
+            if nullable.contains(&cast.from().id()) {
+                nullable.insert(cast.to().id());
+            }
+        }
+
+        nullable
+    }
 }
@@ -17,7 +17,7 @@ impl Schema {
     }
 
     pub fn id(&self) -> RelationId {
-        self.id
+        self.id.clone()
     }
 
     pub fn has_col(&self, k: &ColId) -> bool {
@@ -1,44 +1,74 @@
 use anyhow::Result;
-use std::{cell::RefCell, collections::HashMap, fmt::Debug, sync::Arc};
+use std::{cell::RefCell, collections::HashMap, fmt::Debug, rc::Rc, sync::Arc};
 
 use crate::{
     col_val::ColVal,
     error::{error, Error},
     id::{ColId, VarId},
     logic::ast::{Aggregation, Declaration},
+    predicate::{PredicateWhere, PredicateWrapper},
     types::ColType,
     var::Var,
-    AtomBinding,
+    AtomBinding, AtomBindings,
 };
 
-use crate::aggregation::AggregateWrapper;
+use crate::{
+    aggregation::{AggAcc, AggregateGroupBy, AggregateWrapper, Aggregates},
+    args::Args,
+    var::TypedVar,
+};
+
+use super::rule_body::{Relations, RuleBodyBuilder};
 
 pub struct AggregationBuilder {
-    pub(super) target: Var,
+    pub(super) targets: Vec<Var>,
     pub(super) vars: Vec<Var>,
     pub(super) bindings: RefCell<Vec<(ColId, ColVal)>>,
     pub(super) agg: Arc<dyn AggregateWrapper>,
+    pub(super) body: RuleBodyBuilder,
 }
 
 impl AggregationBuilder {
-    pub(crate) fn new(target: Var, f: Arc<dyn AggregateWrapper>) -> Self {
+    pub(crate) fn new(
+        targets: Vec<Var>,
+        f: Arc<dyn AggregateWrapper>,
+        relations: Rc<RefCell<Relations>>,
+    ) -> Self {
         Self {
-            target,
+            targets,
             agg: f,
             vars: Default::default(),
             bindings: Default::default(),
+            body: RuleBodyBuilder::new(relations),
+        }
+    }
+
+    /// Rebinds the aggregation to the columns of the temporary relation that its
+    /// nested body was flattened into, one for each of the variables it binds.
+    pub(super) fn bind_domain(&self, vars: &[Var]) -> Result<()> {
+        for var in &self.vars {
+            if !vars.iter().any(|bound| bound.id() == var.id()) {
+                return error(Error::ClauseNotDomainIndependent(var.id()));
+            }
         }
+
+        *self.bindings.borrow_mut() = vars
+            .iter()
+            .map(|var| (ColId::new(var.id().to_string()), ColVal::Binding(*var)))
+            .collect();
+
+        Ok(())
     }
+
     pub(crate) fn finalize(
         self,
         relation: Arc<Declaration>,
         bound_vars: &mut HashMap<VarId, ColType>,
     ) -> Result<Aggregation> {
-        if bound_vars
-            .insert(self.target.id(), self.target.typ())
-            .is_some()
-        {
-            return error(Error::AggregationBoundTarget(self.target.id()));
+        for target in &self.targets {
+            if bound_vars.insert(target.id(), target.typ()).is_some() {
+                return error(Error::AggregationBoundTarget(target.id()));
+            }
         }
 
         let mut cols = HashMap::default();
@@ -56,7 +86,7 @@ impl AggregationBuilder {
 
             match &col_val {
                 ColVal::Lit(val) => {
-                    if col.col_type().check(val).is_err() {
+                    if col.check(val).is_err() {
                         return error(Error::ColumnValueTypeConflict(
                             relation.id(),
                             col_id,
@@ -66,7 +96,15 @@ impl AggregationBuilder {
                     }
                 }
                 ColVal::Binding(var) => {
-                    if col.col_type().unify(&var.typ()).is_err() {
+                    // Aggregated columns widen into their variables, and grouping
+                    // variables into their columns
+                    let widens = if self.vars.contains(var) {
+                        col.col_type().widens_to(&var.typ())
+                    } else {
+                        var.typ().widens_to(col.col_type())
+                    };
+
+                    if !widens {
                         return error(Error::ColumnValueTypeConflict(
                             relation.id(),
                             col_id,
@@ -80,7 +118,7 @@ impl AggregationBuilder {
             cols.insert(col_id, col_val);
         }
 
-        let aggregation = Aggregation::new(self.target, self.vars, relation, cols, self.agg);
+        let aggregation = Aggregation::new(self.targets, self.vars, relation, cols, self.agg);
 
         Ok(aggregation)
     }
@@ -95,14 +133,98 @@ impl AggregationBuilder {
 
         Ok(())
     }
+
+    /// Joins another relation into the facts being aggregated.
+    pub fn search<T>(&self, id: &str, bindings: T) -> Result<()>
+    where
+        T: AtomBindings,
+    {
+        self.body.search(id, bindings)
+    }
+
+    pub fn except<T>(&self, id: &str, bindings: T) -> Result<()>
+    where
+        T: AtomBindings,
+    {
+        self.body.except(id, bindings)
+    }
+
+    pub fn predicate<I, Pred>(&self, pred: Pred) -> Result<()>
+    where
+        I: Args,
+        Pred: PredicateWhere<I>,
+        Pred::Predicate: PredicateWrapper,
+    {
+        self.body.predicate(pred)
+    }
 }
 
 impl Debug for AggregationBuilder {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AggregationBuilder")
-            .field("target", &self.target)
+            .field("targets", &self.targets)
             .field("vars", &self.vars)
             .field("bindings", &self.bindings)
             .finish()
     }
 }
+
+type Aggregate = (Var, Vec<Var>, Arc<dyn AggregateWrapper>);
+
+/// Collects several aggregates over the same group, which are then evaluated in a
+/// single pass over it.
+#[derive(Default)]
+pub struct AggregatesBuilder {
+    aggs: RefCell<Vec<Aggregate>>,
+}
+
+impl AggregatesBuilder {
+    pub fn aggregate<Agg, I, O>(&self, target: TypedVar<O>, agg: Agg) -> Result<()>
+    where
+        Agg: AggregateGroupBy<I, O>,
+        I: Args,
+        O: AggAcc,
+        Agg::Aggregate: AggregateWrapper + 'static,
+    {
+        self.aggs
+            .borrow_mut()
+            .push((target.into(), agg.as_args(), Arc::new(agg.aggregate())));
+
+        Ok(())
+    }
+
+    pub(super) fn finalize(self, relations: Rc<RefCell<Relations>>) -> AggregationBuilder {
+        let aggs = self.aggs.into_inner();
+
+        let targets = aggs.iter().map(|(target, _, _)| *target).collect();
+        let wrappers = aggs
+            .iter()
+            .map(|(_, _, agg)| Arc::clone(agg))
+            .collect::<Vec<_>>();
+
+        let mut builder =
+            AggregationBuilder::new(targets, Arc::new(Aggregates::new(&wrappers)), relations);
+
+        for (_, vars, _) in aggs {
+            builder.vars.extend(vars);
+        }
+
+        builder
+    }
+}
+
+impl Debug for AggregatesBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AggregatesBuilder")
+            .field(
+                "targets",
+                &self
+                    .aggs
+                    .borrow()
+                    .iter()
+                    .map(|(target, _, _)| *target)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
@@ -35,14 +35,12 @@ where
         let mut cols = HashMap::default();
 
         for (col_id, col) in self.cols {
-            if cols.insert(col_id, col).is_some() {
+            if cols.insert(col_id.clone(), col).is_some() {
                 return error(Error::DuplicateDeclarationCol(self.id, col_id));
             }
-
-            cols.insert(col_id, col);
         }
 
-        let schema = Schema::new(self.id, cols);
+        let schema = Schema::new(self.id.clone(), cols);
         let declaration =
             Declaration::new(self.id, Arc::new(schema), self.source, Box::<R>::default());
 
@@ -62,7 +60,20 @@ where
     {
         let id = ColId::new(id);
         let t = ColType::new::<C>();
-        let col = Col::new(id, t);
+        let col = Col::new(id.clone(), t);
+
+        self.cols.push((id, col));
+
+        self
+    }
+
+    pub fn optional_column<C>(mut self, id: &str) -> Self
+    where
+        C: IntoColType,
+    {
+        let id = ColId::new(id);
+        let t = ColType::new::<C>();
+        let col = Col::optional(id.clone(), t);
 
         self.cols.push((id, col));
 
@@ -7,6 +7,7 @@ use crate::{
     id::ColId,
     logic::ast::{Declaration, Fact},
     relation::Source,
+    value::Val,
 };
 
 use super::{atom_binding::AtomBinding, atom_bindings::AtomBindings};
@@ -47,7 +48,7 @@ impl FactBuilder {
                         return error(Error::ConflictingColumnBinding(self.relation.id(), col_id));
                     }
 
-                    if col.col_type().check(&val).is_err() {
+                    if col.check(&val).is_err() {
                         return error(Error::ColumnValueTypeConflict(
                             self.relation.id(),
                             col_id,
@@ -64,10 +65,16 @@ impl FactBuilder {
             }
         }
 
-        for col_id in self.relation.schema().cols().keys() {
-            if !cols.contains_key(col_id) {
-                return error(Error::ColumnMissing(self.relation.id(), *col_id));
+        for (col_id, col) in self.relation.schema().cols() {
+            if cols.contains_key(col_id) {
+                continue;
             }
+
+            if !col.is_optional() {
+                return error(Error::ColumnMissing(self.relation.id(), col_id.clone()));
+            }
+
+            cols.insert(col_id.clone(), Val::Null);
         }
 
         match self.relation.source() {
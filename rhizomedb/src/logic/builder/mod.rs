@@ -1,10 +1,12 @@
 use anyhow::Result;
 
-use crate::ram::Program;
+use crate::{pretty::Pretty, ram::Program};
+
+use super::dependency_graph::DependencyGraph;
 
 pub use self::{
     atom_binding::AtomBinding, atom_bindings::AtomBindings, program::ProgramBuilder,
-    rule_body::RuleBodyBuilder, rule_vars::RuleVars,
+    query::QueryBuilder, rule_body::RuleBodyBuilder, rule_vars::RuleVars,
 };
 
 use super::lower_to_ram;
@@ -16,6 +18,7 @@ mod declaration;
 mod fact;
 mod negation;
 mod program;
+mod query;
 mod rel_predicate;
 mod rule_body;
 mod rule_head;
@@ -31,6 +34,42 @@ where
     Ok(ram)
 }
 
+/// Builds a program that only derives the tuples of a relation matching a query,
+/// along with those they depend upon.
+pub fn query<F, Q>(f: F, id: &str, q: Q) -> Result<Program>
+where
+    F: FnOnce(ProgramBuilder) -> Result<ProgramBuilder>,
+    Q: FnOnce(QueryBuilder) -> QueryBuilder,
+{
+    let logic = ProgramBuilder::build_query(f, id, q)?;
+    let ram = lower_to_ram::lower_to_ram(&logic)?;
+
+    Ok(ram)
+}
+
+/// Renders the program that a builder lowers to, along with the rewrites that
+/// were made to it before lowering.
+pub fn explain<F>(f: F) -> Result<String>
+where
+    F: FnOnce(ProgramBuilder) -> Result<ProgramBuilder>,
+{
+    let program = build(f)?;
+
+    let mut w = Vec::default();
+    Pretty::to_doc(&program).render(80, &mut w)?;
+
+    Ok(String::from_utf8(w)?)
+}
+
+pub fn dependency_graph<F>(f: F) -> Result<DependencyGraph>
+where
+    F: FnOnce(ProgramBuilder) -> Result<ProgramBuilder>,
+{
+    let logic = ProgramBuilder::build(f)?;
+
+    DependencyGraph::new(&logic)
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -230,6 +269,27 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_conflicting_choice_column() {
+        assert_compile_err!(
+            &Error::ConflictingChoiceColumn("p".into(), "p0".into()),
+            |p| {
+                p.output("p", |h| h.column::<i32>("p0").column::<i32>("p1"))?;
+                p.output("q", |h| h.column::<i32>("q0").column::<i32>("q1"))?;
+
+                p.rule::<(i32, i32)>("p", &|h, b, (x, y)| {
+                    h.bind((("p0", x), ("p1", y)))?;
+                    h.choose(["p0"], ["p0", "p1"])?;
+                    b.search("q", (("q0", x), ("q1", y)))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            }
+        );
+    }
+
     #[test]
     fn test_range_restriction() {
         assert_compile_err!(
@@ -653,6 +713,27 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_nullable_column_binding() {
+        assert_compile_err!(
+            &Error::NullableColumnBinding("q".into(), "y".into(), "x1".into()),
+            |p| {
+                p.output("p", |h| h.column::<i32>("x").column::<i32>("y"))?;
+                p.output("q", |h| h.column::<i32>("x").column::<i32>("y"))?;
+
+                p.rule::<(i32, i32)>("q", &|h, b, (x, y)| {
+                    h.bind((("x", x), ("y", y)))?;
+                    b.search("p", (("x", x),))?;
+                    b.search_optional("p", (("y", x), ("x", y)))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            }
+        );
+    }
+
     #[test]
     fn test_var_type_conflict_get_link_cid() {
         assert_compile_err!(
@@ -711,13 +792,13 @@ mod tests {
             &Error::ColumnValueTypeConflict(
                 "p".into(),
                 "x".into(),
-                ColVal::Lit(Val::S8(5)),
+                ColVal::Lit(Val::S64(5)),
                 ColType::Type(Type::S32)
             ),
             |p| {
                 p.output("p", |h| h.column::<i32>("x"))?;
 
-                p.fact("p", |f| f.bind((("x", 5_i8),)))?;
+                p.fact("p", |f| f.bind((("x", 5_i64),)))?;
 
                 Ok(p)
             }
@@ -32,7 +32,7 @@ impl NegationBuilder {
 
             match &col_val {
                 ColVal::Lit(val) => {
-                    if col.col_type().check(val).is_err() {
+                    if col.check(val).is_err() {
                         return error(Error::ColumnValueTypeConflict(
                             relation.id(),
                             col_id,
@@ -42,7 +42,7 @@ impl NegationBuilder {
                     }
                 }
                 ColVal::Binding(var) => {
-                    if col.col_type().unify(&var.typ()).is_err() {
+                    if !var.typ().widens_to(col.col_type()) {
                         return error(Error::ColumnValueTypeConflict(
                             relation.id(),
                             col_id,
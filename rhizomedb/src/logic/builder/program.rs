@@ -3,17 +3,30 @@ use cid::Cid;
 use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::Arc};
 
 use crate::{
+    col_val::ColVal,
     error::{error, Error},
     id::RelationId,
-    logic::ast::{Clause, Declaration, Program, Rule},
+    logic::{
+        ast::{Aggregation, BodyTerm, Clause, Declaration, Program, Query, Rule, Schema},
+        coerce::coerce,
+        infer::infer,
+        magic_sets::magic_sets,
+        prune::prune,
+        rewrite::Rewrite,
+        share::share,
+    },
     relation::{Bistore, Hexastore, Relation, Source},
     tuple::Tuple,
     value::Any,
 };
 
 use super::{
-    declaration::DeclarationBuilder, fact::FactBuilder, rule_body::RuleBodyBuilder,
-    rule_head::RuleHeadBuilder, rule_vars::RuleVars,
+    declaration::DeclarationBuilder,
+    fact::FactBuilder,
+    query::QueryBuilder,
+    rule_body::{Domains, RuleBodyBuilder},
+    rule_head::RuleHeadBuilder,
+    rule_vars::RuleVars,
 };
 
 type RuleBuilderClosure<'a, T> =
@@ -23,6 +36,10 @@ type RuleBuilderClosure<'a, T> =
 pub struct ProgramBuilder {
     relations: Rc<RefCell<HashMap<String, Arc<Declaration>>>>,
     clauses: RefCell<Vec<Clause>>,
+    demands: RefCell<Vec<Query>>,
+    consumed: RefCell<Vec<RelationId>>,
+    rewrites: RefCell<Vec<Rewrite>>,
+    domains: RefCell<Domains>,
 }
 
 impl ProgramBuilder {
@@ -36,11 +53,50 @@ impl ProgramBuilder {
         builder.finalize()
     }
 
+    pub fn build_query<F, Q>(f: F, id: &str, q: Q) -> Result<Program>
+    where
+        F: FnOnce(Self) -> Result<Self>,
+        Q: FnOnce(QueryBuilder) -> QueryBuilder,
+    {
+        let builder = Self::default().install_preamble()?;
+        let builder = f(builder)?;
+
+        builder.demand(id, q)?;
+        builder.consume(id)?;
+        builder.finalize()
+    }
+
     pub fn finalize(self) -> Result<Program> {
-        let declarations = self.relations.borrow_mut().values().cloned().collect();
-        let program = Program::new(declarations, self.clauses.into_inner());
+        let mut declarations = self.relations.borrow().values().cloned().collect();
+        let mut clauses = self.clauses.into_inner();
+        let mut rewrites = self.rewrites.into_inner();
+
+        name_domains(
+            self.domains.into_inner(),
+            &self.relations.borrow(),
+            &mut declarations,
+            &mut clauses,
+            &mut rewrites,
+        );
+
+        let mut program = infer(&Program::new(declarations, clauses).with_rewrites(rewrites))?;
+
+        let demands = self.demands.into_inner();
+        if !demands.is_empty() {
+            program = magic_sets(&program, &demands)?;
+        }
+
+        let mut consumed = self.consumed.into_inner();
+        if !consumed.is_empty() {
+            // Demanded relations are always read by the caller
+            consumed.extend(demands.iter().map(Query::relation));
 
-        Ok(program)
+            program = prune(&program, &consumed);
+        }
+
+        let program = share(&program)?;
+
+        Ok(coerce(&program))
     }
 
     pub fn input<F>(&self, id: &str, f: F) -> Result<()>
@@ -111,6 +167,37 @@ impl ProgramBuilder {
         Ok(())
     }
 
+    /// Marks an output relation as demand-driven, so that it only derives the tuples
+    /// matching the given query, or those of any other demand on it.
+    pub fn demand<F>(&self, id: &str, f: F) -> Result<()>
+    where
+        F: FnOnce(QueryBuilder) -> QueryBuilder,
+    {
+        let Some(declaration) = self.relations.borrow().get(id).cloned() else {
+            return error(Error::UnrecognizedRelation(id.to_string()));
+        };
+
+        let query = QueryBuilder::build(declaration, f)?;
+
+        self.demands.borrow_mut().push(query);
+
+        Ok(())
+    }
+
+    /// Marks a relation as consumed by the caller. Once any relation is marked,
+    /// the outputs that no consumed relation depends upon are pruned, and
+    /// registering a sink on one is an error. Demanded relations are consumed
+    /// implicitly.
+    pub fn consume(&self, id: &str) -> Result<()> {
+        let Some(declaration) = self.relations.borrow().get(id).cloned() else {
+            return error(Error::UnrecognizedRelation(id.to_string()));
+        };
+
+        self.consumed.borrow_mut().push(declaration.id());
+
+        Ok(())
+    }
+
     pub fn rule<T>(&self, id: &str, f: &RuleBuilderClosure<'_, T::Vars>) -> Result<()>
     where
         T: RuleVars,
@@ -125,17 +212,40 @@ impl ProgramBuilder {
 
         f(&head_builder, &body_builder, T::into_vars(0))?;
 
-        let body = body_builder.finalize(&mut bound_vars)?;
-        let head = head_builder.finalize(&mut bound_vars)?;
+        let (body, domains) = body_builder.finalize(&mut bound_vars)?;
+        let (head, choices) = head_builder.finalize(&mut bound_vars)?;
 
         match declaration.source() {
             Source::Edb => error(Error::ClauseHeadEDB(declaration.id())),
             Source::Idb => {
-                let rule = Rule::new(declaration.id(), head, body);
+                let rule = Rule::new(declaration.id(), head, body).with_choices(choices);
+                let nullable = rule.nullable_vars();
+
+                for (col_id, col_val) in rule.args() {
+                    let ColVal::Binding(var) = col_val else {
+                        continue;
+                    };
+
+                    let is_optional = declaration
+                        .schema()
+                        .get_col(col_id)
+                        .map_or(false, |col| col.is_optional());
+
+                    if nullable.contains(&var.id()) && !is_optional {
+                        return error(Error::NullableColumnBinding(
+                            declaration.id(),
+                            col_id.clone(),
+                            var.id(),
+                        ));
+                    }
+                }
+
                 let clause = Clause::Rule(rule);
 
                 self.clauses.borrow_mut().push(clause);
 
+                self.domains.borrow_mut().extend(domains);
+
                 Ok(())
             }
         }
@@ -155,3 +265,78 @@ impl ProgramBuilder {
         Ok(self)
     }
 }
+
+/// Names the relations that the nested bodies of aggregations are flattened into,
+/// once every declared relation is known, and points the aggregations at them.
+fn name_domains(
+    domains: Domains,
+    relations: &HashMap<String, Arc<Declaration>>,
+    declarations: &mut Vec<Arc<Declaration>>,
+    clauses: &mut Vec<Clause>,
+    rewrites: &mut Vec<Rewrite>,
+) {
+    let mut names = (0..)
+        .map(|idx| format!("domain_{idx}"))
+        .filter(|name| !relations.contains_key(name));
+
+    let named = domains
+        .iter()
+        .map(|(domain, _)| {
+            let name = names.next().expect("relation names are unbounded");
+            let rel_id = RelationId::new(&name);
+            let declaration = Declaration::new(
+                rel_id.clone(),
+                Arc::new(Schema::new(rel_id, domain.schema().cols().clone())),
+                Source::Idb,
+                domain.relation(),
+            )
+            .internal();
+
+            (Arc::clone(domain), Arc::new(declaration))
+        })
+        .collect::<Vec<_>>();
+
+    let rename = |rule: &Rule, head: RelationId| {
+        let body = rule
+            .body()
+            .iter()
+            .map(|term| {
+                let BodyTerm::Aggregation(inner) = term else {
+                    return term.clone();
+                };
+
+                let relation = inner.relation();
+                let Some((_, declaration)) = named
+                    .iter()
+                    .find(|(domain, _)| Arc::ptr_eq(domain, &relation))
+                else {
+                    return term.clone();
+                };
+
+                BodyTerm::Aggregation(Aggregation::new(
+                    inner.targets().to_vec(),
+                    inner.vars().clone(),
+                    Arc::clone(declaration),
+                    inner.group_by_cols().clone(),
+                    inner.agg(),
+                ))
+            })
+            .collect();
+
+        Rule::new(head, rule.args().clone(), body).with_choices(rule.choices().to_vec())
+    };
+
+    for clause in clauses.iter_mut() {
+        if let Clause::Rule(rule) = clause {
+            *rule = rename(rule, rule.head());
+        }
+    }
+
+    for ((_, rule), (_, declaration)) in domains.iter().zip(&named) {
+        rewrites.push(Rewrite::Flattened {
+            relation: declaration.id(),
+        });
+        clauses.push(Clause::Rule(rename(rule, declaration.id())));
+        declarations.push(Arc::clone(declaration));
+    }
+}
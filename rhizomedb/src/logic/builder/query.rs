@@ -0,0 +1,99 @@
+use anyhow::Result;
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    col_val::ColVal,
+    error::{error, Error},
+    id::ColId,
+    logic::ast::{Declaration, Query},
+    relation::Source,
+    types::ColType,
+};
+
+use super::{atom_binding::AtomBinding, atom_bindings::AtomBindings};
+
+#[derive(Debug)]
+pub struct QueryBuilder {
+    relation: Arc<Declaration>,
+    bindings: Vec<(ColId, ColVal)>,
+}
+
+impl QueryBuilder {
+    fn new(relation: Arc<Declaration>) -> Self {
+        Self {
+            relation,
+            bindings: Vec::default(),
+        }
+    }
+
+    pub fn build<F>(relation: Arc<Declaration>, f: F) -> Result<Query>
+    where
+        F: FnOnce(Self) -> Self,
+    {
+        f(Self::new(relation)).finalize()
+    }
+
+    pub fn finalize(self) -> Result<Query> {
+        let schema = self.relation.schema();
+        let mut cols = HashMap::default();
+
+        for (col_id, col_val) in self.bindings {
+            match col_val {
+                ColVal::Lit(val) => {
+                    let Some(col) = schema.get_col(&col_id) else {
+                        return error(Error::UnrecognizedColumnBinding(self.relation.id(), col_id));
+                    };
+
+                    if cols.contains_key(&col_id) {
+                        return error(Error::ConflictingColumnBinding(self.relation.id(), col_id));
+                    }
+
+                    if col.check(&val).is_err() {
+                        return error(Error::ColumnValueTypeConflict(
+                            self.relation.id(),
+                            col_id,
+                            ColVal::Lit(val),
+                            *col.col_type(),
+                        ));
+                    };
+
+                    // Demanded values are matched against the column, so widen them to it
+                    let val = match col.col_type() {
+                        ColType::Type(typ) => val.clone().widen(*typ).unwrap_or(val),
+                        ColType::Any => val,
+                    };
+
+                    cols.insert(col_id, val);
+                }
+                ColVal::Binding(var) => {
+                    return error(Error::NonGroundFact(self.relation.id(), col_id, var.id()));
+                }
+            }
+        }
+
+        match self.relation.source() {
+            Source::Edb => error(Error::DemandEDB(self.relation.id())),
+            Source::Idb => Ok(Query::new(self.relation.id(), cols)),
+        }
+    }
+
+    pub fn bind<T>(mut self, bindings: T) -> Self
+    where
+        T: AtomBindings,
+    {
+        bindings.bind(&mut self.bindings);
+
+        self
+    }
+
+    pub fn bind_one<T>(mut self, binding: T) -> Self
+    where
+        T: AtomBinding,
+    {
+        let (id, value) = binding.into_pair();
+
+        self.bindings.push((id, value));
+
+        self
+    }
+}
@@ -57,7 +57,7 @@ impl RelPredicateBuilder {
 
             match &col_val {
                 ColVal::Lit(val) => {
-                    if col.col_type().check(val).is_err() {
+                    if col.check(val).is_err() {
                         return error(Error::ColumnValueTypeConflict(
                             relation.id(),
                             col_id,
@@ -67,7 +67,14 @@ impl RelPredicateBuilder {
                     }
                 }
                 ColVal::Binding(var) => {
-                    if let Ok(unified) = col.col_type().unify(&var.typ()) {
+                    // Values widen into the variables they're bound to, but never narrow
+                    let unified = col
+                        .col_type()
+                        .unify(&var.typ())
+                        .ok()
+                        .filter(|_| col.col_type().widens_to(&var.typ()));
+
+                    if let Some(unified) = unified {
                         bound_vars.insert(var.id(), unified);
                     } else {
                         return error(Error::ColumnValueTypeConflict(
@@ -2,18 +2,25 @@ use anyhow::Result;
 use std::{cell::RefCell, collections::HashMap, fmt::Debug, rc::Rc, sync::Arc};
 
 use crate::{
-    aggregation::{AggAcc, AggregateGroupBy, AggregateWrapper},
-    args::Args,
+    aggregation::{AggAcc, AggregateGroupBy, AggregateWrapper, Rows},
+    args::{Args, Row},
+    col::Col,
+    col_val::ColVal,
     error::{error, Error},
-    id::VarId,
-    logic::ast::{BodyTerm, CidValue, Declaration, VarPredicate},
+    id::{ColId, RelationId, VarId},
+    logic::ast::{BodyTerm, Cast, CidValue, Declaration, Rule, Schema, VarPredicate},
     predicate::{PredicateWhere, PredicateWrapper},
-    types::ColType,
+    relation::{DefaultRelation, Source},
+    typed_vars::TypedVars,
+    types::{ColType, IntoColType},
+    value::Any,
     var::{TypedVar, Var},
 };
 
 use super::{
-    aggregation::AggregationBuilder, atom_bindings::AtomBindings, negation::NegationBuilder,
+    aggregation::{AggregatesBuilder, AggregationBuilder},
+    atom_bindings::AtomBindings,
+    negation::NegationBuilder,
     rel_predicate::RelPredicateBuilder,
 };
 
@@ -21,10 +28,13 @@ type RelPredicates = Vec<(String, RelPredicateBuilder)>;
 type Negations = Vec<(String, NegationBuilder)>;
 type VarPredicates = Vec<(Vec<Var>, Arc<dyn PredicateWrapper>)>;
 type Aggregations = Vec<(String, AggregationBuilder)>;
-type Relations = HashMap<String, Arc<Declaration>>;
+pub(super) type Relations = HashMap<String, Arc<Declaration>>;
+pub(super) type Domains = Vec<(Arc<Declaration>, Rule)>;
 
 pub struct RuleBodyBuilder {
     rel_predicates: RefCell<RelPredicates>,
+    optionals: RefCell<RelPredicates>,
+    casts: RefCell<Vec<(Var, Var)>>,
     negations: RefCell<Negations>,
     var_predicates: RefCell<VarPredicates>,
     aggregations: RefCell<Aggregations>,
@@ -41,6 +51,8 @@ impl RuleBodyBuilder {
     pub fn new(relations: Rc<RefCell<HashMap<String, Arc<Declaration>>>>) -> Self {
         Self {
             rel_predicates: RefCell::default(),
+            optionals: RefCell::default(),
+            casts: RefCell::default(),
             negations: RefCell::default(),
             var_predicates: RefCell::default(),
             aggregations: RefCell::default(),
@@ -48,8 +60,14 @@ impl RuleBodyBuilder {
         }
     }
 
-    pub fn finalize(self, bound_vars: &mut HashMap<VarId, ColType>) -> Result<Vec<BodyTerm>> {
+    /// Finalizes the body, along with the temporary relations that the nested bodies
+    /// of its aggregations are flattened into and the rules deriving them.
+    pub fn finalize(
+        self,
+        bound_vars: &mut HashMap<VarId, ColType>,
+    ) -> Result<(Vec<BodyTerm>, Domains)> {
         let mut body_terms = Vec::default();
+        let mut domains = Vec::default();
 
         for (id, builder) in self.rel_predicates.into_inner() {
             let Some(declaration) = self.relations.borrow().get(&id).cloned() else {
@@ -62,6 +80,32 @@ impl RuleBodyBuilder {
             body_terms.push(term);
         }
 
+        for (id, builder) in self.optionals.into_inner() {
+            let Some(declaration) = self.relations.borrow().get(&id).cloned() else {
+                return error(Error::UnrecognizedRelation(id));
+            };
+
+            let predicate = builder.finalize(declaration, bound_vars)?;
+            let term = BodyTerm::Optional(predicate);
+
+            body_terms.push(term);
+        }
+
+        for (from, to) in self.casts.into_inner() {
+            if !bound_vars.contains_key(&from.id()) {
+                return error(Error::ClauseNotDomainIndependent(from.id()));
+            }
+
+            if let (ColType::Type(from_type), ColType::Type(to_type)) = (from.typ(), to.typ()) {
+                if !from_type.widens_to(&to_type) {
+                    return error(Error::TypeMismatch(to_type, from_type));
+                }
+            }
+
+            bound_vars.entry(to.id()).or_insert(to.typ());
+            body_terms.push(BodyTerm::Cast(Cast::new(from, to)));
+        }
+
         for (vars, f) in self.var_predicates.into_inner() {
             for var in &vars {
                 if !bound_vars.contains_key(&var.id()) {
@@ -92,9 +136,25 @@ impl RuleBodyBuilder {
             body_terms.push(term);
         }
 
-        for (id, builder) in self.aggregations.into_inner() {
-            let Some(declaration) = self.relations.borrow().get(&id).cloned() else {
-                return error(Error::UnrecognizedRelation(id));
+        for (id, mut builder) in self.aggregations.into_inner() {
+            let declaration = if builder.body.is_empty() {
+                let Some(declaration) = self.relations.borrow().get(&id).cloned() else {
+                    return error(Error::UnrecognizedRelation(id));
+                };
+
+                declaration
+            } else {
+                let body = std::mem::replace(
+                    &mut builder.body,
+                    RuleBodyBuilder::new(Rc::clone(&self.relations)),
+                );
+
+                let (domain, vars, nested) = body.domain(&id, builder.bindings.take())?;
+
+                builder.bind_domain(&vars)?;
+                domains.extend(nested);
+
+                domain
             };
 
             let aggregation = builder.finalize(declaration, bound_vars)?;
@@ -103,7 +163,127 @@ impl RuleBodyBuilder {
             body_terms.push(term);
         }
 
-        Ok(body_terms)
+        Ok((body_terms, domains))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.rel_predicates.borrow().is_empty()
+            && self.optionals.borrow().is_empty()
+            && self.casts.borrow().is_empty()
+            && self.negations.borrow().is_empty()
+            && self.var_predicates.borrow().is_empty()
+            && self.aggregations.borrow().is_empty()
+    }
+
+    /// Flattens a search of a relation joined with this body into a temporary
+    /// relation, with a column for each column of the searches, so that every
+    /// row of the join is aggregated once. Returns its declaration, its variables
+    /// and the rules deriving it and any relations nested within it. The relation
+    /// is only named once the program is finalized.
+    fn domain(
+        self,
+        id: &str,
+        bindings: Vec<(ColId, ColVal)>,
+    ) -> Result<(Arc<Declaration>, Vec<Var>, Domains)> {
+        let search = RelPredicateBuilder::new(None);
+        *search.bindings.borrow_mut() = bindings;
+
+        self.rel_predicates
+            .borrow_mut()
+            .insert(0, (id.to_string(), search));
+
+        // Bind every column of the searches, so that facts differing only in columns
+        // no variable is bound to remain distinct rows of the domain
+        let searches = self.rel_predicates.borrow();
+        let optionals = self.optionals.borrow();
+
+        for (idx, (id, search)) in searches.iter().chain(optionals.iter()).enumerate() {
+            let Some(declaration) = self.relations.borrow().get(id).cloned() else {
+                return error(Error::UnrecognizedRelation(id.clone()));
+            };
+
+            let schema = declaration.schema();
+            let mut cols = schema.cols().iter().collect::<Vec<_>>();
+            cols.sort_by_key(|(col_id, _)| col_id.to_string());
+
+            let mut bindings = search.bindings.borrow_mut();
+            for (col_id, col) in cols {
+                if bindings.iter().any(|(bound, _)| bound == col_id) {
+                    continue;
+                }
+
+                let var = Var::new::<Any>(&format!("_{idx}_{col_id}")).with_typ(*col.col_type());
+                bindings.push((col_id.clone(), ColVal::Binding(var)));
+            }
+        }
+
+        drop((searches, optionals));
+
+        let mut bound_vars = HashMap::default();
+        let (body, mut domains) = self.finalize(&mut bound_vars)?;
+
+        let mut vars = Vec::<Var>::default();
+        for term in &body {
+            let bound = match term {
+                BodyTerm::RelPredicate(inner) | BodyTerm::Optional(inner) => {
+                    let mut bound = inner.vars().into_iter().copied().collect::<Vec<_>>();
+
+                    if let Some(CidValue::Var(var)) = inner.cid() {
+                        bound.push(*var);
+                    }
+
+                    bound
+                }
+                BodyTerm::Cast(inner) => vec![*inner.to()],
+                BodyTerm::Aggregation(inner) => inner.targets().to_vec(),
+                BodyTerm::VarPredicate(_) | BodyTerm::Negation(_) => continue,
+            };
+
+            for var in bound {
+                if !vars.iter().any(|other| other.id() == var.id()) {
+                    vars.push(var);
+                }
+            }
+        }
+
+        vars.sort_by_key(|var| var.id().to_string());
+
+        let rel_id = RelationId::new("domain");
+        let args = vars
+            .iter()
+            .map(|var| (ColId::new(var.id().to_string()), ColVal::Binding(*var)))
+            .collect();
+
+        let rule = Rule::new(rel_id.clone(), args, body);
+        let nullable = rule.nullable_vars();
+
+        let cols = vars
+            .iter()
+            .map(|var| {
+                let col_id = ColId::new(var.id().to_string());
+                let col = if nullable.contains(&var.id()) {
+                    Col::optional(col_id.clone(), var.typ())
+                } else {
+                    Col::new(col_id.clone(), var.typ())
+                };
+
+                (col_id, col)
+            })
+            .collect();
+
+        let declaration = Arc::new(
+            Declaration::new(
+                rel_id.clone(),
+                Arc::new(Schema::new(rel_id, cols)),
+                Source::Idb,
+                Box::<DefaultRelation>::default(),
+            )
+            .internal(),
+        );
+
+        domains.push((Arc::clone(&declaration), rule));
+
+        Ok((declaration, vars, domains))
     }
 
     pub fn search<T>(&self, id: &str, bindings: T) -> Result<()>
@@ -152,6 +332,46 @@ impl RuleBodyBuilder {
         Ok(())
     }
 
+    /// Searches a relation as in an outer join, binding any variables that aren't
+    /// already bound to null when no fact matches those that are.
+    pub fn search_optional<T>(&self, id: &str, bindings: T) -> Result<()>
+    where
+        T: AtomBindings,
+    {
+        let builder = RelPredicateBuilder::new(None);
+
+        bindings.bind(&mut builder.bindings.borrow_mut());
+
+        self.optionals.borrow_mut().push((id.to_string(), builder));
+
+        Ok(())
+    }
+
+    pub fn search_optional_cid<C, T>(&self, id: &str, cid: C, bindings: T) -> Result<()>
+    where
+        C: Into<CidValue>,
+        T: AtomBindings,
+    {
+        let builder = RelPredicateBuilder::new(Some(cid.into()));
+
+        bindings.bind(&mut builder.bindings.borrow_mut());
+
+        self.optionals.borrow_mut().push((id.to_string(), builder));
+
+        Ok(())
+    }
+
+    /// Binds a variable to the value of another, widened to the type of the first.
+    pub fn cast<T, U>(&self, from: TypedVar<T>, to: TypedVar<U>) -> Result<()>
+    where
+        T: IntoColType,
+        U: IntoColType,
+    {
+        self.casts.borrow_mut().push((from.into(), to.into()));
+
+        Ok(())
+    }
+
     pub fn except<T>(&self, id: &str, bindings: T) -> Result<()>
     where
         T: AtomBindings,
@@ -206,8 +426,64 @@ impl RuleBodyBuilder {
         O: AggAcc,
         Agg::Aggregate: AggregateWrapper + 'static,
     {
-        let wrapper = Arc::new(Agg::Aggregate::default());
-        let mut builder = AggregationBuilder::new(target.into(), wrapper);
+        let wrapper = Arc::new(agg.aggregate());
+        let mut builder =
+            AggregationBuilder::new(vec![target.into()], wrapper, Rc::clone(&self.relations));
+
+        for var in agg.as_args() {
+            builder.vars.push(var);
+        }
+
+        group_by.bind(&mut builder.bindings.borrow_mut());
+
+        self.aggregations
+            .borrow_mut()
+            .push((id.to_string(), builder));
+
+        Ok(())
+    }
+
+    /// Computes several aggregates over the same group in a single pass over it.
+    pub fn group_by_many<GroupBy, F>(&self, id: &str, group_by: GroupBy, f: F) -> Result<()>
+    where
+        GroupBy: AtomBindings,
+        F: FnOnce(&'_ AggregatesBuilder) -> Result<()>,
+    {
+        let aggregates = AggregatesBuilder::default();
+
+        f(&aggregates)?;
+
+        let builder = aggregates.finalize(Rc::clone(&self.relations));
+
+        group_by.bind(&mut builder.bindings.borrow_mut());
+
+        self.aggregations
+            .borrow_mut()
+            .push((id.to_string(), builder));
+
+        Ok(())
+    }
+
+    /// Binds the targets to the values of each row that a selecting aggregate, such
+    /// as `arg_max` or `top_k`, picks from each group.
+    pub fn select<Targets, GroupBy, Agg, I, R>(
+        &self,
+        targets: Targets,
+        id: &str,
+        group_by: GroupBy,
+        agg: Agg,
+    ) -> Result<()>
+    where
+        Targets: TypedVars<Args = R>,
+        GroupBy: AtomBindings,
+        Agg: AggregateGroupBy<I, Rows<R>>,
+        I: Args,
+        R: Row,
+        Agg::Aggregate: AggregateWrapper + 'static,
+    {
+        let wrapper = Arc::new(agg.aggregate());
+        let mut builder =
+            AggregationBuilder::new(targets.vars(), wrapper, Rc::clone(&self.relations));
 
         for var in agg.as_args() {
             builder.vars.push(var);
@@ -236,8 +512,9 @@ impl RuleBodyBuilder {
         O: AggAcc,
         Agg::Aggregate: AggregateWrapper + 'static,
     {
-        let wrapper = Arc::new(Agg::Aggregate::default());
-        let mut builder = AggregationBuilder::new(target.into(), wrapper);
+        let wrapper = Arc::new(agg.aggregate());
+        let mut builder =
+            AggregationBuilder::new(vec![target.into()], wrapper, Rc::clone(&self.relations));
 
         for var in agg.as_args() {
             builder.vars.push(var);
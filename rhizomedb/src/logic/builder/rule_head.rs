@@ -5,7 +5,7 @@ use crate::{
     col_val::ColVal,
     error::{error, Error},
     id::{ColId, VarId},
-    logic::ast::Declaration,
+    logic::ast::{Choice, Declaration},
     types::ColType,
     value::Val,
 };
@@ -16,6 +16,7 @@ use super::{atom_binding::AtomBinding, atom_bindings::AtomBindings};
 pub struct RuleHeadBuilder {
     relation: Arc<Declaration>,
     bindings: RefCell<Vec<(ColId, ColVal)>>,
+    choices: RefCell<Vec<Choice>>,
 }
 
 impl RuleHeadBuilder {
@@ -23,14 +24,34 @@ impl RuleHeadBuilder {
         Self {
             relation,
             bindings: RefCell::default(),
+            choices: RefCell::default(),
         }
     }
 
     pub fn finalize(
         self,
         bound_vars: &mut HashMap<VarId, ColType>,
-    ) -> Result<HashMap<ColId, ColVal>> {
+    ) -> Result<(HashMap<ColId, ColVal>, Vec<Choice>)> {
         let schema = self.relation.schema();
+
+        for choice in self.choices.borrow().iter() {
+            for col_id in choice.keys().iter().chain(choice.vals()) {
+                if schema.get_col(col_id).is_none() {
+                    return error(Error::UnrecognizedColumnBinding(
+                        self.relation.id(),
+                        col_id.clone(),
+                    ));
+                }
+            }
+
+            if let Some(col_id) = choice.keys().iter().find(|k| choice.vals().contains(k)) {
+                return error(Error::ConflictingChoiceColumn(
+                    self.relation.id(),
+                    col_id.clone(),
+                ));
+            }
+        }
+
         let mut cols = HashMap::default();
 
         for (col_id, col_val) in self.bindings.into_inner() {
@@ -44,7 +65,7 @@ impl RuleHeadBuilder {
 
             match &col_val {
                 ColVal::Lit(val) => {
-                    if col.col_type().check(val).is_err() {
+                    if col.check(val).is_err() {
                         return error(Error::ColumnValueTypeConflict(
                             self.relation.id(),
                             col_id,
@@ -55,9 +76,14 @@ impl RuleHeadBuilder {
                 }
                 ColVal::Binding(var) => {
                     if let Some(bound_type) = bound_vars.get(&var.id()) {
-                        if let Ok(unified_type) = bound_type
+                        if let Some(unified_type) = bound_type
                             .unify(col.col_type())
                             .and_then(|t| t.unify(&var.typ()))
+                            .ok()
+                            .filter(|_| {
+                                bound_type.widens_to(col.col_type())
+                                    && var.typ().widens_to(col.col_type())
+                            })
                         {
                             bound_vars.insert(var.id(), unified_type);
                         } else {
@@ -81,13 +107,19 @@ impl RuleHeadBuilder {
             cols.insert(col_id, col_val);
         }
 
-        for col_id in self.relation.schema().cols().keys() {
-            if !cols.contains_key(col_id) {
-                return error(Error::ColumnMissing(self.relation.id(), *col_id));
+        for (col_id, col) in self.relation.schema().cols() {
+            if cols.contains_key(col_id) {
+                continue;
+            }
+
+            if !col.is_optional() {
+                return error(Error::ColumnMissing(self.relation.id(), col_id.clone()));
             }
+
+            cols.insert(col_id.clone(), ColVal::Lit(Val::Null));
         }
 
-        Ok(cols)
+        Ok((cols, self.choices.into_inner()))
     }
 
     pub fn set<S, T>(&self, id: S, value: T) -> Result<()>
@@ -122,4 +154,22 @@ impl RuleHeadBuilder {
 
         Ok(())
     }
+
+    /// Declares that the values of the key columns determine those of the others, so
+    /// that the rule derives a single fact for each key: the least, where several are
+    /// derived together, and otherwise the first.
+    pub fn choose<K, V>(&self, keys: K, vals: V) -> Result<()>
+    where
+        K: IntoIterator,
+        K::Item: AsRef<str>,
+        V: IntoIterator,
+        V::Item: AsRef<str>,
+    {
+        let keys = keys.into_iter().map(ColId::new).collect();
+        let vals = vals.into_iter().map(ColId::new).collect();
+
+        self.choices.borrow_mut().push(Choice::new(keys, vals));
+
+        Ok(())
+    }
 }
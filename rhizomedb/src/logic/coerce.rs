@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+
+use crate::{
+    col_val::ColVal,
+    id::{ColId, RelationId},
+    types::{ColType, Type},
+    value::Val,
+    var::Var,
+};
+
+use super::{
+    ast::{
+        Aggregation, BodyTerm, Clause, Declaration, Fact, Negation, Program, RelPredicate, Rule,
+    },
+    rewrite::Rewrite,
+};
+
+/// Widens the literals in a program to the types of the columns they're bound to,
+/// and records where values are widened between variables and columns.
+pub(crate) fn coerce(program: &Program) -> Program {
+    let mut coercions = Coercions::default();
+
+    let clauses = program
+        .clauses()
+        .iter()
+        .map(|clause| match clause {
+            Clause::Fact(fact) => {
+                let declaration = declaration(program, fact.head());
+                let args = fact
+                    .args()
+                    .iter()
+                    .map(|(col_id, val)| {
+                        (
+                            col_id.clone(),
+                            coercions.lit(declaration, col_id.clone(), val.clone()),
+                        )
+                    })
+                    .collect();
+
+                Clause::Fact(Fact::new(fact.head(), args))
+            }
+            Clause::Rule(rule) => Clause::Rule(coercions.rule(program, rule)),
+        })
+        .collect();
+
+    let mut rewrites = program.rewrites().to_vec();
+    rewrites.extend(coercions.into_rewrites());
+
+    Program::new(program.declarations().to_vec(), clauses).with_rewrites(rewrites)
+}
+
+fn declaration(program: &Program, id: RelationId) -> Option<&Declaration> {
+    program
+        .declarations()
+        .iter()
+        .find(|declaration| declaration.id() == id)
+        .map(AsRef::as_ref)
+}
+
+fn col_type(declaration: Option<&Declaration>, col_id: ColId) -> ColType {
+    declaration
+        .and_then(|declaration| {
+            declaration
+                .schema()
+                .get_col(&col_id)
+                .map(|col| *col.col_type())
+        })
+        .unwrap_or(ColType::Any)
+}
+
+#[derive(Default)]
+struct Coercions(Vec<(RelationId, ColId, Type, Type)>);
+
+impl Coercions {
+    fn rule(&mut self, program: &Program, rule: &Rule) -> Rule {
+        let head = declaration(program, rule.head());
+        let args = self.args(head, rule.args(), Direction::Into);
+
+        let body = rule
+            .body()
+            .iter()
+            .map(|term| match term {
+                BodyTerm::RelPredicate(inner) => BodyTerm::RelPredicate(self.search(inner)),
+                BodyTerm::Optional(inner) => BodyTerm::Optional(self.search(inner)),
+                BodyTerm::Negation(inner) => {
+                    let relation = inner.relation();
+                    let args = self.args(Some(&relation), inner.args(), Direction::Into);
+
+                    BodyTerm::Negation(Negation::new(relation, args))
+                }
+                BodyTerm::Aggregation(inner) => {
+                    let relation = inner.relation();
+                    let args = inner
+                        .group_by_cols()
+                        .iter()
+                        .map(|(col_id, col_val)| {
+                            let direction = match col_val {
+                                ColVal::Binding(var) if inner.vars().contains(var) => {
+                                    Direction::From
+                                }
+                                _ => Direction::Into,
+                            };
+
+                            (
+                                col_id.clone(),
+                                self.col_val(Some(&relation), col_id.clone(), col_val, direction),
+                            )
+                        })
+                        .collect();
+
+                    BodyTerm::Aggregation(Aggregation::new(
+                        inner.targets().to_vec(),
+                        inner.vars().clone(),
+                        relation,
+                        args,
+                        inner.agg(),
+                    ))
+                }
+                BodyTerm::VarPredicate(_) | BodyTerm::Cast(_) => term.clone(),
+            })
+            .collect();
+
+        Rule::new(rule.head(), args, body).with_choices(rule.choices().to_vec())
+    }
+
+    fn search(&mut self, predicate: &RelPredicate) -> RelPredicate {
+        let relation = predicate.relation();
+        let args = self.args(Some(&relation), predicate.args(), Direction::From);
+
+        RelPredicate::new(relation, *predicate.cid(), args)
+    }
+
+    fn args(
+        &mut self,
+        declaration: Option<&Declaration>,
+        args: &HashMap<ColId, ColVal>,
+        direction: Direction,
+    ) -> HashMap<ColId, ColVal> {
+        args.iter()
+            .map(|(col_id, col_val)| {
+                (
+                    col_id.clone(),
+                    self.col_val(declaration, col_id.clone(), col_val, direction),
+                )
+            })
+            .collect()
+    }
+
+    fn col_val(
+        &mut self,
+        declaration: Option<&Declaration>,
+        col_id: ColId,
+        col_val: &ColVal,
+        direction: Direction,
+    ) -> ColVal {
+        match col_val {
+            ColVal::Lit(val) => ColVal::Lit(self.lit(declaration, col_id, val.clone())),
+            ColVal::Binding(var) => {
+                self.var(declaration, col_id, var, direction);
+
+                ColVal::Binding(*var)
+            }
+        }
+    }
+
+    fn lit(&mut self, declaration: Option<&Declaration>, col_id: ColId, val: Val) -> Val {
+        let (Some(declaration), ColType::Type(to)) =
+            (declaration, col_type(declaration, col_id.clone()))
+        else {
+            return val;
+        };
+
+        let from = val.type_of();
+
+        match val.clone().widen(to) {
+            Some(widened) if from != to => {
+                self.0.push((declaration.id(), col_id, from, to));
+
+                widened
+            }
+            _ => val,
+        }
+    }
+
+    fn var(
+        &mut self,
+        declaration: Option<&Declaration>,
+        col_id: ColId,
+        var: &Var,
+        direction: Direction,
+    ) {
+        let (Some(declaration), ColType::Type(col), ColType::Type(typ)) = (
+            declaration,
+            col_type(declaration, col_id.clone()),
+            var.typ(),
+        ) else {
+            return;
+        };
+
+        let (from, to) = match direction {
+            Direction::From => (col, typ),
+            Direction::Into => (typ, col),
+        };
+
+        if from != to {
+            self.0.push((declaration.id(), col_id, from, to));
+        }
+    }
+
+    fn into_rewrites(mut self) -> Vec<Rewrite> {
+        self.0.sort_by_key(|(relation, col, from, to)| {
+            (
+                relation.to_string(),
+                col.to_string(),
+                from.to_string(),
+                to.to_string(),
+            )
+        });
+        self.0.dedup();
+
+        self.0
+            .into_iter()
+            .map(|(relation, col, from, to)| Rewrite::Coerced {
+                relation,
+                col,
+                from,
+                to,
+            })
+            .collect()
+    }
+}
+
+/// Whether values flow from a column into a variable, or into a column.
+#[derive(Clone, Copy)]
+enum Direction {
+    From,
+    Into,
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        assert_compile_err, assert_derives, error::Error, logic::builder::explain, tuple::Tuple,
+        types::Type, ProgramBuilder,
+    };
+
+    fn numbers(p: &ProgramBuilder) -> Result<()> {
+        p.output("small", |h| h.column::<i32>("n"))?;
+        p.output("big", |h| h.column::<i64>("n"))?;
+        p.output("both", |h| h.column::<i64>("n"))?;
+
+        p.fact("small", |f| f.bind((("n", 1),)))?;
+        p.fact("small", |f| f.bind((("n", 2),)))?;
+        p.fact("big", |f| f.bind((("n", 2_i64),)))?;
+        p.fact("big", |f| f.bind((("n", 3),)))?;
+
+        p.rule::<(i64,)>("both", &|h, b, (n,)| {
+            h.bind((("n", n),))?;
+            b.search("small", (("n", n),))?;
+            b.search("big", (("n", n),))?;
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_coerce() {
+        assert_derives!(
+            |p| {
+                numbers(&p)?;
+
+                Ok(p)
+            },
+            [
+                (
+                    "big",
+                    vec![
+                        Tuple::new("big", [("n", 2_i64)], None),
+                        Tuple::new("big", [("n", 3_i64)], None),
+                    ]
+                ),
+                ("both", vec![Tuple::new("both", [("n", 2_i64)], None)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cast() {
+        assert_derives!(
+            |p| {
+                numbers(&p)?;
+
+                p.output("wide", |h| h.column::<i64>("n"))?;
+
+                p.rule::<(i32, i64)>("wide", &|h, b, (x, y)| {
+                    h.bind((("n", y),))?;
+                    b.search("small", (("n", x),))?;
+                    b.cast(x, y)?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [(
+                "wide",
+                [
+                    Tuple::new("wide", [("n", 1_i64)], None),
+                    Tuple::new("wide", [("n", 2_i64)], None),
+                ]
+            )]
+        );
+
+        assert_compile_err!(&Error::TypeMismatch(Type::S32, Type::S64), |p| {
+            numbers(&p)?;
+
+            p.output("narrow", |h| h.column::<i32>("n"))?;
+
+            p.rule::<(i64, i32)>("narrow", &|h, b, (x, y)| {
+                h.bind((("n", y),))?;
+                b.search("big", (("n", x),))?;
+                b.cast(x, y)?;
+
+                Ok(())
+            })?;
+
+            Ok(p)
+        });
+    }
+
+    #[test]
+    fn test_explain_coerced() -> Result<()> {
+        let explained = explain(|p| {
+            numbers(&p)?;
+
+            Ok(p)
+        })?;
+
+        assert_eq!(
+            vec![
+                "// coerced big.n from s32 to s64",
+                "// coerced small.n from s32 to s64",
+                "",
+            ],
+            explained.lines().take(3).collect::<Vec<_>>()
+        );
+        assert!(explained.contains("as s64)"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,447 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt::Write,
+    sync::Arc,
+};
+
+use anyhow::Result;
+use petgraph::{
+    graph::{DiGraph, NodeIndex},
+    visit::EdgeRef,
+    Direction,
+};
+
+use crate::{error::Error, id::RelationId, relation::Source};
+
+use super::ast::{clause::Clause, program::Program, BodyTerm, Declaration, Rule};
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub(crate) enum Node {
+    Edb(RelationId),
+    Idb(RelationId),
+}
+
+impl Node {
+    pub(crate) fn id(&self) -> RelationId {
+        match self {
+            Node::Edb(id) => id.clone(),
+            Node::Idb(id) => id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+enum Edge {
+    FromEDB(RelationId, RelationId, Polarity),
+    FromIDB(RelationId, RelationId, Polarity),
+}
+
+impl Edge {
+    fn from(&self) -> Node {
+        match self {
+            Edge::FromEDB(from, _, _) => Node::Edb(from.clone()),
+            Edge::FromIDB(from, _, _) => Node::Idb(from.clone()),
+        }
+    }
+
+    fn to(&self) -> Node {
+        match self {
+            Edge::FromEDB(_, to, _) => Node::Idb(to.clone()),
+            Edge::FromIDB(_, to, _) => Node::Idb(to.clone()),
+        }
+    }
+
+    fn polarity(&self) -> Polarity {
+        match *self {
+            Edge::FromEDB(_, _, polarity) => polarity,
+            Edge::FromIDB(_, _, polarity) => polarity,
+        }
+    }
+}
+
+/// Whether a rule depends on a relation positively, or through negation or aggregation.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Polarity {
+    Positive,
+    Negative,
+}
+
+impl Polarity {
+    pub fn is_positive(&self) -> bool {
+        matches!(self, Polarity::Positive)
+    }
+
+    pub fn is_negative(&self) -> bool {
+        matches!(self, Polarity::Negative)
+    }
+}
+
+/// The predicate dependency graph of a program, with its relations grouped into strata.
+#[derive(Debug, Clone)]
+pub struct DependencyGraph {
+    graph: DiGraph<Node, Polarity>,
+    sccs: Vec<Vec<NodeIndex>>,
+}
+
+impl DependencyGraph {
+    pub(crate) fn new(program: &Program) -> Result<Self> {
+        let mut graph = DiGraph::<Node, Polarity>::default();
+        let mut nodes = im::HashMap::<Node, NodeIndex>::default();
+
+        for clause in program.clauses() {
+            nodes
+                .entry(Node::Idb(clause.head()))
+                .or_insert_with(|| graph.add_node(Node::Idb(clause.head())));
+
+            for dependency in clause_depends_on(clause) {
+                nodes
+                    .entry(dependency.to())
+                    .or_insert_with(|| graph.add_node(dependency.to()));
+
+                nodes
+                    .entry(dependency.from())
+                    .or_insert_with(|| graph.add_node(dependency.from()));
+
+                let to = nodes.get(&dependency.to()).ok_or_else(|| {
+                    Error::InternalRhizomeError("dependency not found".to_owned())
+                })?;
+
+                let from = nodes.get(&dependency.from()).ok_or_else(|| {
+                    Error::InternalRhizomeError("dependency not found".to_owned())
+                })?;
+
+                graph.add_edge(*from, *to, dependency.polarity());
+            }
+        }
+
+        // Kosaraju's algorithm yields the SCCs in reverse topological order
+        let sccs = petgraph::algo::kosaraju_scc(&graph)
+            .into_iter()
+            .rev()
+            .collect();
+
+        Ok(Self { graph, sccs })
+    }
+
+    pub(crate) fn node(&self, idx: NodeIndex) -> Option<&Node> {
+        self.graph.node_weight(idx)
+    }
+
+    /// The SCCs that contain at least one output relation, in evaluation order.
+    pub(crate) fn strata_nodes(&self) -> impl Iterator<Item = &[NodeIndex]> + '_ {
+        self.sccs
+            .iter()
+            .filter(|scc| {
+                scc.iter()
+                    .any(|idx| matches!(self.node(*idx), Some(Node::Idb(_))))
+            })
+            .map(|scc| scc.as_slice())
+    }
+
+    pub(crate) fn is_recursive(&self, scc: &[NodeIndex]) -> bool {
+        scc.len() > 1 || self.graph.contains_edge(scc[0], scc[0])
+    }
+
+    pub(crate) fn is_stratifiable(&self) -> bool {
+        self.sccs.iter().all(|scc| {
+            scc.iter().all(|node| {
+                self.graph
+                    .edges_directed(*node, Direction::Outgoing)
+                    .all(|edge| !edge.weight().is_negative() || !scc.contains(&edge.target()))
+            })
+        })
+    }
+
+    /// The input relations that rules in the program depend on.
+    pub fn inputs(&self) -> Vec<String> {
+        self.node_names(|node| matches!(node, Node::Edb(_)))
+    }
+
+    /// The output relations that appear in the program.
+    pub fn outputs(&self) -> Vec<String> {
+        self.node_names(|node| matches!(node, Node::Idb(_)))
+    }
+
+    /// The distinct dependencies between relations, as `(from, to, polarity)`.
+    pub fn edges(&self) -> Vec<(String, String, Polarity)> {
+        self.graph
+            .edge_references()
+            .filter_map(|edge| {
+                let from = self.node(edge.source())?.id().resolve();
+                let to = self.node(edge.target())?.id().resolve();
+
+                Some((from, to, *edge.weight()))
+            })
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// The output relations of each stratum, in evaluation order.
+    pub fn strata(&self) -> Vec<Vec<String>> {
+        self.strata_nodes().map(|scc| self.scc_names(scc)).collect()
+    }
+
+    /// The strata whose relations are mutually recursive, in evaluation order.
+    pub fn recursive_strata(&self) -> Vec<Vec<String>> {
+        self.strata_nodes()
+            .filter(|scc| self.is_recursive(scc))
+            .map(|scc| self.scc_names(scc))
+            .collect()
+    }
+
+    /// Renders the graph in the Graphviz DOT language.
+    ///
+    /// Each stratum is drawn as a cluster, with recursive strata filled in,
+    /// input relations are drawn as boxes, and negative dependencies are drawn
+    /// as dashed edges. Negative dependencies within a stratum, which make a
+    /// program unstratifiable, are drawn in red.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+
+        // Writing into a String is infallible
+        let _ = self.write_dot(&mut dot);
+
+        dot
+    }
+
+    fn write_dot(&self, w: &mut impl Write) -> std::fmt::Result {
+        writeln!(w, "digraph {{")?;
+
+        for input in self.inputs() {
+            writeln!(w, "    {} [shape = box];", quote(&input))?;
+        }
+
+        for (i, scc) in self.strata_nodes().enumerate() {
+            writeln!(w, "    subgraph cluster_{i} {{")?;
+
+            if self.is_recursive(scc) {
+                writeln!(w, "        label = \"stratum {i} (recursive)\";")?;
+                writeln!(w, "        style = filled;")?;
+                writeln!(w, "        fillcolor = lightgrey;")?;
+            } else {
+                writeln!(w, "        label = \"stratum {i}\";")?;
+            }
+
+            for output in self.scc_names(scc) {
+                writeln!(w, "        {};", quote(&output))?;
+            }
+
+            writeln!(w, "    }}")?;
+        }
+
+        let cyclic = self.cyclic_negations();
+
+        for (from, to, polarity) in self.edges() {
+            let attrs = match polarity {
+                Polarity::Positive => "",
+                Polarity::Negative if cyclic.contains(&(from.clone(), to.clone())) => {
+                    " [style = dashed, color = red]"
+                }
+                Polarity::Negative => " [style = dashed]",
+            };
+
+            writeln!(w, "    {} -> {}{};", quote(&from), quote(&to), attrs)?;
+        }
+
+        writeln!(w, "}}")
+    }
+
+    fn cyclic_negations(&self) -> HashSet<(String, String)> {
+        let mut result = HashSet::default();
+
+        for scc in &self.sccs {
+            for node in scc {
+                for edge in self.graph.edges_directed(*node, Direction::Outgoing) {
+                    if edge.weight().is_negative() && scc.contains(&edge.target()) {
+                        if let (Some(from), Some(to)) =
+                            (self.node(edge.source()), self.node(edge.target()))
+                        {
+                            result.insert((from.id().resolve(), to.id().resolve()));
+                        }
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    fn node_names<F>(&self, f: F) -> Vec<String>
+    where
+        F: Fn(&Node) -> bool,
+    {
+        self.graph
+            .node_weights()
+            .filter(|node| f(node))
+            .map(|node| node.id().resolve())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    fn scc_names(&self, scc: &[NodeIndex]) -> Vec<String> {
+        scc.iter()
+            .filter_map(|idx| match self.node(*idx) {
+                Some(Node::Idb(id)) => Some(id.resolve()),
+                _ => None,
+            })
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+fn quote(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn clause_depends_on(clause: &Clause) -> Vec<Edge> {
+    match clause {
+        Clause::Fact(_) => vec![],
+        Clause::Rule(rule) => rule_depends_on(rule),
+    }
+}
+
+fn rule_depends_on(rule: &Rule) -> Vec<Edge> {
+    let mut edges = Vec::default();
+
+    for term in rule.body() {
+        if let Some(polarity) = term_polarity(term) {
+            for dependency in term_depends_on(term) {
+                let edge = match dependency.source() {
+                    Source::Edb => Edge::FromEDB(dependency.id(), rule.head(), polarity),
+                    Source::Idb => Edge::FromIDB(dependency.id(), rule.head(), polarity),
+                };
+
+                edges.push(edge);
+            }
+        }
+    }
+
+    edges
+}
+
+fn term_polarity(term: &BodyTerm) -> Option<Polarity> {
+    match term {
+        BodyTerm::RelPredicate(_) => Some(Polarity::Positive),
+        BodyTerm::Optional(_) => Some(Polarity::Negative),
+        BodyTerm::Negation(_) => Some(Polarity::Negative),
+        BodyTerm::VarPredicate(_) | BodyTerm::Cast(_) => None,
+        BodyTerm::Aggregation(_) => Some(Polarity::Negative),
+    }
+}
+
+fn term_depends_on(term: &BodyTerm) -> Vec<Arc<Declaration>> {
+    match term {
+        BodyTerm::RelPredicate(inner) => vec![inner.relation()],
+        BodyTerm::Optional(inner) => vec![inner.relation()],
+        BodyTerm::Negation(inner) => vec![inner.relation()],
+        BodyTerm::VarPredicate(_) | BodyTerm::Cast(_) => vec![],
+        BodyTerm::Aggregation(inner) => vec![inner.relation()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    use crate::logic::dependency_graph;
+
+    #[test]
+    fn test_dependency_graph() -> Result<()> {
+        let graph = dependency_graph(|p| {
+            p.input("r", |h| h.column::<i32>("r0").column::<i32>("r1"))?;
+
+            p.output("v", |h| h.column::<i32>("v"))?;
+            p.output("t", |h| h.column::<i32>("t0").column::<i32>("t1"))?;
+            p.output("tc", |h| h.column::<i32>("tc0").column::<i32>("tc1"))?;
+
+            p.rule::<(i32, i32)>("v", &|h, b, (x, y)| {
+                h.bind((("v", x),))?;
+                b.search("r", (("r0", x), ("r1", y)))?;
+
+                Ok(())
+            })?;
+
+            p.rule::<(i32, i32)>("t", &|h, b, (x, y)| {
+                h.bind((("t0", x), ("t1", y)))?;
+                b.search("r", (("r0", x), ("r1", y)))?;
+
+                Ok(())
+            })?;
+
+            p.rule::<(i32, i32, i32)>("t", &|h, b, (x, y, z)| {
+                h.bind((("t0", x), ("t1", y)))?;
+
+                b.search("t", (("t0", x), ("t1", z)))?;
+                b.search("r", (("r0", z), ("r1", y)))?;
+
+                Ok(())
+            })?;
+
+            p.rule::<(i32, i32)>("tc", &|h, b, (x, y)| {
+                h.bind((("tc0", x), ("tc1", y)))?;
+
+                b.search("v", (("v", x),))?;
+                b.search("v", (("v", y),))?;
+                b.except("t", (("t0", x), ("t1", y)))?;
+
+                Ok(())
+            })?;
+
+            Ok(p)
+        })?;
+
+        assert_eq!(vec!["r"], graph.inputs());
+        assert_eq!(vec!["t", "tc", "v"], graph.outputs());
+        assert_eq!(vec![vec!["t"]], graph.recursive_strata());
+
+        let strata = graph.strata();
+        let position = |id: &str| strata.iter().position(|s| s == &vec![id.to_owned()]);
+
+        assert_eq!(3, strata.len());
+        assert!(position("t") < position("tc"));
+        assert!(position("v") < position("tc"));
+
+        let dot = graph.to_dot();
+
+        assert!(dot.starts_with("digraph {\n"));
+        assert!(dot.contains("    \"r\" [shape = box];\n"));
+        assert!(dot.contains("label = \"stratum 0\";"));
+        assert!(dot.contains("(recursive)\";"));
+        assert!(dot.contains("    \"r\" -> \"t\";\n"));
+        assert!(dot.contains("    \"t\" -> \"t\";\n"));
+        assert!(dot.contains("    \"t\" -> \"tc\" [style = dashed];\n"));
+        assert!(dot.ends_with("}\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dependency_graph_unstratifiable() -> Result<()> {
+        let graph = dependency_graph(|p| {
+            p.input("q", |h| h.column::<i32>("x"))?;
+            p.output("p", |h| h.column::<i32>("x"))?;
+
+            p.rule::<(i32,)>("p", &|h, b, (x,)| {
+                h.bind((("x", x),))?;
+
+                b.search("q", (("x", x),))?;
+                b.except("p", (("x", x),))?;
+
+                Ok(())
+            })?;
+
+            Ok(p)
+        })?;
+
+        assert!(!graph.is_stratifiable());
+        assert!(graph
+            .to_dot()
+            .contains("    \"p\" -> \"p\" [style = dashed, color = red];\n"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::{
+    col_val::ColVal,
+    error::{error, Error},
+    id::{ColId, VarId},
+    types::{ColType, Type},
+    var::Var,
+};
+
+use super::ast::{
+    Aggregation, BodyTerm, Cast, CidValue, Clause, Declaration, Negation, Program, RelPredicate,
+    Rule, VarPredicate,
+};
+
+/// Infers concrete types for the `Any` typed variables of each rule, from the
+/// columns, predicates and aggregates they're bound to, so that later passes treat
+/// them as if they'd been declared with those types.
+pub(crate) fn infer(program: &Program) -> Result<Program> {
+    let clauses = program
+        .clauses()
+        .iter()
+        .map(|clause| match clause {
+            Clause::Fact(fact) => Ok(Clause::Fact(fact.clone())),
+            Clause::Rule(rule) => {
+                let head = program
+                    .declarations()
+                    .iter()
+                    .find(|declaration| declaration.id() == rule.head());
+
+                let types = infer_rule(rule, head.map(AsRef::as_ref))?;
+
+                Ok(Clause::Rule(retype(rule, &types)))
+            }
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Program::new(program.declarations().to_vec(), clauses)
+        .with_rewrites(program.rewrites().to_vec()))
+}
+
+/// Infers the type of each `Any` typed variable as the narrowest type that the
+/// values bound to it widen to, then checks that it widens to the type of each
+/// column, predicate or aggregate that the variable is passed to.
+fn infer_rule(rule: &Rule, head: Option<&Declaration>) -> Result<HashMap<VarId, Type>> {
+    let mut types = HashMap::default();
+
+    // Casts propagate types between variables, so iterate to a fixpoint
+    loop {
+        let mut changed = false;
+
+        for (var, typ) in sources(rule, &types) {
+            changed |= narrow(&mut types, var, typ)?;
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    for (var, typ) in sinks(rule, head) {
+        if let Some(inferred) = types.get(&var.id()) {
+            if !inferred.widens_to(&typ) {
+                return error(Error::VarTypeConflict(var.id(), *inferred, typ));
+            }
+        }
+    }
+
+    Ok(types)
+}
+
+fn narrow(types: &mut HashMap<VarId, Type>, var: Var, typ: Type) -> Result<bool> {
+    if var.typ() != ColType::Any {
+        return Ok(false);
+    }
+
+    let Some(inferred) = types.get(&var.id()).copied() else {
+        types.insert(var.id(), typ);
+
+        return Ok(true);
+    };
+
+    let unified = inferred
+        .unify(&typ)
+        .map_err(|_| Error::VarTypeConflict(var.id(), inferred, typ))?;
+
+    types.insert(var.id(), unified);
+
+    Ok(unified != inferred)
+}
+
+fn col_type(declaration: &Declaration, col_id: &ColId) -> Option<Type> {
+    match declaration
+        .schema()
+        .get_col(col_id)
+        .map(|col| *col.col_type())
+    {
+        Some(ColType::Type(typ)) => Some(typ),
+        _ => None,
+    }
+}
+
+fn typed(vars: &[Var], types: Vec<ColType>) -> impl Iterator<Item = (Var, Type)> + '_ {
+    vars.iter().zip(types).filter_map(|(var, typ)| match typ {
+        ColType::Type(typ) => Some((*var, typ)),
+        ColType::Any => None,
+    })
+}
+
+/// The types of the values that are bound to variables.
+fn sources(rule: &Rule, types: &HashMap<VarId, Type>) -> Vec<(Var, Type)> {
+    let mut sources = Vec::default();
+
+    for term in rule.body() {
+        match term {
+            BodyTerm::RelPredicate(inner) | BodyTerm::Optional(inner) => {
+                let relation = inner.relation();
+
+                if let Some(CidValue::Var(var)) = inner.cid() {
+                    sources.push((*var, Type::Cid));
+                }
+
+                for (col_id, col_val) in inner.args() {
+                    if let (ColVal::Binding(var), Some(typ)) =
+                        (col_val, col_type(&relation, col_id))
+                    {
+                        sources.push((*var, typ));
+                    }
+                }
+            }
+            BodyTerm::Aggregation(inner) => {
+                let relation = inner.relation();
+
+                for (col_id, col_val) in inner.group_by_cols() {
+                    match (col_val, col_type(&relation, col_id)) {
+                        (ColVal::Binding(var), Some(typ)) if inner.vars().contains(var) => {
+                            sources.push((*var, typ));
+                        }
+                        _ => (),
+                    }
+                }
+
+                sources.extend(typed(inner.targets(), inner.agg().output_types()));
+            }
+            BodyTerm::Cast(inner) => {
+                let typ = match inner.from().typ() {
+                    ColType::Type(typ) => Some(typ),
+                    ColType::Any => types.get(&inner.from().id()).copied(),
+                };
+
+                if let Some(typ) = typ {
+                    sources.push((*inner.to(), typ));
+                }
+            }
+            BodyTerm::Negation(_) | BodyTerm::VarPredicate(_) => (),
+        }
+    }
+
+    sources
+}
+
+/// The types that the values of variables are passed to.
+fn sinks(rule: &Rule, head: Option<&Declaration>) -> Vec<(Var, Type)> {
+    let mut sinks = Vec::default();
+
+    let mut bind = |declaration: &Declaration, args: &HashMap<ColId, ColVal>| {
+        for (col_id, col_val) in args {
+            if let (ColVal::Binding(var), Some(typ)) = (col_val, col_type(declaration, col_id)) {
+                sinks.push((*var, typ));
+            }
+        }
+    };
+
+    if let Some(head) = head {
+        bind(head, rule.args());
+    }
+
+    for term in rule.body() {
+        match term {
+            BodyTerm::Negation(inner) => bind(&inner.relation(), inner.args()),
+            BodyTerm::Aggregation(inner) => {
+                let group_by = inner
+                    .group_by_cols()
+                    .iter()
+                    .filter(|(_, col_val)| match col_val {
+                        ColVal::Binding(var) => !inner.vars().contains(var),
+                        ColVal::Lit(_) => false,
+                    })
+                    .map(|(col_id, col_val)| (col_id.clone(), col_val.clone()))
+                    .collect();
+
+                bind(&inner.relation(), &group_by);
+            }
+            _ => (),
+        }
+    }
+
+    for term in rule.body() {
+        match term {
+            BodyTerm::VarPredicate(inner) => {
+                sinks.extend(typed(inner.vars(), inner.f().arg_types()))
+            }
+            BodyTerm::Aggregation(inner) => {
+                sinks.extend(typed(inner.vars(), inner.agg().arg_types()))
+            }
+            BodyTerm::Cast(inner) => {
+                if let ColType::Type(typ) = inner.to().typ() {
+                    sinks.push((*inner.from(), typ));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    sinks
+}
+
+/// Rewrites a rule with its `Any` typed variables given their inferred types.
+fn retype(rule: &Rule, types: &HashMap<VarId, Type>) -> Rule {
+    let var = |var: &Var| match types.get(&var.id()) {
+        Some(typ) if var.typ() == ColType::Any => var.with_typ(ColType::Type(*typ)),
+        _ => *var,
+    };
+
+    let args = |args: &HashMap<ColId, ColVal>| -> HashMap<ColId, ColVal> {
+        args.iter()
+            .map(|(col_id, col_val)| {
+                let col_val = match col_val {
+                    ColVal::Binding(inner) => ColVal::Binding(var(inner)),
+                    ColVal::Lit(_) => col_val.clone(),
+                };
+
+                (col_id.clone(), col_val)
+            })
+            .collect()
+    };
+
+    let search = |inner: &RelPredicate| {
+        let cid = match inner.cid() {
+            Some(CidValue::Var(inner)) => Some(CidValue::Var(var(inner))),
+            cid => *cid,
+        };
+
+        RelPredicate::new(inner.relation(), cid, args(inner.args()))
+    };
+
+    let body = rule
+        .body()
+        .iter()
+        .map(|term| match term {
+            BodyTerm::RelPredicate(inner) => BodyTerm::RelPredicate(search(inner)),
+            BodyTerm::Optional(inner) => BodyTerm::Optional(search(inner)),
+            BodyTerm::Negation(inner) => {
+                BodyTerm::Negation(Negation::new(inner.relation(), args(inner.args())))
+            }
+            BodyTerm::Aggregation(inner) => BodyTerm::Aggregation(Aggregation::new(
+                inner.targets().iter().map(var).collect(),
+                inner.vars().iter().map(var).collect(),
+                inner.relation(),
+                args(inner.group_by_cols()),
+                inner.agg(),
+            )),
+            BodyTerm::VarPredicate(inner) => BodyTerm::VarPredicate(VarPredicate::new(
+                inner.vars().iter().map(var).collect(),
+                inner.f(),
+            )),
+            BodyTerm::Cast(inner) => BodyTerm::Cast(Cast::new(var(inner.from()), var(inner.to()))),
+        })
+        .collect();
+
+    Rule::new(rule.head(), args(rule.args()), body).with_choices(rule.choices().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        assert_compile_err, assert_derives, error::Error, tuple::Tuple, types::Type, value::Any,
+    };
+
+    #[test]
+    fn test_infer() {
+        assert_derives!(
+            |p| {
+                p.output("small", |h| h.column::<i32>("n"))?;
+                p.output("big", |h| h.column::<i64>("n"))?;
+                p.output("both", |h| h.column::<Any>("n"))?;
+
+                p.fact("small", |f| f.bind((("n", 1),)))?;
+                p.fact("small", |f| f.bind((("n", 2),)))?;
+                p.fact("big", |f| f.bind((("n", 2_i64),)))?;
+
+                p.rule::<(Any,)>("both", &|h, b, (n,)| {
+                    h.bind((("n", n),))?;
+                    b.search("small", (("n", n),))?;
+                    b.search("big", (("n", n),))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [("both", [Tuple::new("both", [("n", 2_i64)], None)])]
+        );
+    }
+
+    #[test]
+    fn test_var_type_conflict() {
+        assert_compile_err!(
+            &Error::VarTypeConflict("x0".into(), Type::S32, Type::String),
+            |p| {
+                p.output("p", |h| h.column::<i32>("x"))?;
+                p.output("q", |h| h.column::<&str>("x"))?;
+                p.output("r", |h| h.column::<Any>("x"))?;
+
+                p.rule::<(Any,)>("r", &|h, b, (x,)| {
+                    h.bind((("x", x),))?;
+                    b.search("p", (("x", x),))?;
+                    b.search("q", (("x", x),))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            }
+        );
+
+        assert_compile_err!(
+            &Error::VarTypeConflict("x0".into(), Type::S64, Type::S32),
+            |p| {
+                p.output("p", |h| h.column::<i64>("x"))?;
+                p.output("q", |h| h.column::<i32>("x"))?;
+                p.output("r", |h| h.column::<i64>("x"))?;
+
+                p.rule::<(Any,)>("r", &|h, b, (x,)| {
+                    h.bind((("x", x),))?;
+                    b.search("p", (("x", x),))?;
+                    b.except("q", (("x", x),))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            }
+        );
+    }
+}
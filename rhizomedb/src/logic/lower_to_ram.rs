@@ -10,17 +10,19 @@ use crate::{
     error::{error, Error},
     id::{ColId, RelationId, VarId},
     ram::{
-        self, Aggregation, AliasId, ExitBuilder, Formula, Insert, Loop, Merge, Operation, Project,
-        Purge, Search, SinksBuilder, SourcesBuilder, Statement, Swap, Term,
+        self, Aggregation, AliasId, Choice, ExitBuilder, Formula, Insert, Loop, Merge, Operation,
+        Project, Purge, RelationSchema, Search, SinksBuilder, Slots, SourcesBuilder, Statement,
+        Swap, Term,
     },
     relation::{Relation, RelationKey, Source, Version},
+    types::ColType,
     value::Val,
 };
 
 use super::{
     ast::{
         cid_value::CidValue, declaration::Declaration, fact::Fact, program::Program, rule::Rule,
-        stratum::Stratum, Negation, RelPredicate, VarPredicate,
+        stratum::Stratum, Cast, Negation, RelPredicate, VarPredicate,
     },
     stratify::stratify,
 };
@@ -67,11 +69,11 @@ pub(crate) fn lower_to_ram(program: &Program) -> Result<ram::program::Program> {
             let id = input.id();
             let relation = Arc::clone(
                 relations
-                    .get(&(id, Version::Delta))
+                    .get(&(id.clone(), Version::Delta))
                     .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
             );
 
-            sources_builder.add_relation(id, relation);
+            sources_builder.add_relation(id, relation, relation_schema(input));
         }
 
         statements.push(Statement::Sources(sources_builder.finalize()));
@@ -89,19 +91,19 @@ pub(crate) fn lower_to_ram(program: &Program) -> Result<ram::program::Program> {
 
         let delta_relation = Arc::clone(
             relations
-                .get(&(id, Version::Delta))
+                .get(&(id.clone(), Version::Delta))
                 .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
         );
 
         let total_relation = Arc::clone(
             relations
-                .get(&(id, Version::Total))
+                .get(&(id.clone(), Version::Total))
                 .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
         );
 
         let merge = Merge::new(
-            (id, Version::Delta),
-            (id, Version::Total),
+            (id.clone(), Version::Delta),
+            (id.clone(), Version::Total),
             Arc::clone(&delta_relation),
             total_relation,
         );
@@ -119,7 +121,7 @@ pub(crate) fn lower_to_ram(program: &Program) -> Result<ram::program::Program> {
         let id = output.id();
         let relation = Arc::clone(
             relations
-                .get(&(id, Version::Delta))
+                .get(&(id.clone(), Version::Delta))
                 .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
         );
 
@@ -130,12 +132,24 @@ pub(crate) fn lower_to_ram(program: &Program) -> Result<ram::program::Program> {
     // during stratification?
     let is_monotonic = strata.len() == 1 && !strata[0].is_recursive();
     let statements = statements.into_iter().map(Arc::new).collect();
+    let outputs = outputs
+        .iter()
+        .filter(|output| !output.is_internal())
+        .map(|output| output.id())
+        .collect();
+
+    Ok(
+        ram::program::Program::new(is_monotonic, relations, statements)
+            .with_outputs(outputs)
+            .with_rewrites(program.rewrites().to_vec()),
+    )
+}
 
-    Ok(ram::program::Program::new(
-        is_monotonic,
-        relations,
-        statements,
-    ))
+fn is_internal(program: &Program, id: &RelationId) -> bool {
+    program
+        .declarations()
+        .iter()
+        .any(|declaration| declaration.id() == *id && declaration.is_internal())
 }
 
 pub(crate) fn lower_stratum_to_ram(
@@ -176,18 +190,18 @@ pub(crate) fn lower_stratum_to_ram(
         for relation in HashSet::<RelationId>::from_iter(static_rules.iter().map(|r| r.head())) {
             let from_relation = Arc::clone(
                 relations
-                    .get(&(relation, Version::Delta))
+                    .get(&(relation.clone(), Version::Delta))
                     .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
             );
 
             let into_relation = Arc::clone(
                 relations
-                    .get(&(relation, Version::Total))
+                    .get(&(relation.clone(), Version::Total))
                     .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
             );
 
             let merge = Merge::new(
-                (relation, Version::Delta),
+                (relation.clone(), Version::Delta),
                 (relation, Version::Total),
                 Arc::clone(&from_relation),
                 into_relation,
@@ -199,14 +213,14 @@ pub(crate) fn lower_stratum_to_ram(
         let mut loop_body: Vec<Statement> = Vec::default();
 
         // Purge new, computed during the last loop iteration
-        for &id in stratum.relations() {
+        for id in stratum.relations() {
             let relation = Arc::clone(
                 relations
-                    .get(&(id, Version::New))
+                    .get(&(id.clone(), Version::New))
                     .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
             );
 
-            let statement = Statement::Purge(Purge::new((id, Version::New), relation));
+            let statement = Statement::Purge(Purge::new((id.clone(), Version::New), relation));
 
             loop_body.push(statement);
         }
@@ -221,14 +235,18 @@ pub(crate) fn lower_stratum_to_ram(
         // Run sinks for the stratum
         let mut sinks_builder = SinksBuilder::default();
 
-        for &id in stratum.relations() {
+        for id in stratum.relations() {
+            if is_internal(program, id) {
+                continue;
+            }
+
             let relation = Arc::clone(
                 relations
-                    .get(&(id, Version::Delta))
+                    .get(&(id.clone(), Version::Delta))
                     .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
             );
 
-            sinks_builder.add_relation(id, relation);
+            sinks_builder.add_relation(id.clone(), relation);
         }
 
         if !sinks_builder.relations.is_empty() {
@@ -236,23 +254,23 @@ pub(crate) fn lower_stratum_to_ram(
         }
 
         // Merge delta into total
-        for &relation in stratum.relations() {
+        for relation in stratum.relations() {
             // Merge the output of the static rules into total
             let from_relation = Arc::clone(
                 relations
-                    .get(&(relation, Version::Delta))
+                    .get(&(relation.clone(), Version::Delta))
                     .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
             );
 
             let into_relation = Arc::clone(
                 relations
-                    .get(&(relation, Version::Total))
+                    .get(&(relation.clone(), Version::Total))
                     .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
             );
 
             let merge = Merge::new(
-                (relation, Version::Delta),
-                (relation, Version::Total),
+                (relation.clone(), Version::Delta),
+                (relation.clone(), Version::Total),
                 Arc::clone(&from_relation),
                 into_relation,
             );
@@ -263,36 +281,36 @@ pub(crate) fn lower_stratum_to_ram(
         // Exit the loop if all of the dynamic relations have reached a fixed point
         let mut exit_builder = ExitBuilder::default();
 
-        for &id in stratum.relations() {
+        for id in stratum.relations() {
             let relation = Arc::clone(
                 relations
-                    .get(&(id, Version::New))
+                    .get(&(id.clone(), Version::New))
                     .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
             );
 
-            exit_builder.add_relation((id, Version::New), relation);
+            exit_builder.add_relation((id.clone(), Version::New), relation);
         }
 
         loop_body.push(Statement::Exit(exit_builder.finalize()));
 
         //Swap new and delta
-        for &relation in stratum.relations() {
+        for relation in stratum.relations() {
             // Swap new and delta
             let left_relation = Arc::clone(
                 relations
-                    .get(&(relation, Version::New))
+                    .get(&(relation.clone(), Version::New))
                     .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
             );
 
             let right_relation = Arc::clone(
                 relations
-                    .get(&(relation, Version::Delta))
+                    .get(&(relation.clone(), Version::Delta))
                     .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
             );
 
             let swap = Swap::new(
-                (relation, Version::New),
-                (relation, Version::Delta),
+                (relation.clone(), Version::New),
+                (relation.clone(), Version::Delta),
                 left_relation,
                 right_relation,
             );
@@ -319,22 +337,22 @@ pub(crate) fn lower_stratum_to_ram(
         }
 
         // Merge rules from Delta into Total
-        for &relation in stratum.relations() {
+        for relation in stratum.relations() {
             let from_relation = Arc::clone(
                 relations
-                    .get(&(relation, Version::Delta))
+                    .get(&(relation.clone(), Version::Delta))
                     .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
             );
 
             let into_relation = Arc::clone(
                 relations
-                    .get(&(relation, Version::Total))
+                    .get(&(relation.clone(), Version::Total))
                     .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
             );
 
             let merge = Merge::new(
-                (relation, Version::Delta),
-                (relation, Version::Total),
+                (relation.clone(), Version::Delta),
+                (relation.clone(), Version::Total),
                 Arc::clone(&from_relation),
                 into_relation,
             );
@@ -345,14 +363,18 @@ pub(crate) fn lower_stratum_to_ram(
         // Run sinks for the stratum
         let mut sinks_builder = SinksBuilder::default();
 
-        for &id in stratum.relations() {
+        for id in stratum.relations() {
+            if is_internal(program, id) {
+                continue;
+            }
+
             let relation = Arc::clone(
                 relations
-                    .get(&(id, Version::Delta))
+                    .get(&(id.clone(), Version::Delta))
                     .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
             );
 
-            sinks_builder.add_relation(id, relation);
+            sinks_builder.add_relation(id.clone(), relation);
         }
 
         if !sinks_builder.relations.is_empty() {
@@ -367,7 +389,10 @@ pub(crate) fn lower_fact_to_ram(
     fact: &Fact,
     relations: &HashMap<RelationKey, Arc<RwLock<Box<dyn Relation>>>>,
 ) -> Result<Statement> {
-    let cols = fact.args().iter().map(|(k, v)| (*k, Term::Lit(v.clone())));
+    let cols = fact
+        .args()
+        .iter()
+        .map(|(k, v)| (k.clone(), Term::Lit(v.clone())));
 
     let relation = Arc::clone(
         relations
@@ -382,6 +407,7 @@ pub(crate) fn lower_fact_to_ram(
             vec![],
             relation,
         )),
+        0,
         true,
     )))
 }
@@ -389,26 +415,39 @@ pub(crate) fn lower_fact_to_ram(
 pub(crate) fn lower_rule_to_ram(
     rule: &Rule,
     _stratum: &Stratum<'_>,
-    _program: &Program,
+    program: &Program,
     version: Version,
     relations: &HashMap<RelationKey, Arc<RwLock<Box<dyn Relation>>>>,
 ) -> Result<Vec<Statement>> {
     let mut statements: Vec<Statement> = Vec::default();
 
+    let head = program
+        .declarations()
+        .iter()
+        .find(|declaration| declaration.id() == rule.head())
+        .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?;
+
     for rewrite in semi_naive_rewrites(rule) {
         let ordered = order_terms(rewrite);
+        let mut slots = Slots::default();
 
         let operation = lower_rule_body_to_ram(
             rule,
+            head,
             version,
             Default::default(),
             Default::default(),
             ordered.into_iter().rev().collect(),
             vec![],
+            &mut slots,
             relations,
         )?;
 
-        statements.push(Statement::Insert(Insert::new(operation, false)));
+        statements.push(Statement::Insert(Insert::new(
+            operation,
+            slots.len(),
+            false,
+        )));
     }
 
     Ok(statements)
@@ -417,15 +456,24 @@ pub(crate) fn lower_rule_to_ram(
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn lower_rule_body_to_ram(
     rule: &Rule,
+    head: &Declaration,
     version: Version,
     bindings: im::HashMap<VarId, Term>,
     mut next_alias: im::HashMap<RelationId, AliasId>,
     mut terms: Vec<SemiNaiveTerm>,
     mut formulae: Vec<Formula>,
+    slots: &mut Slots,
     relations: &HashMap<RelationKey, Arc<RwLock<Box<dyn Relation>>>>,
 ) -> Result<ram::Operation> {
     match terms.pop() {
-        Some(SemiNaiveTerm::RelPredicate(inner, inner_version)) => {
+        Some(term @ (SemiNaiveTerm::RelPredicate(_, _) | SemiNaiveTerm::Optional(_))) => {
+            // Optional relations belong to earlier strata, so are only ever searched in full
+            let (inner, inner_version, is_outer) = match term {
+                SemiNaiveTerm::RelPredicate(inner, inner_version) => (inner, inner_version, false),
+                SemiNaiveTerm::Optional(inner) => (inner, Version::Total, true),
+                _ => unreachable!(),
+            };
+
             let mut next_bindings = bindings.clone();
             let alias = next_alias.get(&inner.relation().id()).copied();
 
@@ -441,7 +489,7 @@ pub(crate) fn lower_rule_body_to_ram(
 
                 if let CidValue::Var(var) = cid {
                     if !bindings.contains_key(&var.id()) {
-                        next_bindings.insert(var.id(), Term::Cid(inner.relation().id(), alias));
+                        next_bindings.insert(var.id(), slots.cid(inner.relation().id(), alias));
                     }
                 }
             }
@@ -449,85 +497,58 @@ pub(crate) fn lower_rule_body_to_ram(
             for (col_id, col_val) in inner.args() {
                 if let ColVal::Binding(var) = col_val {
                     if !bindings.contains_key(&var.id()) {
-                        next_bindings
-                            .insert(var.id(), Term::Col(inner.relation().id(), alias, *col_id));
+                        let term = slots.col(inner.relation().id(), alias, col_id.clone());
+                        let from = col_type(&inner.relation(), col_id);
+
+                        next_bindings.insert(var.id(), coerce(term, from, var.typ()));
                     }
                 };
             }
 
-            if rule
-                .args()
-                .clone()
-                .into_values()
-                .filter_map(|v| match v {
-                    ColVal::Binding(v) => Some(v),
-                    _ => None,
-                })
-                .all(|v| next_bindings.contains_key(&v.id()))
-            {
-                let relation = relations
-                    .get(&(rule.head(), Version::Total))
-                    .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?
-                    .clone();
-
-                let mut cols = im::HashMap::<ColId, Term>::default();
-                for (&k, v) in rule.args() {
-                    let term = match v {
-                        ColVal::Lit(c) => Term::Lit(c.clone()),
-                        ColVal::Binding(v) => next_bindings
-                            .get(&v.id())
-                            .ok_or_else(|| {
-                                Error::InternalRhizomeError(format!(
-                                    "binding not found: {}",
-                                    v.id()
-                                ))
-                            })?
-                            .clone(),
-                    };
-
-                    cols.insert(k, term);
-                }
-
-                formulae.push(Formula::not_in(
-                    rule.head(),
-                    Version::Total,
-                    Vec::from_iter(cols.clone()),
-                    relation,
-                ))
+            if let Some(formula) = head_not_in(rule, head, &next_bindings, relations)? {
+                formulae.push(formula);
             }
 
+            // Only the formulae over the fact decide whether it's matched, while the
+            // others filter its bindings, so that an outer search never binds null
+            // for a fact it matched but filtered out
+            let mut when = Vec::default();
+
             if let Some(cid_val) = inner.cid() {
                 match cid_val {
                     CidValue::Cid(cid) => {
                         let formula = Formula::equality(
-                            Term::Cid(inner.relation().id(), alias),
+                            slots.cid(inner.relation().id(), alias),
                             Term::Lit(Val::Cid(*cid)),
                         );
 
-                        formulae.push(formula);
+                        when.push(formula);
                     }
                     CidValue::Var(var) => {
                         if let Some(bound) = bindings.get(&var.id()) {
                             let formula = Formula::equality(
-                                Term::Cid(inner.relation().id(), alias),
+                                slots.cid(inner.relation().id(), alias),
                                 bound.clone(),
                             );
 
-                            formulae.push(formula);
+                            when.push(formula);
                         }
                     }
                 }
             }
 
             let mut rel_bindings = Vec::default();
-            for (&col_id, col_val) in inner.args() {
+            for (col_id, col_val) in inner.args() {
                 match col_val {
                     ColVal::Lit(val) => {
-                        rel_bindings.push((col_id, Term::Lit(val.clone())));
+                        rel_bindings.push((col_id.clone(), Term::Lit(val.clone())));
                     }
                     ColVal::Binding(var) => {
                         if let Some(bound) = bindings.get(&var.id()) {
-                            rel_bindings.push((col_id, bound.clone()));
+                            let to = col_type(&inner.relation(), col_id);
+
+                            rel_bindings
+                                .push((col_id.clone(), coerce(bound.clone(), var.typ(), to)));
                         }
                     }
                 }
@@ -539,22 +560,35 @@ pub(crate) fn lower_rule_body_to_ram(
                     .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
             );
 
-            Ok(Operation::Search(Search::new(
+            let operation = lower_rule_body_to_ram(
+                rule,
+                head,
+                version,
+                next_bindings,
+                next_alias,
+                terms,
+                vec![],
+                slots,
+                relations,
+            )?;
+
+            let search = Search::new(
                 (inner.relation().id(), inner_version),
                 alias,
                 search_relation,
                 rel_bindings,
-                formulae,
-                lower_rule_body_to_ram(
-                    rule,
-                    version,
-                    next_bindings,
-                    next_alias,
-                    terms,
-                    vec![],
-                    relations,
-                )?,
-            )))
+                slots.cols_of(inner.relation().id(), alias),
+                slots.cid_of(inner.relation().id(), alias),
+                when,
+                operation,
+            )
+            .with_filter(formulae);
+
+            if is_outer {
+                Ok(Operation::Search(search.outer()))
+            } else {
+                Ok(Operation::Search(search))
+            }
         }
         Some(SemiNaiveTerm::VarPredicate(inner)) => {
             let formula = lower_var_predicate_to_ram(&inner, &bindings)?;
@@ -562,7 +596,7 @@ pub(crate) fn lower_rule_body_to_ram(
             formulae.push(formula);
 
             lower_rule_body_to_ram(
-                rule, version, bindings, next_alias, terms, formulae, relations,
+                rule, head, version, bindings, next_alias, terms, formulae, slots, relations,
             )
         }
         Some(SemiNaiveTerm::Negation(inner)) => {
@@ -575,7 +609,7 @@ pub(crate) fn lower_rule_body_to_ram(
             formulae.push(formula_total);
 
             lower_rule_body_to_ram(
-                rule, version, bindings, next_alias, terms, formulae, relations,
+                rule, head, version, bindings, next_alias, terms, formulae, slots, relations,
             )
         }
         Some(SemiNaiveTerm::Aggregation(inner)) => {
@@ -587,21 +621,44 @@ pub(crate) fn lower_rule_body_to_ram(
                     old.next()
                 });
 
-            let mut args = Vec::default();
+            let mut arg_terms: HashMap<VarId, Term> = HashMap::default();
             let mut group_by_cols = HashMap::default();
 
             for (col_id, col_val) in inner.group_by_cols() {
                 if let Some(term) = match col_val {
                     ColVal::Lit(lit) => Some(Term::Lit(lit.clone())),
                     ColVal::Binding(var) => {
+                        let col_type = col_type(&inner.relation(), col_id);
+
                         if let Some(term) = bindings.get(&var.id()) {
                             if inner.vars().contains(var) {
-                                args.push(term.clone());
+                                // The column is equal to the bound value, so read the
+                                // argument from the fact where that's lossless, which
+                                // lets the aggregation group the relation in one pass
+                                let is_lossless = col_type == var.typ()
+                                    || matches!(
+                                        (col_type, var.typ()),
+                                        (ColType::Type(from), ColType::Type(to))
+                                            if from.widens_to(&to)
+                                    );
+
+                                let arg = if is_lossless {
+                                    let col =
+                                        slots.col(inner.relation().id(), alias, col_id.clone());
+
+                                    coerce(col, col_type, var.typ())
+                                } else {
+                                    term.clone()
+                                };
+
+                                arg_terms.insert(var.id(), arg);
                             }
 
-                            Some(term.clone())
+                            Some(coerce(term.clone(), var.typ(), col_type))
                         } else if inner.vars().contains(var) {
-                            args.push(Term::Col(inner.relation().id(), alias, *col_id));
+                            let term = slots.col(inner.relation().id(), alias, col_id.clone());
+
+                            arg_terms.insert(var.id(), coerce(term, col_type, var.typ()));
 
                             None
                         } else {
@@ -609,109 +666,145 @@ pub(crate) fn lower_rule_body_to_ram(
                         }
                     }
                 } {
-                    group_by_cols.insert(*col_id, term);
+                    group_by_cols.insert(col_id.clone(), term);
                 }
             }
 
+            // Pass the arguments in the order the aggregate declares them
+            let mut args = Vec::default();
+
+            for var in inner.vars() {
+                let term = arg_terms.get(&var.id()).ok_or_else(|| {
+                    Error::InternalRhizomeError(format!(
+                        "aggregate argument not bound: {}",
+                        var.id()
+                    ))
+                })?;
+
+                args.push(term.clone());
+            }
+
             let aggregation_relation = Arc::clone(
                 relations
                     .get(&(inner.relation().id(), Version::Total))
                     .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?,
             );
 
-            next_bindings.insert(
-                inner.target().id(),
-                Term::Agg(inner.relation().id(), alias, *inner.target()),
-            );
+            let mut targets = Vec::default();
 
-            if rule
-                .args()
-                .clone()
-                .into_values()
-                .filter_map(|v| match v {
-                    ColVal::Binding(v) => Some(v),
-                    _ => None,
-                })
-                .all(|v| next_bindings.contains_key(&v.id()))
-            {
-                let relation = relations
-                    .get(&(rule.head(), Version::Total))
-                    .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?
-                    .clone();
-
-                let mut cols = im::HashMap::<ColId, Term>::default();
-                for (&k, v) in rule.args() {
-                    let term = match v {
-                        ColVal::Lit(c) => Term::Lit(c.clone()),
-                        ColVal::Binding(v) => next_bindings
-                            .get(&v.id())
-                            .ok_or_else(|| {
-                                Error::InternalRhizomeError(format!(
-                                    "binding not found: {}",
-                                    v.id()
-                                ))
-                            })?
-                            .clone(),
-                    };
-
-                    cols.insert(k, term);
-                }
+            for var in inner.targets() {
+                let target = slots.agg(inner.relation().id(), alias, *var);
+                let slot = target.slot().ok_or_else(|| {
+                    Error::InternalRhizomeError(
+                        "expected aggregate to be bound to a slot".to_owned(),
+                    )
+                })?;
 
-                formulae.push(Formula::not_in(
-                    rule.head(),
-                    Version::Total,
-                    Vec::from_iter(cols.clone()),
-                    relation,
-                ));
+                targets.push((*var, slot));
+                next_bindings.insert(var.id(), target);
             }
 
+            if let Some(formula) = head_not_in(rule, head, &next_bindings, relations)? {
+                formulae.push(formula);
+            }
+
+            let operation = lower_rule_body_to_ram(
+                rule,
+                head,
+                version,
+                next_bindings,
+                next_alias,
+                terms,
+                vec![],
+                slots,
+                relations,
+            )?;
+
             Ok(Operation::Aggregation(Aggregation::new(
                 args,
                 inner.agg(),
-                *inner.target(),
+                targets,
                 group_by_cols,
                 inner.relation().id(),
                 alias,
+                slots.cols_of(inner.relation().id(), alias),
                 aggregation_relation,
                 formulae,
-                lower_rule_body_to_ram(
-                    rule,
-                    version,
-                    next_bindings,
-                    next_alias,
-                    terms,
-                    vec![],
-                    relations,
-                )?,
+                operation,
             )))
         }
+        Some(SemiNaiveTerm::Cast(inner)) => {
+            let mut next_bindings = bindings.clone();
+
+            let from = bindings.get(&inner.from().id()).ok_or_else(|| {
+                Error::InternalRhizomeError(format!("binding not found: {}", inner.from().id()))
+            })?;
+
+            let term = coerce(from.clone(), inner.from().typ(), inner.to().typ());
+
+            if let Some(bound) = bindings.get(&inner.to().id()) {
+                formulae.push(Formula::equality(bound.clone(), term));
+            } else {
+                next_bindings.insert(inner.to().id(), term);
+
+                if let Some(formula) = head_not_in(rule, head, &next_bindings, relations)? {
+                    formulae.push(formula);
+                }
+            }
+
+            lower_rule_body_to_ram(
+                rule,
+                head,
+                version,
+                next_bindings,
+                next_alias,
+                terms,
+                formulae,
+                slots,
+                relations,
+            )
+        }
         None => {
             let relation = relations
                 .get(&(rule.head(), version))
                 .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?
                 .clone();
 
-            let mut cols = im::HashMap::<ColId, Term>::default();
-            for (&k, v) in rule.args() {
-                let term = match v {
-                    ColVal::Lit(c) => Term::Lit(c.clone()),
-                    ColVal::Binding(v) => bindings
-                        .get(&v.id())
-                        .ok_or_else(|| {
-                            Error::InternalRhizomeError(format!("binding not found: {}", v.id()))
-                        })?
-                        .clone(),
+            let cols = head_cols(rule, head, &bindings)?;
+            let mut project = Project::new((rule.head(), version), cols, formulae, relation)
+                .with_schema(relation_schema(head));
+
+            if !rule.choices().is_empty() {
+                // Facts chosen in earlier iterations have been merged into delta or
+                // total by the time new is projected into
+                let settled_versions = match version {
+                    Version::New => vec![Version::Delta, Version::Total],
+                    Version::Delta => vec![Version::Total],
+                    Version::Total => vec![],
                 };
 
-                cols.insert(k, term);
+                let mut settled = Vec::default();
+                for settled_version in settled_versions {
+                    let relation =
+                        relations
+                            .get(&(rule.head(), settled_version))
+                            .ok_or_else(|| {
+                                Error::InternalRhizomeError("relation not found".to_owned())
+                            })?;
+
+                    settled.push(Arc::clone(relation));
+                }
+
+                let choices = rule
+                    .choices()
+                    .iter()
+                    .map(|choice| Choice::new(choice.keys().to_vec(), choice.vals().to_vec()))
+                    .collect();
+
+                project = project.with_choices(choices, settled);
             }
 
-            Ok(Operation::Project(Project::new(
-                (rule.head(), version),
-                cols,
-                formulae,
-                relation,
-            )))
+            Ok(Operation::Project(project))
         }
     }
 }
@@ -726,15 +819,19 @@ pub(crate) fn lower_negation_to_ram(
     for (k, v) in negation.args() {
         let term = match v {
             ColVal::Lit(val) => Term::Lit(val.clone()),
-            ColVal::Binding(var) => bindings
-                .get(&var.id())
-                .ok_or_else(|| {
-                    Error::InternalRhizomeError(format!("binding not found: {}", var.id()))
-                })?
-                .clone(),
+            ColVal::Binding(var) => coerce(
+                bindings
+                    .get(&var.id())
+                    .ok_or_else(|| {
+                        Error::InternalRhizomeError(format!("binding not found: {}", var.id()))
+                    })?
+                    .clone(),
+                var.typ(),
+                col_type(&negation.relation(), k),
+            ),
         };
 
-        cols.insert(*k, term);
+        cols.insert(k.clone(), term);
     }
 
     let not_in_relation = relations
@@ -749,6 +846,90 @@ pub(crate) fn lower_negation_to_ram(
     ))
 }
 
+/// Resolves the columns of the head of a rule, widening the variables bound to them
+/// to the types of their columns.
+fn head_cols(
+    rule: &Rule,
+    head: &Declaration,
+    bindings: &im::HashMap<VarId, Term>,
+) -> Result<im::HashMap<ColId, Term>> {
+    let mut cols = im::HashMap::<ColId, Term>::default();
+
+    for (k, v) in rule.args() {
+        let term = match v {
+            ColVal::Lit(c) => Term::Lit(c.clone()),
+            ColVal::Binding(v) => coerce(
+                bindings
+                    .get(&v.id())
+                    .ok_or_else(|| {
+                        Error::InternalRhizomeError(format!("binding not found: {}", v.id()))
+                    })?
+                    .clone(),
+                v.typ(),
+                col_type(head, k),
+            ),
+        };
+
+        cols.insert(k.clone(), term);
+    }
+
+    Ok(cols)
+}
+
+/// Excludes tuples already in the head relation, once all of its variables are bound.
+fn head_not_in(
+    rule: &Rule,
+    head: &Declaration,
+    bindings: &im::HashMap<VarId, Term>,
+    relations: &HashMap<RelationKey, Arc<RwLock<Box<dyn Relation>>>>,
+) -> Result<Option<Formula>> {
+    let is_bound = rule.args().values().all(|v| match v {
+        ColVal::Binding(v) => bindings.contains_key(&v.id()),
+        ColVal::Lit(_) => true,
+    });
+
+    if !is_bound {
+        return Ok(None);
+    }
+
+    let relation = relations
+        .get(&(rule.head(), Version::Total))
+        .ok_or_else(|| Error::InternalRhizomeError("relation not found".to_owned()))?
+        .clone();
+
+    let cols = head_cols(rule, head, bindings)?;
+
+    Ok(Some(Formula::not_in(
+        rule.head(),
+        Version::Total,
+        Vec::from_iter(cols),
+        relation,
+    )))
+}
+
+fn relation_schema(declaration: &Declaration) -> RelationSchema {
+    RelationSchema::new(
+        declaration.id(),
+        declaration.schema().cols().values().cloned(),
+    )
+}
+
+fn col_type(declaration: &Declaration, col_id: &ColId) -> ColType {
+    declaration
+        .schema()
+        .get_col(col_id)
+        .map_or(ColType::Any, |col| *col.col_type())
+}
+
+/// Casts a term holding values of one type where values of another are expected,
+/// unless they're known to be the same.
+fn coerce(term: Term, from: ColType, to: ColType) -> Term {
+    match to {
+        ColType::Type(typ) if from != to => Term::Cast(Box::new(term), typ),
+        _ => term,
+    }
+}
+
 pub(crate) fn lower_var_predicate_to_ram(
     var_predicate: &VarPredicate,
     bindings: &im::HashMap<VarId, Term>,
@@ -772,9 +953,11 @@ pub(crate) fn lower_var_predicate_to_ram(
 #[derive(Debug, Clone)]
 pub(crate) enum SemiNaiveTerm {
     RelPredicate(RelPredicate, Version),
+    Optional(RelPredicate),
     VarPredicate(VarPredicate),
     Negation(Negation),
     Aggregation(super::ast::body_term::Aggregation),
+    Cast(Cast),
 }
 
 pub(crate) fn semi_naive_rewrites(rule: &Rule) -> Vec<Vec<SemiNaiveTerm>> {
@@ -784,6 +967,14 @@ pub(crate) fn semi_naive_rewrites(rule: &Rule) -> Vec<Vec<SemiNaiveTerm>> {
         non_relational_terms.push(SemiNaiveTerm::VarPredicate(var_predicate.clone()));
     }
 
+    for cast in rule.cast_terms() {
+        non_relational_terms.push(SemiNaiveTerm::Cast(cast.clone()));
+    }
+
+    for optional in rule.optional_terms() {
+        non_relational_terms.push(SemiNaiveTerm::Optional(optional.clone()));
+    }
+
     for negation in rule.negation_terms() {
         non_relational_terms.push(SemiNaiveTerm::Negation(negation.clone()));
     }
@@ -850,11 +1041,14 @@ fn select_term(
         .enumerate()
         .filter(|(_, term)| match term {
             SemiNaiveTerm::RelPredicate(_, _) => true,
+            SemiNaiveTerm::Optional(_) => true,
             SemiNaiveTerm::VarPredicate(inner) => inner.is_vars_bound(bindings),
             SemiNaiveTerm::Negation(inner) => inner.is_vars_bound(bindings),
             SemiNaiveTerm::Aggregation(_) => true,
+            SemiNaiveTerm::Cast(inner) => bindings.contains(&inner.from().id()),
         })
         .max_by_key(|(_, term)| match term {
+            SemiNaiveTerm::Cast(_) => (5, 0),
             SemiNaiveTerm::Negation(inner) => (4, inner.vars().len()),
             SemiNaiveTerm::VarPredicate(inner) => (3, inner.vars().len()),
             SemiNaiveTerm::RelPredicate(inner, Version::Delta) => {
@@ -866,6 +1060,7 @@ fn select_term(
             SemiNaiveTerm::RelPredicate(_, Version::New) => {
                 panic!("New relation in semi-naive rule");
             }
+            SemiNaiveTerm::Optional(inner) => (0, inner.bound_vars(bindings).len()),
             SemiNaiveTerm::Aggregation(inner) => (0, inner.bound_vars(bindings).len()),
         })
         .map(|(index, _)| index);
@@ -876,9 +1071,12 @@ fn select_term(
 fn update_bindings(bindings: &mut HashSet<VarId>, term: &SemiNaiveTerm) {
     match term {
         SemiNaiveTerm::Aggregation(inner) => {
-            bindings.insert(inner.target().id());
+            bindings.extend(inner.targets().iter().map(|var| var.id()));
+        }
+        SemiNaiveTerm::Cast(inner) => {
+            bindings.insert(inner.to().id());
         }
-        SemiNaiveTerm::RelPredicate(inner, _) => {
+        SemiNaiveTerm::RelPredicate(inner, _) | SemiNaiveTerm::Optional(inner) => {
             if let Some(CidValue::Var(var)) = inner.cid() {
                 bindings.insert(var.id());
             }
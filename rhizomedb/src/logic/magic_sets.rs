@@ -0,0 +1,522 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
+
+use anyhow::Result;
+
+use crate::{
+    col_val::ColVal,
+    error::{error, Error},
+    id::{ColId, RelationId, VarId},
+    relation::{DefaultRelation, Source},
+};
+
+use super::ast::{
+    BodyTerm, CidValue, Clause, Declaration, Fact, Program, Query, RelPredicate, Rule, Schema,
+};
+
+// The columns of a relation that are bound when it is demanded, sorted by name.
+type Adornment = Vec<ColId>;
+
+/// Rewrites a program so that each queried relation only derives the tuples that
+/// are relevant to its queries.
+///
+/// Each demanded relation, R, is guarded by a magic relation holding the bindings
+/// for which R is demanded, which is seeded by the queries and populated by rules
+/// that pass bindings sideways, from left to right, through the bodies of the rules
+/// that depend on R. Relations that are negated or aggregated over are still computed
+/// in full, as are those they depend upon, and any relations that the queried
+/// relations do not depend upon.
+pub(crate) fn magic_sets(program: &Program, queries: &[Query]) -> Result<Program> {
+    let rewriter = Rewriter::new(program, queries);
+
+    // Each attempt can only grow the set of relations computed in full
+    let mut full = rewriter.initial_full();
+    loop {
+        rewriter.close_full(&mut full);
+
+        match rewriter.rewrite(&full)? {
+            Ok(program) => return Ok(program),
+            Err(undemanded) => full.extend(undemanded),
+        }
+    }
+}
+
+struct Rewriter<'a> {
+    program: &'a Program,
+    queries: &'a [Query],
+    declarations: HashMap<RelationId, Arc<Declaration>>,
+    rules: HashMap<RelationId, Vec<&'a Rule>>,
+}
+
+impl<'a> Rewriter<'a> {
+    fn new(program: &'a Program, queries: &'a [Query]) -> Self {
+        let declarations = program
+            .declarations()
+            .iter()
+            .map(|declaration| (declaration.id(), Arc::clone(declaration)))
+            .collect();
+
+        let mut rules: HashMap<RelationId, Vec<&Rule>> = HashMap::default();
+        for clause in program.clauses() {
+            if let Clause::Rule(rule) = clause {
+                rules.entry(rule.head()).or_default().push(rule);
+            }
+        }
+
+        Self {
+            program,
+            queries,
+            declarations,
+            rules,
+        }
+    }
+
+    /// The relations that must be computed in full before any bindings are propagated.
+    fn initial_full(&self) -> HashSet<RelationId> {
+        let demanded = self.positively_reachable();
+
+        self.rules
+            .keys()
+            .filter(|id| !demanded.contains(id))
+            .cloned()
+            .collect()
+    }
+
+    /// Extends the set of relations computed in full with those that they depend upon,
+    /// and those negated, aggregated over or optionally searched by the remaining
+    /// demanded relations.
+    fn close_full(&self, full: &mut HashSet<RelationId>) {
+        let demanded = self.positively_reachable();
+
+        loop {
+            let mut next = full.clone();
+
+            for (head, rules) in &self.rules {
+                for rule in rules {
+                    for term in rule.body() {
+                        match term {
+                            BodyTerm::RelPredicate(inner) if full.contains(head) => {
+                                next.insert(inner.relation().id());
+                            }
+                            BodyTerm::Optional(inner)
+                                if full.contains(head) || demanded.contains(head) =>
+                            {
+                                next.insert(inner.relation().id());
+                            }
+                            BodyTerm::Negation(inner)
+                                if full.contains(head) || demanded.contains(head) =>
+                            {
+                                next.insert(inner.relation().id());
+                            }
+                            BodyTerm::Aggregation(inner)
+                                if full.contains(head) || demanded.contains(head) =>
+                            {
+                                next.insert(inner.relation().id());
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+            }
+
+            if next.len() == full.len() {
+                return;
+            }
+
+            *full = next;
+        }
+    }
+
+    /// The relations that the queried relations depend upon through positive atoms.
+    fn positively_reachable(&self) -> HashSet<RelationId> {
+        let mut reachable: HashSet<RelationId> =
+            self.queries.iter().map(|query| query.relation()).collect();
+        let mut queue: VecDeque<RelationId> = reachable.iter().cloned().collect();
+
+        while let Some(id) = queue.pop_front() {
+            for rule in self.rules.get(&id).into_iter().flatten() {
+                for predicate in rule.rel_predicate_terms() {
+                    if reachable.insert(predicate.relation().id()) {
+                        queue.push_back(predicate.relation().id());
+                    }
+                }
+            }
+        }
+
+        reachable
+    }
+
+    /// Rewrites the program, or returns the demanded relations that were found to
+    /// have no bound columns, and so must be computed in full.
+    fn rewrite(&self, full: &HashSet<RelationId>) -> Result<Result<Program, Vec<RelationId>>> {
+        let mut magic: HashMap<(RelationId, Adornment), Arc<Declaration>> = HashMap::default();
+        let mut queue: VecDeque<(RelationId, Adornment)> = VecDeque::default();
+        let mut undemanded: Vec<RelationId> = Vec::default();
+        let mut clauses: Vec<Clause> = Vec::default();
+
+        for query in self.queries {
+            if full.contains(&query.relation()) {
+                continue;
+            }
+
+            let adornment = adorn(query.args().keys().cloned());
+            if adornment.is_empty() {
+                undemanded.push(query.relation());
+
+                continue;
+            }
+
+            let declaration = self.magic_declaration(
+                &mut magic,
+                &mut queue,
+                query.relation(),
+                adornment.clone(),
+            )?;
+
+            clauses.push(Clause::Fact(Fact::new(
+                declaration.id(),
+                query.args().clone(),
+            )));
+        }
+
+        let mut guarded: HashSet<RelationId> = HashSet::default();
+
+        while let Some((id, adornment)) = queue.pop_front() {
+            guarded.insert(id.clone());
+
+            let declaration = Arc::clone(&magic[&(id.clone(), adornment.clone())]);
+
+            for rule in self.rules.get(&id).into_iter().flatten() {
+                let mut guard_args = HashMap::default();
+                for col_id in &adornment {
+                    let Some(col_val) = rule.args().get(col_id) else {
+                        return error(Error::ColumnMissing(id, col_id.clone()));
+                    };
+
+                    guard_args.insert(col_id.clone(), col_val.clone());
+                }
+
+                let guard = RelPredicate::new(Arc::clone(&declaration), None, guard_args);
+
+                let mut bound: HashSet<VarId> = HashSet::default();
+                bind_vars(&mut bound, &guard);
+
+                let mut prefix = vec![BodyTerm::RelPredicate(guard)];
+
+                for term in rule.body() {
+                    let BodyTerm::RelPredicate(inner) = term else {
+                        continue;
+                    };
+
+                    let relation = inner.relation();
+
+                    if relation.source() == Source::Idb && !full.contains(&relation.id()) {
+                        let adornment = adorn(inner.args().iter().filter_map(
+                            |(col_id, col_val)| match col_val {
+                                ColVal::Lit(_) => Some(col_id.clone()),
+                                ColVal::Binding(var) if bound.contains(&var.id()) => {
+                                    Some(col_id.clone())
+                                }
+                                ColVal::Binding(_) => None,
+                            },
+                        ));
+
+                        if adornment.is_empty() {
+                            undemanded.push(relation.id());
+                        } else {
+                            let head = self.magic_declaration(
+                                &mut magic,
+                                &mut queue,
+                                relation.id(),
+                                adornment.clone(),
+                            )?;
+
+                            let args = adornment
+                                .iter()
+                                .map(|col_id| (col_id.clone(), inner.args()[col_id].clone()))
+                                .collect();
+
+                            let mut body = prefix.clone();
+                            body.extend(filters(rule, &bound));
+
+                            clauses.push(Clause::Rule(Rule::new(head.id(), args, body)));
+                        }
+                    }
+
+                    bind_vars(&mut bound, inner);
+                    prefix.push(term.clone());
+                }
+
+                let mut body = vec![prefix[0].clone()];
+                body.extend(rule.body().iter().cloned());
+
+                clauses.push(Clause::Rule(
+                    Rule::new(id.clone(), rule.args().clone(), body)
+                        .with_choices(rule.choices().to_vec()),
+                ));
+            }
+        }
+
+        if !undemanded.is_empty() {
+            return Ok(Err(undemanded));
+        }
+
+        for clause in self.program.clauses() {
+            match clause {
+                Clause::Fact(fact) => clauses.push(Clause::Fact(fact.clone())),
+                Clause::Rule(rule) if !guarded.contains(&rule.head()) => {
+                    clauses.push(Clause::Rule(
+                        Rule::new(rule.head(), rule.args().clone(), rule.body().to_vec())
+                            .with_choices(rule.choices().to_vec()),
+                    ))
+                }
+                Clause::Rule(_) => (),
+            }
+        }
+
+        let mut declarations = self.program.declarations().to_vec();
+        declarations.extend(magic.into_values());
+
+        Ok(Ok(
+            Program::new(declarations, clauses).with_rewrites(self.program.rewrites().to_vec())
+        ))
+    }
+
+    /// Returns the magic relation for a relation and adornment, enqueueing the
+    /// adorned relation to be rewritten if it hasn't been seen before.
+    fn magic_declaration(
+        &self,
+        magic: &mut HashMap<(RelationId, Adornment), Arc<Declaration>>,
+        queue: &mut VecDeque<(RelationId, Adornment)>,
+        id: RelationId,
+        adornment: Adornment,
+    ) -> Result<Arc<Declaration>> {
+        if let Some(declaration) = magic.get(&(id.clone(), adornment.clone())) {
+            return Ok(Arc::clone(declaration));
+        }
+
+        let Some(relation) = self.declarations.get(&id) else {
+            return error(Error::UnrecognizedRelation(id.to_string()));
+        };
+
+        let magic_id = RelationId::new(format!(
+            "magic_{}_{}",
+            id,
+            adornment
+                .iter()
+                .map(|col_id| col_id.to_string())
+                .collect::<Vec<_>>()
+                .join("_")
+        ));
+
+        if self.declarations.contains_key(&magic_id) {
+            return error(Error::ConflictingRelationDeclaration(magic_id));
+        }
+
+        let schema = relation.schema();
+        let mut cols = HashMap::default();
+        for col_id in &adornment {
+            let Some(col) = schema.get_col(col_id) else {
+                return error(Error::UnrecognizedColumnBinding(id, col_id.clone()));
+            };
+
+            cols.insert(col_id.clone(), col.clone());
+        }
+
+        let declaration = Arc::new(
+            Declaration::new(
+                magic_id.clone(),
+                Arc::new(Schema::new(magic_id, cols)),
+                Source::Idb,
+                Box::<DefaultRelation>::default(),
+            )
+            .internal(),
+        );
+
+        magic.insert((id.clone(), adornment.clone()), Arc::clone(&declaration));
+        queue.push_back((id, adornment));
+
+        Ok(declaration)
+    }
+}
+
+fn adorn(cols: impl Iterator<Item = ColId>) -> Adornment {
+    let mut adornment: Adornment = cols.collect();
+    adornment.sort_by_key(|col_id| col_id.to_string());
+    adornment.dedup();
+
+    adornment
+}
+
+fn bind_vars(bound: &mut HashSet<VarId>, predicate: &RelPredicate) {
+    bound.extend(predicate.vars().iter().map(|var| var.id()));
+
+    if let Some(CidValue::Var(var)) = predicate.cid() {
+        bound.insert(var.id());
+    }
+}
+
+/// The filters in the body of a rule that only refer to bound variables.
+fn filters<'a>(rule: &'a Rule, bound: &'a HashSet<VarId>) -> impl Iterator<Item = BodyTerm> + 'a {
+    rule.body()
+        .iter()
+        .filter(move |term| match term {
+            BodyTerm::VarPredicate(inner) => inner.is_vars_bound(bound),
+            BodyTerm::Negation(inner) => inner.is_vars_bound(bound),
+            _ => false,
+        })
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        assert_derives, logic::builder::query, runtime::vm::VM, storage::memory::MemoryBlockstore,
+        tuple::Tuple, ProgramBuilder,
+    };
+
+    fn graph(p: &ProgramBuilder) -> Result<()> {
+        p.output("edge", |h| h.column::<i32>("from").column::<i32>("to"))?;
+        p.output("path", |h| h.column::<i32>("from").column::<i32>("to"))?;
+
+        for (from, to) in [(0, 1), (1, 2), (2, 3), (10, 11), (11, 12)] {
+            p.fact("edge", |f| f.bind((("from", from), ("to", to))))?;
+        }
+
+        p.rule::<(i32, i32)>("path", &|h, b, (x, y)| {
+            h.bind((("from", x), ("to", y)))?;
+            b.search("edge", (("from", x), ("to", y)))?;
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_demand() {
+        assert_derives!(
+            |p| {
+                graph(&p)?;
+
+                p.rule::<(i32, i32, i32)>("path", &|h, b, (x, y, z)| {
+                    h.bind((("from", x), ("to", z)))?;
+
+                    b.search("path", (("from", x), ("to", y)))?;
+                    b.search("edge", (("from", y), ("to", z)))?;
+
+                    Ok(())
+                })?;
+
+                p.demand("path", |q| q.bind((("from", 0),)))?;
+
+                Ok(p)
+            },
+            [(
+                "path",
+                [
+                    Tuple::new("path", [("from", 0), ("to", 1)], None),
+                    Tuple::new("path", [("from", 0), ("to", 2)], None),
+                    Tuple::new("path", [("from", 0), ("to", 3)], None),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_demand_negation() {
+        assert_derives!(
+            |p| {
+                graph(&p)?;
+
+                p.output("blocked", |h| h.column::<i32>("node"))?;
+                p.output("open", |h| h.column::<i32>("from").column::<i32>("to"))?;
+
+                p.fact("blocked", |f| f.bind((("node", 2),)))?;
+
+                p.rule::<(i32, i32, i32)>("path", &|h, b, (x, y, z)| {
+                    h.bind((("from", x), ("to", z)))?;
+
+                    b.search("edge", (("from", x), ("to", y)))?;
+                    b.search("path", (("from", y), ("to", z)))?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(i32, i32)>("open", &|h, b, (x, y)| {
+                    h.bind((("from", x), ("to", y)))?;
+
+                    b.search("path", (("from", x), ("to", y)))?;
+                    b.except("blocked", (("node", y),))?;
+
+                    Ok(())
+                })?;
+
+                p.demand("open", |q| q.bind((("from", 1),)))?;
+
+                Ok(p)
+            },
+            [
+                (
+                    "open",
+                    vec![Tuple::new("open", [("from", 1), ("to", 3)], None)]
+                ),
+                (
+                    "path",
+                    vec![
+                        Tuple::new("path", [("from", 1), ("to", 2)], None),
+                        Tuple::new("path", [("from", 1), ("to", 3)], None),
+                        Tuple::new("path", [("from", 2), ("to", 3)], None),
+                    ]
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_query() -> Result<()> {
+        let program = query(
+            |p| {
+                graph(&p)?;
+
+                p.output("node", |h| h.column::<i32>("id"))?;
+
+                p.rule::<(i32, i32)>("node", &|h, b, (x, y)| {
+                    h.bind((("id", x),))?;
+                    b.search("edge", (("from", x), ("to", y)))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            "path",
+            |q| q.bind((("from", 10),)),
+        )?;
+
+        let bs = MemoryBlockstore::default();
+        let mut vm = <VM>::new(program);
+        vm.step_epoch(&bs)?;
+
+        let mut actual = BTreeSet::default();
+        while let Some(tuple) = vm.pop()? {
+            if tuple.id() != "edge".into() {
+                actual.insert(tuple);
+            }
+        }
+
+        assert_eq!(
+            BTreeSet::from_iter([Tuple::new("path", [("from", 10), ("to", 11)], None)]),
+            actual
+        );
+
+        Ok(())
+    }
+}
@@ -1,7 +1,18 @@
 mod ast;
 mod builder;
 
+pub(crate) mod coerce;
+pub(crate) mod dependency_graph;
+pub(crate) mod infer;
 pub(crate) mod lower_to_ram;
+pub(crate) mod magic_sets;
+pub(crate) mod prune;
+pub(crate) mod rewrite;
+pub(crate) mod share;
 pub(crate) mod stratify;
 
-pub use builder::{build, AtomBinding, AtomBindings, ProgramBuilder, RuleBodyBuilder, RuleVars};
+pub use builder::{
+    build, dependency_graph, explain, query, AtomBinding, AtomBindings, ProgramBuilder,
+    QueryBuilder, RuleBodyBuilder, RuleVars,
+};
+pub use dependency_graph::{DependencyGraph, Polarity};
@@ -0,0 +1,219 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::{id::RelationId, relation::Source};
+
+use super::{
+    ast::{BodyTerm, Clause, Fact, Program, Rule},
+    rewrite::Rewrite,
+};
+
+/// Removes the output relations, and their clauses, that none of the consumed
+/// relations depend upon. Inputs are always kept.
+pub(crate) fn prune(program: &Program, consumed: &[RelationId]) -> Program {
+    let mut live: HashSet<RelationId> = consumed.iter().cloned().collect();
+    let mut queue: VecDeque<RelationId> = live.iter().cloned().collect();
+
+    while let Some(id) = queue.pop_front() {
+        for clause in program.clauses() {
+            let Clause::Rule(rule) = clause else {
+                continue;
+            };
+
+            if rule.head() != id {
+                continue;
+            }
+
+            for dependency in dependencies(rule) {
+                if live.insert(dependency.clone()) {
+                    queue.push_back(dependency);
+                }
+            }
+        }
+    }
+
+    let mut declarations = Vec::default();
+    let mut pruned = Vec::default();
+
+    for declaration in program.declarations() {
+        if declaration.source() == Source::Edb || live.contains(&declaration.id()) {
+            declarations.push(declaration.clone());
+
+            continue;
+        }
+
+        let clauses = program
+            .clauses()
+            .iter()
+            .filter(|clause| clause.head() == declaration.id());
+
+        let (rules, facts) = clauses.fold((0, 0), |(rules, facts), clause| match clause {
+            Clause::Rule(_) => (rules + 1, facts),
+            Clause::Fact(_) => (rules, facts + 1),
+        });
+
+        pruned.push((declaration.id(), rules, facts));
+    }
+
+    pruned.sort_by_key(|(id, _, _)| id.to_string());
+
+    let clauses = program
+        .clauses()
+        .iter()
+        .filter(|clause| live.contains(&clause.head()))
+        .map(|clause| match clause {
+            Clause::Fact(fact) => Clause::Fact(Fact::clone(fact)),
+            Clause::Rule(rule) => Clause::Rule(
+                Rule::new(rule.head(), rule.args().clone(), rule.body().to_vec())
+                    .with_choices(rule.choices().to_vec()),
+            ),
+        })
+        .collect();
+
+    let mut rewrites = program.rewrites().to_vec();
+    rewrites.extend(
+        pruned
+            .into_iter()
+            .map(|(relation, rules, facts)| Rewrite::Pruned {
+                relation,
+                rules,
+                facts,
+            }),
+    );
+
+    Program::new(declarations, clauses).with_rewrites(rewrites)
+}
+
+fn dependencies(rule: &Rule) -> impl Iterator<Item = RelationId> + '_ {
+    rule.body().iter().filter_map(|term| match term {
+        BodyTerm::RelPredicate(inner) => Some(inner.relation().id()),
+        BodyTerm::Optional(inner) => Some(inner.relation().id()),
+        BodyTerm::Negation(inner) => Some(inner.relation().id()),
+        BodyTerm::Aggregation(inner) => Some(inner.relation().id()),
+        BodyTerm::VarPredicate(_) | BodyTerm::Cast(_) => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    use crate::{assert_derives, logic::builder::explain, tuple::Tuple, ProgramBuilder};
+
+    fn library(p: &ProgramBuilder) -> Result<()> {
+        p.output("edge", |h| h.column::<i32>("from").column::<i32>("to"))?;
+        p.output("path", |h| h.column::<i32>("from").column::<i32>("to"))?;
+        p.output("node", |h| h.column::<i32>("id"))?;
+        p.output("root", |h| h.column::<i32>("id"))?;
+
+        p.fact("edge", |f| f.bind((("from", 0), ("to", 1))))?;
+        p.fact("edge", |f| f.bind((("from", 1), ("to", 2))))?;
+        p.fact("root", |f| f.bind((("id", 0),)))?;
+
+        p.rule::<(i32, i32)>("path", &|h, b, (x, y)| {
+            h.bind((("from", x), ("to", y)))?;
+            b.search("edge", (("from", x), ("to", y)))?;
+
+            Ok(())
+        })?;
+
+        p.rule::<(i32, i32, i32)>("path", &|h, b, (x, y, z)| {
+            h.bind((("from", x), ("to", z)))?;
+
+            b.search("edge", (("from", x), ("to", y)))?;
+            b.search("path", (("from", y), ("to", z)))?;
+
+            Ok(())
+        })?;
+
+        p.rule::<(i32, i32)>("node", &|h, b, (x, y)| {
+            h.bind((("id", x),))?;
+            b.search("edge", (("from", x), ("to", y)))?;
+
+            Ok(())
+        })?;
+
+        p.rule::<(i32, i32)>("node", &|h, b, (x, y)| {
+            h.bind((("id", y),))?;
+            b.search("edge", (("from", x), ("to", y)))?;
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune() {
+        assert_derives!(
+            |p| {
+                library(&p)?;
+
+                p.consume("path")?;
+
+                Ok(p)
+            },
+            [
+                (
+                    "path",
+                    vec![
+                        Tuple::new("path", [("from", 0), ("to", 1)], None),
+                        Tuple::new("path", [("from", 0), ("to", 2)], None),
+                        Tuple::new("path", [("from", 1), ("to", 2)], None),
+                    ]
+                ),
+                ("node", vec![]),
+                ("root", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prune_demanded() {
+        assert_derives!(
+            |p| {
+                library(&p)?;
+
+                p.demand("node", |q| q.bind((("id", 2),)))?;
+                p.consume("root")?;
+
+                Ok(p)
+            },
+            [
+                ("node", vec![Tuple::new("node", [("id", 2)], None)]),
+                ("root", vec![Tuple::new("root", [("id", 0)], None)]),
+                ("path", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explain_pruned() -> Result<()> {
+        let explained = explain(|p| {
+            library(&p)?;
+
+            p.consume("path")?;
+
+            Ok(p)
+        })?;
+
+        assert_eq!(
+            vec![
+                "// pruned node (2 rules, 0 facts)",
+                "// pruned root (0 rules, 1 facts)",
+                "",
+            ],
+            explained.lines().take(3).collect::<Vec<_>>()
+        );
+
+        let unpruned = explain(|p| {
+            library(&p)?;
+
+            Ok(p)
+        })?;
+
+        assert!(!unpruned.contains("// pruned"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt::{self, Display};
+
+use crate::{
+    id::{ColId, RelationId},
+    types::Type,
+};
+
+/// A change made to a program between building and lowering it, which is
+/// reported when the program is explained.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Rewrite {
+    /// An output relation, and its clauses, that no consumed relation depends upon.
+    Pruned {
+        relation: RelationId,
+        rules: usize,
+        facts: usize,
+    },
+    /// A temporary relation that materializes a join prefix shared by several rules.
+    Shared { relation: RelationId, rules: usize },
+    /// A temporary relation that materializes the nested body of an aggregation.
+    Flattened { relation: RelationId },
+    /// Rules that were dropped because they duplicate another rule for the relation.
+    Subsumed { relation: RelationId, rules: usize },
+    /// Values of a column that are widened to another type where they're bound.
+    Coerced {
+        relation: RelationId,
+        col: ColId,
+        from: Type,
+        to: Type,
+    },
+}
+
+impl Display for Rewrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rewrite::Pruned {
+                relation,
+                rules,
+                facts,
+            } => write!(f, "pruned {relation} ({rules} rules, {facts} facts)"),
+            Rewrite::Shared { relation, rules } => {
+                write!(f, "shared {relation} ({rules} rules)")
+            }
+            Rewrite::Flattened { relation } => write!(f, "flattened {relation}"),
+            Rewrite::Subsumed { relation, rules } => {
+                write!(f, "subsumed {relation} ({rules} rules)")
+            }
+            Rewrite::Coerced {
+                relation,
+                col,
+                from,
+                to,
+            } => write!(f, "coerced {relation}.{col} from {from} to {to}"),
+        }
+    }
+}
@@ -0,0 +1,562 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
+
+use anyhow::Result;
+
+use crate::{
+    col::Col,
+    col_val::ColVal,
+    id::{ColId, RelationId, VarId},
+    relation::{DefaultRelation, Source},
+    types::ColType,
+    value::Val,
+    var::Var,
+};
+
+use super::{
+    ast::{BodyTerm, CidValue, Clause, Declaration, Negation, Program, RelPredicate, Rule, Schema},
+    dependency_graph::DependencyGraph,
+    rewrite::Rewrite,
+};
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+enum Canonical {
+    Lit(Val),
+    Var(usize),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+enum CanonicalTerm {
+    Search(RelationId, Option<Canonical>, Vec<(ColId, Canonical)>),
+    Optional(RelationId, Vec<(ColId, Canonical)>),
+    Negation(RelationId, Vec<(ColId, Canonical)>),
+}
+
+type CanonicalRule = (RelationId, Vec<CanonicalTerm>, Vec<(ColId, Canonical)>);
+
+/// Numbers the variables of a sequence of terms by their first occurrence, so that
+/// terms which only differ in the naming of their variables compare equal.
+#[derive(Default)]
+struct Canonicalizer {
+    indices: HashMap<VarId, usize>,
+    vars: Vec<(Var, ColType)>,
+}
+
+impl Canonicalizer {
+    fn var(&mut self, var: &Var, typ: ColType) -> Canonical {
+        let next = self.vars.len();
+        let idx = *self.indices.entry(var.id()).or_insert_with(|| {
+            self.vars.push((*var, typ));
+
+            next
+        });
+
+        Canonical::Var(idx)
+    }
+
+    fn args(
+        &mut self,
+        relation: &Declaration,
+        args: &HashMap<ColId, ColVal>,
+    ) -> Vec<(ColId, Canonical)> {
+        let mut args = args.iter().collect::<Vec<_>>();
+        args.sort_by_key(|(col_id, _)| col_id.to_string());
+
+        args.into_iter()
+            .map(|(col_id, col_val)| {
+                let canonical = match col_val {
+                    ColVal::Lit(val) => Canonical::Lit(val.clone()),
+                    ColVal::Binding(var) => {
+                        let typ = relation
+                            .schema()
+                            .get_col(col_id)
+                            .map_or(var.typ(), |col| *col.col_type());
+
+                        self.var(var, typ)
+                    }
+                };
+
+                (col_id.clone(), canonical)
+            })
+            .collect()
+    }
+
+    fn search(&mut self, predicate: &RelPredicate) -> CanonicalTerm {
+        let cid = predicate.cid().map(|cid| match cid {
+            CidValue::Cid(cid) => Canonical::Lit(Val::Cid(cid)),
+            CidValue::Var(var) => self.var(&var, var.typ()),
+        });
+
+        let args = self.args(&predicate.relation(), predicate.args());
+
+        CanonicalTerm::Search(predicate.relation().id(), cid, args)
+    }
+
+    fn optional(&mut self, predicate: &RelPredicate) -> CanonicalTerm {
+        let args = self.args(&predicate.relation(), predicate.args());
+
+        CanonicalTerm::Optional(predicate.relation().id(), args)
+    }
+
+    fn negation(&mut self, negation: &Negation) -> CanonicalTerm {
+        let args = self.args(&negation.relation(), negation.args());
+
+        CanonicalTerm::Negation(negation.relation().id(), args)
+    }
+}
+
+/// Drops the rules that are equivalent, up to the naming of their variables, to an
+/// earlier rule for the same relation, and materializes the longest join prefixes
+/// shared by several rules in a stratum as temporary relations, so that they're
+/// evaluated once per iteration rather than once per rule.
+pub(crate) fn share(program: &Program) -> Result<Program> {
+    let mut declarations = program.declarations().to_vec();
+    let mut rewrites = program.rewrites().to_vec();
+
+    let mut seen = HashSet::new();
+    let mut subsumed = BTreeMap::<String, (RelationId, usize)>::default();
+    let mut clauses = Vec::default();
+
+    for clause in program.clauses() {
+        if let Clause::Rule(rule) = clause {
+            if let Some(key) = rule_key(rule) {
+                if !seen.insert(key) {
+                    subsumed
+                        .entry(rule.head().to_string())
+                        .or_insert((rule.head(), 0))
+                        .1 += 1;
+
+                    continue;
+                }
+            }
+        }
+
+        clauses.push(match clause {
+            Clause::Fact(fact) => Clause::Fact(fact.clone()),
+            Clause::Rule(rule) => Clause::Rule(rule.clone()),
+        });
+    }
+
+    rewrites.extend(
+        subsumed
+            .into_values()
+            .map(|(relation, rules)| Rewrite::Subsumed { relation, rules }),
+    );
+
+    let graph = DependencyGraph::new(program)?;
+    let mut strata = HashMap::<RelationId, usize>::default();
+    for (idx, scc) in graph.strata_nodes().enumerate() {
+        for node in scc {
+            if let Some(node) = graph.node(*node) {
+                strata.insert(node.id(), idx);
+            }
+        }
+    }
+
+    let mut members = BTreeMap::<usize, Vec<usize>>::default();
+    for (idx, clause) in clauses.iter().enumerate() {
+        if let (Clause::Rule(rule), Some(stratum)) = (clause, strata.get(&clause.head())) {
+            if leading_searches(rule).len() >= 2 {
+                members.entry(*stratum).or_default().push(idx);
+            }
+        }
+    }
+
+    let mut names: HashSet<RelationId> = declarations.iter().map(|d| d.id()).collect();
+    let mut next_name = 0;
+
+    for members in members.into_values() {
+        let mut skipped = HashSet::new();
+
+        while let Some((len, sharing)) = shared_prefix(&clauses, &members, &skipped) {
+            let rules = sharing
+                .iter()
+                .filter_map(|idx| match &clauses[*idx] {
+                    Clause::Rule(rule) => Some(rule),
+                    Clause::Fact(_) => None,
+                })
+                .collect::<Vec<_>>();
+
+            let prefixes = rules
+                .iter()
+                .map(|rule| {
+                    let mut canonicalizer = Canonicalizer::default();
+                    let key = leading_searches(rule)[..len]
+                        .iter()
+                        .map(|predicate| canonicalizer.search(predicate))
+                        .collect::<Vec<_>>();
+
+                    (key, canonicalizer.vars)
+                })
+                .collect::<Vec<_>>();
+
+            // Only the variables that some rule uses past the prefix need to be kept.
+            let mut kept = HashSet::new();
+            for (rule, (_, vars)) in rules.iter().zip(&prefixes) {
+                let used = vars_after(rule, len);
+
+                for (idx, (var, _)) in vars.iter().enumerate() {
+                    if used.contains(&var.id()) {
+                        kept.insert(idx);
+                    }
+                }
+            }
+
+            let (key, representative) = &prefixes[0];
+            if kept.is_empty() {
+                kept.extend(0..representative.len());
+            }
+
+            if kept.is_empty() {
+                skipped.insert(key.clone());
+
+                continue;
+            }
+
+            let mut kept = kept.into_iter().collect::<Vec<_>>();
+            kept.sort();
+
+            let id = loop {
+                let id = RelationId::new(format!("prefix_{next_name}"));
+                next_name += 1;
+
+                if names.insert(id.clone()) {
+                    break id;
+                }
+            };
+
+            let col_id = |idx: usize| ColId::new(format!("v{idx}"));
+            let cols = kept
+                .iter()
+                .map(|idx| (col_id(*idx), Col::new(col_id(*idx), representative[*idx].1)))
+                .collect();
+
+            let declaration = Arc::new(
+                Declaration::new(
+                    id.clone(),
+                    Arc::new(Schema::new(id.clone(), cols)),
+                    Source::Idb,
+                    Box::<DefaultRelation>::default(),
+                )
+                .internal(),
+            );
+
+            let bind = |vars: &[(Var, ColType)]| -> HashMap<ColId, ColVal> {
+                kept.iter()
+                    .map(|idx| (col_id(*idx), ColVal::Binding(vars[*idx].0)))
+                    .collect()
+            };
+
+            let body = leading_searches(rules[0])[..len]
+                .iter()
+                .map(|predicate| BodyTerm::RelPredicate((*predicate).clone()))
+                .collect();
+
+            let prefix_rule = Rule::new(id.clone(), bind(representative), body);
+
+            let rewritten = rules
+                .iter()
+                .zip(&prefixes)
+                .map(|(rule, (_, vars))| {
+                    let mut body = vec![BodyTerm::RelPredicate(RelPredicate::new(
+                        Arc::clone(&declaration),
+                        None,
+                        bind(vars),
+                    ))];
+                    body.extend(rule.body()[len..].iter().cloned());
+
+                    Rule::new(rule.head(), rule.args().clone(), body)
+                        .with_choices(rule.choices().to_vec())
+                })
+                .collect::<Vec<_>>();
+
+            for (idx, rule) in sharing.iter().zip(rewritten) {
+                clauses[*idx] = Clause::Rule(rule);
+            }
+
+            clauses.push(Clause::Rule(prefix_rule));
+            declarations.push(declaration);
+            rewrites.push(Rewrite::Shared {
+                relation: id,
+                rules: sharing.len(),
+            });
+        }
+    }
+
+    Ok(Program::new(declarations, clauses).with_rewrites(rewrites))
+}
+
+/// Finds the longest join prefix that is shared by the most rules, returning its
+/// length and the rules that share it.
+fn shared_prefix(
+    clauses: &[Clause],
+    members: &[usize],
+    skipped: &HashSet<Vec<CanonicalTerm>>,
+) -> Option<(usize, Vec<usize>)> {
+    let mut prefixes = HashMap::<Vec<CanonicalTerm>, Vec<usize>>::default();
+
+    for idx in members {
+        let Clause::Rule(rule) = &clauses[*idx] else {
+            continue;
+        };
+
+        let mut canonicalizer = Canonicalizer::default();
+        let mut key = Vec::default();
+
+        for predicate in leading_searches(rule) {
+            key.push(canonicalizer.search(predicate));
+
+            if key.len() >= 2 && !skipped.contains(&key) {
+                prefixes.entry(key.clone()).or_default().push(*idx);
+            }
+        }
+    }
+
+    prefixes
+        .into_iter()
+        .filter(|(_, sharing)| sharing.len() >= 2)
+        .max_by_key(|(key, sharing)| (key.len(), sharing.len(), Reverse(sharing[0])))
+        .map(|(key, sharing)| (key.len(), sharing))
+}
+
+fn leading_searches(rule: &Rule) -> Vec<&RelPredicate> {
+    rule.body()
+        .iter()
+        .map_while(|term| match term {
+            BodyTerm::RelPredicate(inner) => Some(inner),
+            _ => None,
+        })
+        .collect()
+}
+
+fn vars_after(rule: &Rule, len: usize) -> HashSet<VarId> {
+    let mut vars = HashSet::new();
+
+    for col_val in rule.args().values() {
+        if let ColVal::Binding(var) = col_val {
+            vars.insert(var.id());
+        }
+    }
+
+    for term in &rule.body()[len..] {
+        match term {
+            BodyTerm::RelPredicate(inner) | BodyTerm::Optional(inner) => {
+                if let Some(CidValue::Var(var)) = inner.cid() {
+                    vars.insert(var.id());
+                }
+
+                vars.extend(inner.vars().into_iter().map(|var| var.id()));
+            }
+            BodyTerm::Negation(inner) => {
+                vars.extend(inner.vars().into_iter().map(|var| var.id()));
+            }
+            BodyTerm::VarPredicate(inner) => {
+                vars.extend(inner.vars().iter().map(|var| var.id()));
+            }
+            BodyTerm::Cast(inner) => {
+                vars.insert(inner.from().id());
+                vars.insert(inner.to().id());
+            }
+            BodyTerm::Aggregation(inner) => {
+                vars.extend(inner.vars().iter().map(|var| var.id()));
+                vars.extend(inner.group_by_cols().values().filter_map(|v| match v {
+                    ColVal::Binding(var) => Some(var.id()),
+                    ColVal::Lit(_) => None,
+                }));
+            }
+        }
+    }
+
+    vars
+}
+
+/// The canonical form of a rule, if it can be compared with others. Rules with
+/// predicates or aggregations can't be, since their closures are opaque, and nor can
+/// those that choose between the facts they derive.
+fn rule_key(rule: &Rule) -> Option<CanonicalRule> {
+    if !rule.choices().is_empty() {
+        return None;
+    }
+
+    let mut canonicalizer = Canonicalizer::default();
+    let mut body = Vec::default();
+
+    for term in rule.body() {
+        match term {
+            BodyTerm::RelPredicate(inner) => body.push(canonicalizer.search(inner)),
+            BodyTerm::Optional(inner) => body.push(canonicalizer.optional(inner)),
+            BodyTerm::Negation(inner) => body.push(canonicalizer.negation(inner)),
+            BodyTerm::VarPredicate(_) | BodyTerm::Aggregation(_) | BodyTerm::Cast(_) => {
+                return None
+            }
+        }
+    }
+
+    let mut args = rule.args().iter().collect::<Vec<_>>();
+    args.sort_by_key(|(col_id, _)| col_id.to_string());
+
+    let head = args
+        .into_iter()
+        .map(|(col_id, col_val)| {
+            let canonical = match col_val {
+                ColVal::Lit(val) => Canonical::Lit(val.clone()),
+                ColVal::Binding(var) => canonicalizer.var(var, var.typ()),
+            };
+
+            (col_id.clone(), canonical)
+        })
+        .collect();
+
+    Some((rule.head(), body, head))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        assert_derives, build, id::RelationId, logic::builder::explain, runtime::vm::VM,
+        storage::memory::MemoryBlockstore, tuple::Tuple, ProgramBuilder,
+    };
+
+    fn two_hops(p: &ProgramBuilder) -> Result<()> {
+        p.output("edge", |h| h.column::<i32>("from").column::<i32>("to"))?;
+        p.output("label", |h| h.column::<i32>("id").column::<i32>("label"))?;
+        p.output("mark", |h| h.column::<i32>("id").column::<i32>("label"))?;
+        p.output("tagged", |h| h.column::<i32>("from").column::<i32>("label"))?;
+
+        p.fact("edge", |f| f.bind((("from", 0), ("to", 1))))?;
+        p.fact("edge", |f| f.bind((("from", 1), ("to", 2))))?;
+        p.fact("edge", |f| f.bind((("from", 2), ("to", 3))))?;
+        p.fact("label", |f| f.bind((("id", 2), ("label", 20))))?;
+        p.fact("mark", |f| f.bind((("id", 3), ("label", 30))))?;
+
+        p.rule::<(i32, i32, i32, i32)>("tagged", &|h, b, (x, y, z, l)| {
+            h.bind((("from", x), ("label", l)))?;
+
+            b.search("edge", (("from", x), ("to", y)))?;
+            b.search("edge", (("from", y), ("to", z)))?;
+            b.search("label", (("id", z), ("label", l)))?;
+
+            Ok(())
+        })?;
+
+        p.rule::<(i32, i32, i32, i32)>("tagged", &|h, b, (a, c, d, l)| {
+            h.bind((("from", a), ("label", l)))?;
+
+            b.search("edge", (("from", a), ("to", c)))?;
+            b.search("edge", (("from", c), ("to", d)))?;
+            b.search("mark", (("id", d), ("label", l)))?;
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_share() {
+        assert_derives!(
+            |p| {
+                two_hops(&p)?;
+
+                Ok(p)
+            },
+            [(
+                "tagged",
+                vec![
+                    Tuple::new("tagged", [("from", 0), ("label", 20)], None),
+                    Tuple::new("tagged", [("from", 1), ("label", 30)], None),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_shared_not_sunk() -> Result<()> {
+        let program = build(|p| {
+            two_hops(&p)?;
+
+            Ok(p)
+        })?;
+
+        let bs = MemoryBlockstore::default();
+        let mut vm = <VM>::new(program);
+        vm.step_epoch(&bs)?;
+
+        let outputs = ["edge", "label", "mark", "tagged"].map(RelationId::new);
+        while let Some(tuple) = vm.pop()? {
+            assert!(outputs.contains(&tuple.id()), "sunk {}", tuple.id());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_subsume() {
+        assert_derives!(
+            |p| {
+                p.output("edge", |h| h.column::<i32>("from").column::<i32>("to"))?;
+                p.output("path", |h| h.column::<i32>("from").column::<i32>("to"))?;
+
+                p.fact("edge", |f| f.bind((("from", 0), ("to", 1))))?;
+
+                p.rule::<(i32, i32)>("path", &|h, b, (x, y)| {
+                    h.bind((("from", x), ("to", y)))?;
+                    b.search("edge", (("from", x), ("to", y)))?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(i32, i32)>("path", &|h, b, (a, c)| {
+                    h.bind((("from", a), ("to", c)))?;
+                    b.search("edge", (("from", a), ("to", c)))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [(
+                "path",
+                vec![Tuple::new("path", [("from", 0), ("to", 1)], None)]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_explain_shared() -> Result<()> {
+        let explained = explain(|p| {
+            two_hops(&p)?;
+
+            p.rule::<(i32, i32, i32, i32)>("tagged", &|h, b, (u, v, w, l)| {
+                h.bind((("from", u), ("label", l)))?;
+
+                b.search("edge", (("from", u), ("to", v)))?;
+                b.search("edge", (("from", v), ("to", w)))?;
+                b.search("label", (("id", w), ("label", l)))?;
+
+                Ok(())
+            })?;
+
+            Ok(p)
+        })?;
+
+        assert_eq!(
+            vec![
+                "// subsumed tagged (1 rules)",
+                "// shared prefix_0 (2 rules)",
+                "",
+            ],
+            explained.lines().take(3).collect::<Vec<_>>()
+        );
+
+        assert!(explained.contains("prefix_0"));
+
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{args::Args, value::Val, var::Var};
+use crate::{args::Args, types::ColType, value::Val, var::Var};
 
 pub trait PredicateWhere<I> {
     type Predicate: Predicate<Input = I>;
@@ -16,7 +16,7 @@ pub trait Predicate: Sized {
     fn apply(&self, args: Self::Input) -> Option<bool>;
 }
 
-impl<T: ?Sized> Predicate for Box<T>
+impl<T> Predicate for Box<T>
 where
     T: Predicate,
 {
@@ -29,6 +29,7 @@ where
 
 pub trait PredicateWrapper: Send + Sync + 'static {
     fn apply(&self, args: Vec<Val>) -> Option<bool>;
+    fn arg_types(&self) -> Vec<ColType>;
 }
 
 impl<T, I> PredicateWrapper for T
@@ -37,8 +38,12 @@ where
     I: Args,
 {
     fn apply(&self, args: Vec<Val>) -> Option<bool> {
-        let args = <T::Input as Args>::instantiate(args).unwrap();
+        let args = <T::Input as Args>::instantiate(args).ok()?;
 
         T::apply(self, args)
     }
+
+    fn arg_types(&self) -> Vec<ColType> {
+        <T::Input as Args>::col_types()
+    }
 }
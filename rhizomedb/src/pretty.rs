@@ -51,6 +51,8 @@ mod tests {
             None,
             Arc::new(RwLock::new(Box::new(DefaultRelation::default()))),
             vec![("name".into(), Term::Lit(Val::String("Quinn".into())))],
+            vec![],
+            None,
             [formula],
             project,
         ));
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
 
 use crate::{
@@ -8,10 +10,12 @@ use crate::{
     var::Var,
 };
 
-use super::{AliasId, Formula, Term};
+use super::{AliasId, Formula, Slot, Term};
 
+/// The values bound while evaluating an operation, indexed by the slots assigned
+/// to their terms during lowering.
 #[derive(Debug, Clone, Default)]
-pub(crate) struct Bindings(im::HashMap<BindingKey, Val>);
+pub(crate) struct Bindings(Vec<Option<Val>>);
 
 // TODO: Put Links in here as they're resolved,
 // so that we can memoize their resolution; see https://github.com/RhizomeDB/rs-rhizome/issues/23
@@ -22,9 +26,17 @@ pub(crate) enum BindingKey {
     Agg(RelationId, Option<AliasId>, Var),
 }
 
+/// Assigns a slot to each binding within the body of a rule.
+#[derive(Debug, Default)]
+pub(crate) struct Slots(HashMap<BindingKey, Slot>);
+
 impl Bindings {
-    pub(crate) fn insert(&mut self, key: BindingKey, term: Val) {
-        self.0.insert(key, term);
+    pub(crate) fn new(slots: usize) -> Self {
+        Self(vec![None; slots])
+    }
+
+    pub(crate) fn insert(&mut self, slot: Slot, val: Val) {
+        self.0[slot] = Some(val);
     }
 
     pub(crate) fn resolve<BS>(&self, term: &Term, _blockstore: &BS) -> Result<Option<Val>>
@@ -32,21 +44,14 @@ impl Bindings {
         BS: Blockstore,
     {
         match term {
-            Term::Col(relation_id, alias, col_id) => Ok(self
-                .0
-                .get(&BindingKey::Relation(*relation_id, *alias, *col_id))
-                .cloned()),
-
-            Term::Cid(relation_id, alias) => {
-                Ok(self.0.get(&BindingKey::Cid(*relation_id, *alias)).cloned())
-            }
-
             Term::Lit(val) => Ok(Some(val).cloned()),
-
-            Term::Agg(relation_id, alias, var) => Ok(self
-                .0
-                .get(&BindingKey::Agg(*relation_id, *alias, *var))
-                .cloned()),
+            Term::Col(_, _, _, slot) | Term::Cid(_, _, slot) | Term::Agg(_, _, _, slot) => {
+                Ok(self.0.get(*slot).cloned().flatten())
+            }
+            Term::Cast(_, _) => Ok(term
+                .slot()
+                .and_then(|slot| self.0.get(slot).cloned().flatten())
+                .map(|val| term.cast(val))),
         }
     }
 
@@ -83,3 +88,74 @@ impl Bindings {
         }
     }
 }
+
+impl Slots {
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(crate) fn col(
+        &mut self,
+        relation_id: RelationId,
+        alias: Option<AliasId>,
+        col_id: ColId,
+    ) -> Term {
+        let slot = self.slot(BindingKey::Relation(
+            relation_id.clone(),
+            alias,
+            col_id.clone(),
+        ));
+
+        Term::Col(relation_id, alias, col_id, slot)
+    }
+
+    pub(crate) fn cid(&mut self, relation_id: RelationId, alias: Option<AliasId>) -> Term {
+        let slot = self.slot(BindingKey::Cid(relation_id.clone(), alias));
+
+        Term::Cid(relation_id, alias, slot)
+    }
+
+    pub(crate) fn agg(
+        &mut self,
+        relation_id: RelationId,
+        alias: Option<AliasId>,
+        var: Var,
+    ) -> Term {
+        let slot = self.slot(BindingKey::Agg(relation_id.clone(), alias, var));
+
+        Term::Agg(relation_id, alias, var, slot)
+    }
+
+    /// Returns the slots of the columns of a relation that are referenced elsewhere
+    /// in the rule, so that they can be bound when it is searched.
+    pub(crate) fn cols_of(
+        &self,
+        relation_id: RelationId,
+        alias: Option<AliasId>,
+    ) -> Vec<(ColId, Slot)> {
+        let mut cols: Vec<(ColId, Slot)> = self
+            .0
+            .iter()
+            .filter_map(|(key, slot)| match key {
+                BindingKey::Relation(id, a, col_id) if *id == relation_id && *a == alias => {
+                    Some((col_id.clone(), *slot))
+                }
+                _ => None,
+            })
+            .collect();
+
+        cols.sort_by_key(|(_, slot)| *slot);
+        cols
+    }
+
+    /// Returns the slot of the CID of a relation, if it's referenced elsewhere in the rule.
+    pub(crate) fn cid_of(&self, relation_id: RelationId, alias: Option<AliasId>) -> Option<Slot> {
+        self.0.get(&BindingKey::Cid(relation_id, alias)).copied()
+    }
+
+    fn slot(&mut self, key: BindingKey) -> Slot {
+        let next = self.0.len();
+
+        *self.0.entry(key).or_insert(next)
+    }
+}
@@ -40,6 +40,15 @@ impl Formula {
     pub(crate) fn predicate(terms: Vec<Term>, f: Arc<dyn PredicateWrapper>) -> Self {
         Self::Predicate(Predicate::new(terms, f))
     }
+
+    /// The relation read when checking the formula, if any.
+    pub(crate) fn relation(&self) -> Option<&Arc<RwLock<Box<dyn Relation>>>> {
+        match self {
+            Formula::Equality(_) => None,
+            Formula::NotIn(inner) => Some(inner.relation()),
+            Formula::Predicate(_) => None,
+        }
+    }
 }
 
 impl Pretty for Formula {
@@ -6,6 +6,7 @@ pub(crate) mod not_in;
 pub(crate) mod operation;
 pub(crate) mod predicate;
 pub(crate) mod program;
+pub(crate) mod schema;
 pub(crate) mod statement;
 pub(crate) mod term;
 
@@ -16,5 +17,6 @@ pub(crate) use formula::*;
 pub(crate) use not_in::*;
 pub(crate) use operation::*;
 pub(crate) use program::*;
+pub(crate) use schema::*;
 pub(crate) use statement::*;
 pub(crate) use term::*;
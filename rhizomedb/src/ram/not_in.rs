@@ -46,6 +46,10 @@ impl NotIn {
         }
     }
 
+    pub(crate) fn relation(&self) -> &Arc<RwLock<Box<dyn Relation>>> {
+        &self.relation
+    }
+
     pub(crate) fn cols(&self) -> &HashMap<ColId, Term> {
         &self.cols
     }
@@ -58,7 +62,7 @@ impl NotIn {
 
         for (id, term) in self.cols() {
             if let Some(val) = bindings.resolve::<BS>(term, blockstore)? {
-                bound.push((*id, <Val>::clone(&val)));
+                bound.push((id.clone(), <Val>::clone(&val)));
             } else {
                 return error(Error::InternalRhizomeError(format!(
                     "failed to resolve term for column: {}",
@@ -1,7 +1,9 @@
+use std::sync::{Arc, RwLock};
+
 use derive_more::IsVariant;
 use pretty::RcDoc;
 
-use crate::pretty::Pretty;
+use crate::{pretty::Pretty, relation::Relation};
 
 pub(crate) mod aggregation;
 pub(crate) mod project;
@@ -18,6 +20,70 @@ pub(crate) enum Operation {
     Aggregation(Aggregation),
 }
 
+impl Operation {
+    /// The projection that the operation ultimately ends in.
+    pub(crate) fn project(&self) -> &Project {
+        match self {
+            Operation::Search(inner) => inner.operation().project(),
+            Operation::Project(inner) => inner,
+            Operation::Aggregation(inner) => inner.operation().project(),
+        }
+    }
+
+    /// The relation that the operation ultimately projects into.
+    pub(crate) fn target(&self) -> &Arc<RwLock<Box<dyn Relation>>> {
+        self.project().relation()
+    }
+
+    /// The relations read while evaluating the operation.
+    pub(crate) fn sources(&self) -> Vec<&Arc<RwLock<Box<dyn Relation>>>> {
+        let (relation, formulae, operation) = match self {
+            Operation::Search(inner) => (
+                Some(inner.relation()),
+                inner.when(),
+                Some(inner.operation()),
+            ),
+            Operation::Project(inner) => (None, inner.formulae(), None),
+            Operation::Aggregation(inner) => (
+                Some(inner.relation()),
+                inner.when(),
+                Some(inner.operation()),
+            ),
+        };
+
+        let (settled, filter) = match self {
+            Operation::Search(inner) => (&[][..], inner.filter()),
+            Operation::Project(inner) => (inner.settled(), &[][..]),
+            Operation::Aggregation(_) => (&[][..], &[][..]),
+        };
+
+        relation
+            .into_iter()
+            .chain(settled)
+            .chain(
+                formulae
+                    .iter()
+                    .chain(filter)
+                    .filter_map(|formula| formula.relation()),
+            )
+            .chain(
+                operation
+                    .into_iter()
+                    .flat_map(|operation| operation.sources()),
+            )
+            .collect()
+    }
+
+    /// Clears the grouped tables of the aggregations within the operation.
+    pub(crate) fn reset_aggregations(&self) {
+        match self {
+            Operation::Search(inner) => inner.operation().reset_aggregations(),
+            Operation::Project(_) => (),
+            Operation::Aggregation(inner) => inner.reset(),
+        }
+    }
+}
+
 impl Pretty for Operation {
     fn to_doc(&self) -> RcDoc<'_, ()> {
         match self {
@@ -2,7 +2,7 @@ use anyhow::Result;
 use std::{
     collections::HashMap,
     fmt::{self, Debug},
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
 };
 
 use pretty::RcDoc;
@@ -12,25 +12,37 @@ use crate::{
     error::{error, Error},
     id::{ColId, RelationId},
     pretty::Pretty,
-    ram::{AliasId, BindingKey, Bindings, Formula, Term},
+    ram::{AliasId, Bindings, Formula, Slot, Term},
     relation::Relation,
     storage::blockstore::Blockstore,
+    tuple::Tuple,
     value::Val,
     var::Var,
 };
 
 use super::Operation;
 
+/// The rows that each group of the aggregated relation binds the targets to, keyed
+/// by the values of the grouping columns.
+type Groups = HashMap<Vec<Val>, Vec<Vec<Val>>>;
+
 pub(crate) struct Aggregation {
     args: Vec<Term>,
     agg: Arc<dyn AggregateWrapper>,
     group_by_cols: HashMap<ColId, Term>,
-    target: Var,
+    targets: Vec<(Var, Slot)>,
     id: RelationId,
     alias: Option<AliasId>,
+    // The slots bound to the columns of each fact being aggregated
+    cols: Vec<(ColId, Slot)>,
     relation: Arc<RwLock<Box<dyn Relation>>>,
     when: Vec<Formula>,
     operation: Box<Operation>,
+    // The aggregated relation is computed in an earlier stratum, so when the
+    // arguments are all read from its facts, every group is aggregated in a single
+    // pass the first time the operation is evaluated in an epoch. The table is
+    // rebuilt if the relation has grown since, as inputs can within a stratum.
+    groups: Mutex<Option<(usize, Arc<Groups>)>>,
 }
 
 impl Aggregation {
@@ -39,10 +51,11 @@ impl Aggregation {
     pub(crate) fn new(
         args: Vec<Term>,
         f: Arc<dyn AggregateWrapper>,
-        target: Var,
+        targets: Vec<(Var, Slot)>,
         group_by_cols: HashMap<ColId, Term>,
         id: RelationId,
         alias: Option<AliasId>,
+        cols: Vec<(ColId, Slot)>,
         relation: Arc<RwLock<Box<dyn Relation>>>,
         when: impl IntoIterator<Item = Formula>,
         operation: Operation,
@@ -52,21 +65,53 @@ impl Aggregation {
         Self {
             args,
             agg: f,
-            target,
+            targets,
             group_by_cols,
             id,
             alias,
+            cols,
             relation,
             when,
             operation: Box::new(operation),
+            groups: Mutex::default(),
         }
     }
 
+    pub(crate) fn args(&self) -> &[Term] {
+        &self.args
+    }
+
+    pub(crate) fn agg(&self) -> &Arc<dyn AggregateWrapper> {
+        &self.agg
+    }
+
+    pub(crate) fn group_by_cols(&self) -> &HashMap<ColId, Term> {
+        &self.group_by_cols
+    }
+
+    pub(crate) fn targets(&self) -> &[(Var, Slot)] {
+        &self.targets
+    }
+
+    pub(crate) fn cols(&self) -> &[(ColId, Slot)] {
+        &self.cols
+    }
+
+    pub(crate) fn relation(&self) -> &Arc<RwLock<Box<dyn Relation>>> {
+        &self.relation
+    }
+
+    pub(crate) fn when(&self) -> &[Formula] {
+        &self.when
+    }
+
     pub(crate) fn operation(&self) -> &Operation {
         &self.operation
     }
 
-    pub(crate) fn apply<BS>(&self, blockstore: &BS, bindings: &Bindings) -> Result<Option<Bindings>>
+    /// Aggregates the group matching the bindings, returning them extended with
+    /// each row of values that the aggregate binds its targets to.
+    pub(crate) fn apply<BS>(&self, blockstore: &BS, bindings: &Bindings) -> Result<Vec<Bindings>>
     where
         BS: Blockstore,
     {
@@ -81,9 +126,154 @@ impl Aggregation {
                     ))
                 })?;
 
-            group_by_vals.push((*col_id, <Val>::clone(&col_val)));
+            group_by_vals.push((col_id.clone(), <Val>::clone(&col_val)));
+        }
+
+        let rows = match self.probe(&group_by_vals)? {
+            Some(rows) => rows,
+            None => self.scan(blockstore, bindings, group_by_vals)?,
+        };
+
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                let mut next_bindings = bindings.clone();
+
+                for ((_, slot), val) in self.targets.iter().zip(row) {
+                    next_bindings.insert(*slot, val);
+                }
+
+                next_bindings
+            })
+            .collect();
+
+        Ok(rows)
+    }
+
+    /// Looks up the rows of a group in the grouped table, building it if needed,
+    /// unless the arguments depend on more than the aggregated facts.
+    pub(crate) fn probe(&self, group_by_vals: &[(ColId, Val)]) -> Result<Option<Vec<Vec<Val>>>> {
+        if !self.is_indexed() {
+            return Ok(None);
+        }
+
+        let groups = {
+            let mut groups = self.groups.lock().or_else(|_| {
+                error(Error::InternalRhizomeError(
+                    "aggregation groups lock poisoned".to_owned(),
+                ))
+            })?;
+
+            let len = self
+                .relation
+                .read()
+                .map(|relation| relation.len())
+                .or_else(|_| {
+                    error(Error::InternalRhizomeError(
+                        "relation lock poisoned".to_owned(),
+                    ))
+                })?;
+
+            match &*groups {
+                Some((built_len, groups)) if *built_len == len => Arc::clone(groups),
+                _ => Arc::clone(&groups.insert((len, Arc::new(self.group()?))).1),
+            }
+        };
+
+        let key = self.key(|col_id| {
+            group_by_vals
+                .iter()
+                .find(|(id, _)| id == col_id)
+                .map(|(_, val)| val.clone())
+        });
+
+        let rows = match key.and_then(|key| groups.get(&key)) {
+            Some(rows) => rows.clone(),
+            // Empty groups are aggregated from nothing, as when they're scanned
+            None => self.agg.init().finalize(),
+        };
+
+        Ok(Some(rows))
+    }
+
+    /// Clears the grouped table, so that it's rebuilt from the aggregated relation
+    /// when next probed.
+    pub(crate) fn reset(&self) {
+        if let Ok(mut groups) = self.groups.lock() {
+            *groups = None;
         }
 
+        self.operation.reset_aggregations();
+    }
+
+    fn is_indexed(&self) -> bool {
+        self.args.iter().all(|term| self.is_fact_term(term))
+    }
+
+    fn is_fact_term(&self, term: &Term) -> bool {
+        match term {
+            Term::Lit(_) => true,
+            Term::Col(id, alias, _, _) => *id == self.id && *alias == self.alias,
+            Term::Cast(inner, _) => self.is_fact_term(inner),
+            Term::Cid(_, _, _) | Term::Agg(_, _, _, _) => false,
+        }
+    }
+
+    fn key(&self, val: impl FnMut(&ColId) -> Option<Val>) -> Option<Vec<Val>> {
+        let mut col_ids = self.group_by_cols.keys().collect::<Vec<_>>();
+        col_ids.sort_by_key(|col_id| col_id.to_string());
+
+        col_ids.into_iter().map(val).collect()
+    }
+
+    /// Aggregates every group of the relation in a single pass over it.
+    fn group(&self) -> Result<Groups> {
+        let relation = self.relation.read().or_else(|_| {
+            error(Error::InternalRhizomeError(
+                "relation lock poisoned".to_owned(),
+            ))
+        })?;
+
+        let mut accumulators: HashMap<Vec<Val>, Box<dyn AggregateWrapper>> = HashMap::default();
+
+        for fact in relation.search(Vec::default()) {
+            let Some(key) = self.key(|col_id| fact.col(col_id)) else {
+                continue;
+            };
+
+            let mut args = Vec::default();
+            for term in &self.args {
+                let resolved = fact_arg(term, fact).ok_or_else(|| {
+                    Error::InternalRhizomeError(
+                        "argument to aggregation failed to resolve".to_owned(),
+                    )
+                })?;
+
+                args.push(resolved);
+            }
+
+            accumulators
+                .entry(key)
+                .or_insert_with(|| self.agg.init())
+                .step(args);
+        }
+
+        Ok(accumulators
+            .into_iter()
+            .map(|(key, accumulator)| (key, accumulator.finalize()))
+            .collect())
+    }
+
+    /// Aggregates a single group by searching the relation for its facts.
+    fn scan<BS>(
+        &self,
+        blockstore: &BS,
+        bindings: &Bindings,
+        group_by_vals: Vec<(ColId, Val)>,
+    ) -> Result<Vec<Vec<Val>>>
+    where
+        BS: Blockstore,
+    {
         let relation = self.relation.read().or_else(|_| {
             error(Error::InternalRhizomeError(
                 "relation lock poisoned".to_owned(),
@@ -94,15 +284,12 @@ impl Aggregation {
         for fact in relation.search(group_by_vals) {
             let mut match_bindings = bindings.clone();
 
-            for k in fact.cols() {
-                let fact_val = fact.col(&k).ok_or_else(|| {
+            for (col_id, slot) in &self.cols {
+                let fact_val = fact.col(col_id).ok_or_else(|| {
                     Error::InternalRhizomeError("expected column not found".to_owned())
                 })?;
 
-                match_bindings.insert(
-                    BindingKey::Relation(self.id, self.alias, k),
-                    fact_val.clone(),
-                );
+                match_bindings.insert(*slot, fact_val);
             }
 
             let mut args = Vec::default();
@@ -122,15 +309,23 @@ impl Aggregation {
             result.step(args);
         }
 
-        if let Some(result) = result.finalize() {
-            let mut next_bindings = bindings.clone();
-            next_bindings.insert(BindingKey::Agg(self.id, self.alias, self.target), result);
+        Ok(result.finalize())
+    }
+}
 
-            Ok(Some(next_bindings))
-        } else {
-            Ok(None)
+/// Resolves an argument to an aggregate from the columns of a fact being
+/// aggregated.
+fn fact_arg(term: &Term, fact: &Tuple) -> Option<Val> {
+    fn col(term: &Term, fact: &Tuple) -> Option<Val> {
+        match term {
+            Term::Lit(val) => Some(val.clone()),
+            Term::Col(_, _, col_id, _) => fact.col(col_id),
+            Term::Cast(inner, _) => col(inner, fact),
+            Term::Cid(_, _, _) | Term::Agg(_, _, _, _) => None,
         }
     }
+
+    col(term, fact).map(|val| term.cast(val))
 }
 
 impl Debug for Aggregation {
@@ -138,7 +333,7 @@ impl Debug for Aggregation {
         f.debug_struct("Aggregation")
             .field("args", &self.args)
             .field("group_by_cols", &self.group_by_cols)
-            .field("target", &self.target)
+            .field("targets", &self.targets)
             .field("id", &self.id)
             .field("alias", &self.alias)
             .field("when", &self.when)
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use std::{
+    cmp::Ordering,
     collections::HashMap,
     sync::{Arc, RwLock},
 };
@@ -10,19 +11,50 @@ use crate::{
     error::{error, Error},
     id::ColId,
     pretty::Pretty,
-    ram::{term::Term, Bindings, Formula},
+    ram::{term::Term, Bindings, Formula, Rejections, RelationSchema},
     relation::{Relation, RelationKey},
     storage::blockstore::Blockstore,
     tuple::Tuple,
     value::Val,
 };
 
+/// A fact to be inserted, along with the bindings of its columns.
+pub(crate) type ProjectedFact = (Vec<(ColId, Val)>, Tuple);
+
+/// Key columns of the projected facts that determine the values of others.
+#[derive(Clone, Debug)]
+pub(crate) struct Choice {
+    keys: Vec<ColId>,
+    vals: Vec<ColId>,
+}
+
+impl Choice {
+    pub(crate) fn new(keys: Vec<ColId>, vals: Vec<ColId>) -> Self {
+        Self { keys, vals }
+    }
+
+    fn key(&self, fact: &Tuple) -> Vec<(ColId, Val)> {
+        self.keys
+            .iter()
+            .filter_map(|col_id| fact.col(col_id).map(|val| (col_id.clone(), val)))
+            .collect()
+    }
+
+    fn vals(&self, fact: &Tuple) -> Vec<Option<Val>> {
+        self.vals.iter().map(|col_id| fact.col(col_id)).collect()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct Project {
     relation_key: RelationKey,
     cols: HashMap<ColId, Term>,
     relation: Arc<RwLock<Box<dyn Relation>>>,
     formulae: Vec<Formula>,
+    schema: Option<RelationSchema>,
+    choices: Vec<Choice>,
+    // The versions of the relation holding facts chosen in earlier iterations
+    settled: Vec<Arc<RwLock<Box<dyn Relation>>>>,
 }
 
 impl Project {
@@ -46,16 +78,137 @@ impl Project {
             cols,
             formulae,
             relation,
+            schema: None,
+            choices: Vec::default(),
+            settled: Vec::default(),
+        }
+    }
+
+    /// Enforces choices on the facts projected, against those already in the
+    /// relation or in the given versions of it.
+    pub(crate) fn with_choices(
+        mut self,
+        choices: Vec<Choice>,
+        settled: Vec<Arc<RwLock<Box<dyn Relation>>>>,
+    ) -> Self {
+        self.choices = choices;
+        self.settled = settled;
+
+        self
+    }
+
+    /// Checks the facts projected in strict mode against the schema of the relation.
+    pub(crate) fn with_schema(mut self, schema: RelationSchema) -> Self {
+        self.schema = Some(schema);
+
+        self
+    }
+
+    pub(crate) fn cols(&self) -> &HashMap<ColId, Term> {
+        &self.cols
+    }
+
+    pub(crate) fn relation(&self) -> &Arc<RwLock<Box<dyn Relation>>> {
+        &self.relation
+    }
+
+    pub(crate) fn formulae(&self) -> &[Formula] {
+        &self.formulae
+    }
+
+    pub(crate) fn settled(&self) -> &[Arc<RwLock<Box<dyn Relation>>>] {
+        &self.settled
+    }
+
+    pub(crate) fn apply<BS>(
+        &self,
+        blockstore: &BS,
+        bindings: &Bindings,
+        rejections: Option<&Rejections>,
+    ) -> Result<()>
+    where
+        BS: Blockstore,
+    {
+        if let Some((bound, fact)) = self.project(blockstore, bindings, rejections)? {
+            let mut relation = self.relation.write().or_else(|_| {
+                error(Error::InternalRhizomeError(
+                    "relation lock poisoned".to_owned(),
+                ))
+            })?;
+
+            self.insert(&mut relation, bound, fact)?;
         }
+
+        Ok(())
     }
 
-    pub(crate) fn apply<BS>(&self, blockstore: &BS, bindings: &Bindings) -> Result<()>
+    /// Inserts a projected fact into the relation, unless a choice rejects it for
+    /// conflicting with a fact settled earlier, or with a lesser one in the relation.
+    /// Any greater facts it conflicts with in the relation are replaced.
+    pub(crate) fn insert(
+        &self,
+        relation: &mut Box<dyn Relation>,
+        bound: Vec<(ColId, Val)>,
+        fact: Tuple,
+    ) -> Result<()> {
+        let mut superseded = Vec::default();
+
+        for choice in &self.choices {
+            let key = choice.key(&fact);
+            let vals = choice.vals(&fact);
+
+            for settled in &self.settled {
+                let settled = settled.read().or_else(|_| {
+                    error(Error::InternalRhizomeError(
+                        "relation lock poisoned".to_owned(),
+                    ))
+                })?;
+
+                if settled
+                    .search(key.clone())
+                    .any(|other| choice.vals(other) != vals)
+                {
+                    return Ok(());
+                }
+            }
+
+            for other in relation.search(key) {
+                match choice.vals(other).cmp(&vals) {
+                    Ordering::Less => return Ok(()),
+                    Ordering::Equal => (),
+                    Ordering::Greater => superseded.push(other.clone()),
+                }
+            }
+        }
+
+        for other in superseded {
+            let bound = other
+                .cols()
+                .into_iter()
+                .filter_map(|col_id| other.col(&col_id).map(|val| (col_id, val)))
+                .collect();
+
+            relation.remove(bound, &other);
+        }
+
+        relation.insert(bound, fact);
+
+        Ok(())
+    }
+
+    /// Computes the fact to insert for the given bindings, without inserting it.
+    pub(crate) fn project<BS>(
+        &self,
+        blockstore: &BS,
+        bindings: &Bindings,
+        rejections: Option<&Rejections>,
+    ) -> Result<Option<ProjectedFact>>
     where
         BS: Blockstore,
     {
         for formula in self.formulae.iter() {
             if !bindings.is_formula_satisfied::<BS>(formula, blockstore)? {
-                return Ok(());
+                return Ok(None);
             }
         }
 
@@ -63,24 +216,44 @@ impl Project {
 
         for (id, term) in &self.cols {
             if let Some(val) = bindings.resolve::<BS>(term, blockstore)? {
-                bound.push((*id, <Val>::clone(&val)));
+                bound.push((id.clone(), <Val>::clone(&val)));
             } else {
-                return Ok(());
+                return Ok(None);
             }
         }
 
-        let fact = Tuple::new(self.relation_key.0, bound.clone(), None);
+        self.conform(bound, rejections)
+    }
 
-        self.relation
-            .write()
-            .or_else(|_| {
-                error(Error::InternalRhizomeError(
-                    "relation lock poisoned".to_owned(),
-                ))
-            })?
-            .insert(bound, fact);
+    /// Builds the fact for the bound columns, unless it's rejected for not
+    /// conforming to the schema of the relation.
+    pub(crate) fn conform(
+        &self,
+        bound: Vec<(ColId, Val)>,
+        rejections: Option<&Rejections>,
+    ) -> Result<Option<ProjectedFact>> {
+        let bound = match (rejections, &self.schema) {
+            (Some(rejections), Some(schema)) => match schema.conform(bound.clone()) {
+                Ok(bound) => bound,
+                Err(err) => {
+                    rejections
+                        .lock()
+                        .or_else(|_| {
+                            error(Error::InternalRhizomeError(
+                                "rejections lock poisoned".to_owned(),
+                            ))
+                        })?
+                        .push((Tuple::new(self.relation_key.0.clone(), bound, None), err));
 
-        Ok(())
+                    return Ok(None);
+                }
+            },
+            _ => bound,
+        };
+
+        let fact = Tuple::new(self.relation_key.0.clone(), bound.clone(), None);
+
+        Ok(Some((bound, fact)))
     }
 }
 
@@ -112,6 +285,22 @@ impl Pretty for Project {
                 .append(RcDoc::text(")"))
         };
 
+        let choose_doc = RcDoc::concat(self.choices.iter().map(|choice| {
+            let cols_doc = |cols: &[ColId]| {
+                RcDoc::text("(")
+                    .append(RcDoc::intersperse(
+                        cols.iter().map(RcDoc::as_string),
+                        RcDoc::text(", "),
+                    ))
+                    .append(RcDoc::text(")"))
+            };
+
+            RcDoc::text(" choosing ")
+                .append(cols_doc(&choice.keys))
+                .append(RcDoc::text(" -> "))
+                .append(cols_doc(&choice.vals))
+        }));
+
         RcDoc::concat([
             RcDoc::text("project "),
             RcDoc::text("("),
@@ -120,6 +309,7 @@ impl Pretty for Project {
             RcDoc::text(")"),
             RcDoc::text(" into "),
             self.relation_key.to_doc(),
+            choose_doc,
         ])
     }
 }
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
 
 use pretty::RcDoc;
 
@@ -7,9 +7,10 @@ use crate::{
     error::{error, Error},
     id::ColId,
     pretty::Pretty,
-    ram::{alias_id::AliasId, formula::Formula, BindingKey, Bindings, Term},
+    ram::{alias_id::AliasId, formula::Formula, Bindings, Slot, Term},
     relation::{Relation, RelationKey},
     storage::blockstore::Blockstore,
+    tuple::Tuple,
     value::Val,
 };
 
@@ -21,16 +22,27 @@ pub(crate) struct Search {
     alias: Option<AliasId>,
     relation: Arc<RwLock<Box<dyn Relation>>>,
     bindings: Vec<(ColId, Term)>,
+    // The slots bound to the columns and CID of each fact that is found
+    cols: Vec<(ColId, Slot)>,
+    cid: Option<Slot>,
+    // The formulae deciding whether a fact is matched, and those filtering the
+    // bindings of each match, or of the null row of an outer search
     when: Vec<Formula>,
+    filter: Vec<Formula>,
     operation: Box<Operation>,
+    // Whether the search binds its slots to null when no fact matches
+    is_outer: bool,
 }
 
 impl Search {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         relation_key: RelationKey,
         alias: Option<AliasId>,
         relation: Arc<RwLock<Box<dyn Relation>>>,
         bindings: Vec<(ColId, Term)>,
+        cols: Vec<(ColId, Slot)>,
+        cid: Option<Slot>,
         when: impl IntoIterator<Item = Formula>,
         operation: Operation,
     ) -> Self {
@@ -41,11 +53,53 @@ impl Search {
             alias,
             relation,
             bindings,
+            cols,
+            cid,
             when,
+            filter: Vec::default(),
             operation: Box::new(operation),
+            is_outer: false,
         }
     }
 
+    pub(crate) fn with_filter(mut self, filter: impl IntoIterator<Item = Formula>) -> Self {
+        self.filter = filter.into_iter().collect();
+        self
+    }
+
+    pub(crate) fn outer(mut self) -> Self {
+        self.is_outer = true;
+        self
+    }
+
+    pub(crate) fn is_outer(&self) -> bool {
+        self.is_outer
+    }
+
+    pub(crate) fn bindings(&self) -> &[(ColId, Term)] {
+        &self.bindings
+    }
+
+    pub(crate) fn cols(&self) -> &[(ColId, Slot)] {
+        &self.cols
+    }
+
+    pub(crate) fn cid(&self) -> Option<Slot> {
+        self.cid
+    }
+
+    pub(crate) fn relation(&self) -> &Arc<RwLock<Box<dyn Relation>>> {
+        &self.relation
+    }
+
+    pub(crate) fn when(&self) -> &[Formula] {
+        &self.when
+    }
+
+    pub(crate) fn filter(&self) -> &[Formula] {
+        &self.filter
+    }
+
     pub(crate) fn operation(&self) -> &Operation {
         &self.operation
     }
@@ -55,64 +109,164 @@ impl Search {
         BS: Blockstore,
         F: Fn(Bindings) -> Result<bool>,
     {
-        let mut bound_cols = vec![];
-        for (col_id, term) in self.bindings.iter() {
-            let resolved = bindings.resolve::<BS>(term, blockstore)?.ok_or_else(|| {
-                Error::InternalRhizomeError("expected binding not found".to_owned())
-            })?;
+        let bound_cols = self.bound_cols(blockstore, bindings)?;
+        let mut is_matched = false;
 
-            bound_cols.push((*col_id, <Val>::clone(&resolved)));
+        for fact in self.read()?.search(bound_cols) {
+            let Some(next_bindings) = self.join(blockstore, bindings, fact)? else {
+                continue;
+            };
+
+            is_matched = true;
+
+            if let Some(next_bindings) = self.filtered(blockstore, next_bindings)? {
+                if !f(next_bindings)? {
+                    return Ok(false);
+                };
+            }
         }
 
-        for fact in self
-            .relation
-            .read()
-            .or_else(|_| {
-                error(Error::InternalRhizomeError(
-                    "relation lock poisoned".to_owned(),
-                ))
-            })?
-            .search(bound_cols)
-        {
-            let mut next_bindings = bindings.clone();
-
-            // TODO: Only add the CID to the bindings if it's required by
-            // a later operation.
-            if let Some(cid) = fact.cid() {
-                next_bindings.insert(
-                    BindingKey::Cid(self.relation_key.0, self.alias),
-                    Val::Cid(cid),
-                );
+        if self.is_outer && !is_matched {
+            if let Some(next_bindings) = self.bind_null(blockstore, bindings)? {
+                return f(next_bindings);
             }
+        }
 
-            for k in fact.cols() {
-                let v = fact.col(&k).ok_or_else(|| {
-                    Error::InternalRhizomeError("expected column not found".to_owned())
-                })?;
+        Ok(true)
+    }
 
-                next_bindings.insert(
-                    BindingKey::Relation(self.relation_key.0, self.alias, k),
-                    v.clone(),
-                );
-            }
+    pub(crate) fn len(&self) -> Result<usize> {
+        Ok(self.read()?.len())
+    }
 
-            let mut satisfied = true;
-            for formula in self.when.iter() {
-                if !next_bindings.is_formula_satisfied::<BS>(formula, blockstore)? {
-                    satisfied = false;
-                }
+    /// Calls `f` with the facts matching the columns bound by the search, without
+    /// yet checking any formulae. The relation stays read locked until `f` returns.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn with_candidates<BS, R>(
+        &self,
+        blockstore: &BS,
+        bindings: &Bindings,
+        f: impl FnOnce(&[&Tuple]) -> Result<R>,
+    ) -> Result<R>
+    where
+        BS: Blockstore,
+    {
+        let bound_cols = self.bound_cols(blockstore, bindings)?;
+        let relation = self.read()?;
+
+        f(&relation.search(bound_cols).collect::<Vec<_>>())
+    }
+
+    /// Extends the bindings with those of a fact, if the fact satisfies the formulae
+    /// of the search.
+    pub(crate) fn bind<BS>(
+        &self,
+        blockstore: &BS,
+        bindings: &Bindings,
+        fact: &Tuple,
+    ) -> Result<Option<Bindings>>
+    where
+        BS: Blockstore,
+    {
+        match self.join(blockstore, bindings, fact)? {
+            Some(next_bindings) => self.filtered(blockstore, next_bindings),
+            None => Ok(None),
+        }
+    }
+
+    /// Extends the bindings with those of a fact, if the fact is matched.
+    fn join<BS>(
+        &self,
+        blockstore: &BS,
+        bindings: &Bindings,
+        fact: &Tuple,
+    ) -> Result<Option<Bindings>>
+    where
+        BS: Blockstore,
+    {
+        let mut next_bindings = bindings.clone();
+
+        if let (Some(slot), Some(cid)) = (self.cid, fact.cid()) {
+            next_bindings.insert(slot, Val::Cid(cid));
+        }
+
+        for (col_id, slot) in &self.cols {
+            let v = fact.col(col_id).ok_or_else(|| {
+                Error::InternalRhizomeError("expected column not found".to_owned())
+            })?;
+
+            next_bindings.insert(*slot, v);
+        }
+
+        for formula in self.when.iter() {
+            if !next_bindings.is_formula_satisfied::<BS>(formula, blockstore)? {
+                return Ok(None);
             }
+        }
 
-            if !satisfied {
-                continue;
+        Ok(Some(next_bindings))
+    }
+
+    /// Extends the bindings with nulls for each slot of the search, as an outer search
+    /// does when no fact matches.
+    pub(crate) fn bind_null<BS>(
+        &self,
+        blockstore: &BS,
+        bindings: &Bindings,
+    ) -> Result<Option<Bindings>>
+    where
+        BS: Blockstore,
+    {
+        let mut next_bindings = bindings.clone();
+
+        for slot in self.null_slots() {
+            next_bindings.insert(slot, Val::Null);
+        }
+
+        self.filtered(blockstore, next_bindings)
+    }
+
+    fn filtered<BS>(&self, blockstore: &BS, bindings: Bindings) -> Result<Option<Bindings>>
+    where
+        BS: Blockstore,
+    {
+        for formula in self.filter.iter() {
+            if !bindings.is_formula_satisfied::<BS>(formula, blockstore)? {
+                return Ok(None);
             }
+        }
 
-            if !f(next_bindings)? {
-                return Ok(false);
-            };
+        Ok(Some(bindings))
+    }
+
+    pub(crate) fn null_slots(&self) -> impl Iterator<Item = Slot> + '_ {
+        self.cid
+            .into_iter()
+            .chain(self.cols.iter().map(|(_, slot)| *slot))
+    }
+
+    fn bound_cols<BS>(&self, blockstore: &BS, bindings: &Bindings) -> Result<Vec<(ColId, Val)>>
+    where
+        BS: Blockstore,
+    {
+        let mut bound_cols = vec![];
+        for (col_id, term) in self.bindings.iter() {
+            let resolved = bindings.resolve::<BS>(term, blockstore)?.ok_or_else(|| {
+                Error::InternalRhizomeError("expected binding not found".to_owned())
+            })?;
+
+            bound_cols.push((col_id.clone(), <Val>::clone(&resolved)));
         }
 
-        Ok(true)
+        Ok(bound_cols)
+    }
+
+    fn read(&self) -> Result<RwLockReadGuard<'_, Box<dyn Relation>>> {
+        self.relation.read().or_else(|_| {
+            error(Error::InternalRhizomeError(
+                "relation lock poisoned".to_owned(),
+            ))
+        })
     }
 }
 
@@ -122,14 +276,14 @@ impl Pretty for Search {
             Some(alias) => RcDoc::concat([
                 self.relation_key.to_doc(),
                 RcDoc::text(" as "),
-                RcDoc::as_string(self.relation_key.0),
+                RcDoc::as_string(&self.relation_key.0),
                 RcDoc::text("_"),
                 RcDoc::as_string(alias),
             ]),
             None => self.relation_key.to_doc(),
         };
 
-        let when_doc = if self.when.is_empty() {
+        let when_doc = if self.when.is_empty() && self.filter.is_empty() {
             RcDoc::nil()
         } else {
             RcDoc::text(" where")
@@ -146,7 +300,8 @@ impl Pretty for Search {
                                     term.to_doc(),
                                 ])
                             })
-                            .chain(self.when.iter().map(|formula| formula.to_doc())),
+                            .chain(self.when.iter().map(|formula| formula.to_doc()))
+                            .chain(self.filter.iter().map(|formula| formula.to_doc())),
                         RcDoc::text(" and "),
                     )
                     .nest(1)
@@ -155,13 +310,13 @@ impl Pretty for Search {
                 .append(RcDoc::text(")"))
         };
 
-        RcDoc::concat([
-            RcDoc::text("search "),
-            relation_doc,
-            when_doc,
-            RcDoc::text(" do"),
-        ])
-        .append(
+        let search_doc = if self.is_outer {
+            RcDoc::text("search optional ")
+        } else {
+            RcDoc::text("search ")
+        };
+
+        RcDoc::concat([search_doc, relation_doc, when_doc, RcDoc::text(" do")]).append(
             RcDoc::hardline()
                 .append(self.operation().to_doc())
                 .nest(2)
@@ -25,10 +25,14 @@ impl Predicate {
     }
 
     pub(crate) fn is_satisfied(&self, args: Vec<Val>) -> Result<bool> {
-        Ok(self
-            .f
-            .apply(args)
-            .ok_or_else(|| Error::InternalRhizomeError("failed to apply predicate".to_owned()))?)
+        // A predicate over non-optional arguments is never satisfied by null
+        let is_null = args.iter().any(|arg| matches!(arg, Val::Null));
+
+        match self.f.apply(args) {
+            Some(satisfied) => Ok(satisfied),
+            None if is_null => Ok(false),
+            None => Err(Error::InternalRhizomeError("failed to apply predicate".to_owned()).into()),
+        }
     }
 }
 
@@ -1,11 +1,13 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::{Arc, RwLock},
 };
 
 use pretty::RcDoc;
 
 use crate::{
+    id::RelationId,
+    logic::rewrite::Rewrite,
     pretty::Pretty,
     relation::{Relation, RelationKey},
 };
@@ -17,6 +19,8 @@ pub struct Program {
     is_monotonic: bool,
     relations: HashMap<RelationKey, Arc<RwLock<Box<dyn Relation>>>>,
     statements: Vec<Arc<Statement>>,
+    outputs: HashSet<RelationId>,
+    rewrites: Vec<Rewrite>,
 }
 
 impl Program {
@@ -29,9 +33,23 @@ impl Program {
             is_monotonic,
             relations,
             statements,
+            outputs: HashSet::default(),
+            rewrites: Vec::default(),
         }
     }
 
+    pub(crate) fn with_outputs(mut self, outputs: HashSet<RelationId>) -> Self {
+        self.outputs = outputs;
+
+        self
+    }
+
+    pub(crate) fn with_rewrites(mut self, rewrites: Vec<Rewrite>) -> Self {
+        self.rewrites = rewrites;
+
+        self
+    }
+
     pub(crate) fn is_monotonic(&self) -> bool {
         self.is_monotonic
     }
@@ -43,16 +61,49 @@ impl Program {
     pub(crate) fn statements(&self) -> &[Arc<Statement>] {
         &self.statements
     }
+
+    /// Returns the output relations, whose tuples are sunk.
+    pub(crate) fn outputs(&self) -> &HashSet<RelationId> {
+        &self.outputs
+    }
+
+    /// Clears the grouped tables of every aggregation, whose relations may have
+    /// changed since they were built.
+    pub(crate) fn reset_aggregations(&self) {
+        fn reset(statement: &Statement) {
+            match statement {
+                Statement::Insert(insert) => insert.operation().reset_aggregations(),
+                Statement::Loop(inner) => inner.body().iter().for_each(|s| reset(s)),
+                _ => (),
+            }
+        }
+
+        self.statements.iter().for_each(|s| reset(s));
+    }
 }
 
 impl Pretty for Program {
     fn to_doc(&self) -> RcDoc<'_, ()> {
-        RcDoc::intersperse(
-            self.statements().iter().map(|statement| statement.to_doc()),
-            RcDoc::text(";")
+        let rewrites_doc = RcDoc::concat(self.rewrites.iter().map(|rewrite| {
+            RcDoc::text("// ")
+                .append(RcDoc::as_string(rewrite))
                 .append(RcDoc::hardline())
-                .append(RcDoc::hardline()),
+        }));
+
+        let rewrites_doc = if self.rewrites.is_empty() {
+            rewrites_doc
+        } else {
+            rewrites_doc.append(RcDoc::hardline())
+        };
+
+        rewrites_doc.append(
+            RcDoc::intersperse(
+                self.statements().iter().map(|statement| statement.to_doc()),
+                RcDoc::text(";")
+                    .append(RcDoc::hardline())
+                    .append(RcDoc::hardline()),
+            )
+            .append(RcDoc::text(";")),
         )
-        .append(RcDoc::text(";"))
     }
 }
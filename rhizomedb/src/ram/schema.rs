@@ -0,0 +1,73 @@
+use std::sync::Mutex;
+
+use crate::{
+    col::Col,
+    col_val::ColVal,
+    error::Error,
+    id::{ColId, RelationId},
+    tuple::Tuple,
+    types::ColType,
+    value::Val,
+};
+
+/// The tuples that didn't conform to the schemas of the relations they were
+/// inserted into, along with why.
+pub(crate) type Rejections = Mutex<Vec<(Tuple, Error)>>;
+
+/// The columns of a relation, which the tuples inserted into it are checked
+/// against in strict mode.
+#[derive(Clone, Debug)]
+pub(crate) struct RelationSchema {
+    id: RelationId,
+    cols: Vec<Col>,
+}
+
+impl RelationSchema {
+    pub(crate) fn new(id: RelationId, cols: impl IntoIterator<Item = Col>) -> Self {
+        Self {
+            id,
+            cols: cols.into_iter().collect(),
+        }
+    }
+
+    /// Checks the bindings of a tuple against the columns of the relation, widening
+    /// each value to the type of its column.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn conform(&self, bound: Vec<(ColId, Val)>) -> Result<Vec<(ColId, Val)>, Error> {
+        if let Some((col_id, _)) = bound
+            .iter()
+            .find(|(col_id, _)| !self.cols.iter().any(|col| col.id() == *col_id))
+        {
+            return Err(Error::UnrecognizedColumnBinding(
+                self.id.clone(),
+                col_id.clone(),
+            ));
+        }
+
+        let mut conformed = Vec::with_capacity(self.cols.len());
+
+        for col in &self.cols {
+            let Some((_, val)) = bound.iter().find(|(col_id, _)| *col_id == col.id()) else {
+                return Err(Error::ColumnMissing(self.id.clone(), col.id()));
+            };
+
+            if col.check(val).is_err() {
+                return Err(Error::ColumnValueTypeConflict(
+                    self.id.clone(),
+                    col.id(),
+                    ColVal::Lit(val.clone()),
+                    *col.col_type(),
+                ));
+            }
+
+            let val = match col.col_type() {
+                ColType::Type(typ) => val.clone().widen(*typ).unwrap_or_else(|| val.clone()),
+                ColType::Any => val.clone(),
+            };
+
+            conformed.push((col.id(), val));
+        }
+
+        Ok(conformed)
+    }
+}
@@ -5,6 +5,8 @@ use crate::{pretty::Pretty, ram::operation::Operation};
 #[derive(Debug)]
 pub(crate) struct Insert {
     operation: Operation,
+    // The number of slots bound while evaluating the operation
+    slots: usize,
     // Whether the insertion is for a ground atom with all constant columns.
     // I don't love this, but it enables us to ensure ground facts are only inserted
     // into the delta relation once.
@@ -12,9 +14,10 @@ pub(crate) struct Insert {
 }
 
 impl Insert {
-    pub(crate) fn new(operation: Operation, is_ground: bool) -> Self {
+    pub(crate) fn new(operation: Operation, slots: usize, is_ground: bool) -> Self {
         Self {
             operation,
+            slots,
             is_ground,
         }
     }
@@ -23,6 +26,10 @@ impl Insert {
         &self.operation
     }
 
+    pub(crate) fn slots(&self) -> usize {
+        self.slots
+    }
+
     pub(crate) fn is_ground(&self) -> bool {
         self.is_ground
     }
@@ -32,7 +32,12 @@ impl Merge {
         }
     }
 
-    pub(crate) fn apply(&self) -> Result<()> {
+    pub(crate) fn target_key(&self) -> RelationKey {
+        self.into_key.clone()
+    }
+
+    /// Returns the number of tuples in the relation merged into.
+    pub(crate) fn apply(&self) -> Result<usize> {
         let mut merge_into = self.into_relation.write().or_else(|_| {
             error(Error::InternalRhizomeError(
                 "relation lock poisoned".to_owned(),
@@ -49,7 +54,7 @@ impl Merge {
 
         debug_assert!(merge_into.len() >= merge_from.len());
 
-        Ok(())
+        Ok(merge_into.len())
     }
 }
 
@@ -1,26 +1,106 @@
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use pretty::RcDoc;
 
-use crate::pretty::Pretty;
+use crate::{pretty::Pretty, relation::Relation};
 
 use super::Statement;
 
 #[derive(Debug)]
 pub(crate) struct Loop {
     body: Vec<Arc<Statement>>,
+    // The parallel inserts starting at each statement of the body, if any
+    parallel: Vec<Option<ParallelInserts>>,
+}
+
+/// A run of consecutive inserts in a loop body, grouped by the relation they
+/// insert into, where the groups are independent of one another and so can be
+/// evaluated in parallel.
+#[derive(Debug)]
+pub(crate) struct ParallelInserts {
+    len: usize,
+    groups: Vec<Vec<Arc<Statement>>>,
 }
 
 impl Loop {
     pub(crate) fn new(body: impl IntoIterator<Item = Arc<Statement>>) -> Self {
-        let body = body.into_iter().collect();
+        let body: Vec<_> = body.into_iter().collect();
+        let parallel = (0..body.len())
+            .map(|start| ParallelInserts::new(&body[start..]))
+            .collect();
 
-        Self { body }
+        Self { body, parallel }
     }
 
     pub(crate) fn body(&self) -> &[Arc<Statement>] {
         &self.body
     }
+
+    pub(crate) fn parallel_inserts(&self, start: usize) -> Option<&ParallelInserts> {
+        self.parallel.get(start).and_then(Option::as_ref)
+    }
+}
+
+impl ParallelInserts {
+    fn new(body: &[Arc<Statement>]) -> Option<Self> {
+        let run: Vec<&Arc<Statement>> = body
+            .iter()
+            .take_while(|statement| {
+                matches!(&***statement, Statement::Insert(insert) if !insert.is_ground())
+            })
+            .collect();
+
+        let mut targets: Vec<&Arc<RwLock<Box<dyn Relation>>>> = Vec::default();
+        let mut groups: Vec<Vec<Arc<Statement>>> = Vec::default();
+
+        for statement in &run {
+            let Statement::Insert(insert) = &***statement else {
+                continue;
+            };
+
+            let target = insert.operation().target();
+
+            match targets.iter().position(|t| Arc::ptr_eq(t, target)) {
+                Some(i) => groups[i].push(Arc::clone(statement)),
+                None => {
+                    targets.push(target);
+                    groups.push(vec![Arc::clone(statement)]);
+                }
+            }
+        }
+
+        if groups.len() < 2 {
+            return None;
+        }
+
+        // An insert reading from a relation that another insert in the run writes
+        // to would observe a partially computed relation.
+        let is_independent = run.iter().all(|statement| match &***statement {
+            Statement::Insert(insert) => insert
+                .operation()
+                .sources()
+                .iter()
+                .all(|source| targets.iter().all(|target| !Arc::ptr_eq(source, target))),
+            _ => true,
+        });
+
+        if !is_independent {
+            return None;
+        }
+
+        Some(Self {
+            len: run.len(),
+            groups,
+        })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn groups(&self) -> &[Vec<Arc<Statement>>] {
+        &self.groups
+    }
 }
 
 impl Pretty for Loop {
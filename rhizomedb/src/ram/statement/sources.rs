@@ -10,6 +10,7 @@ use crate::{
     error::{error, Error},
     id::{ColId, RelationId},
     pretty::Pretty,
+    ram::{Rejections, RelationSchema},
     relation::Relation,
     tuple::Tuple,
     value::Val,
@@ -18,6 +19,7 @@ use crate::{
 #[derive(Debug, Default)]
 pub(crate) struct SourcesBuilder {
     relations: HashMap<RelationId, Arc<RwLock<Box<dyn Relation>>>>,
+    schemas: HashMap<RelationId, RelationSchema>,
 }
 
 impl SourcesBuilder {
@@ -25,13 +27,16 @@ impl SourcesBuilder {
         &mut self,
         id: RelationId,
         relation: Arc<RwLock<Box<dyn Relation>>>,
+        schema: RelationSchema,
     ) {
-        self.relations.insert(id, relation);
+        self.relations.insert(id.clone(), relation);
+        self.schemas.insert(id, schema);
     }
 
     pub(crate) fn finalize(self) -> Sources {
         Sources {
             relations: self.relations,
+            schemas: self.schemas,
         }
     }
 }
@@ -39,19 +44,45 @@ impl SourcesBuilder {
 #[derive(Clone, Debug, Default)]
 pub(crate) struct Sources {
     relations: HashMap<RelationId, Arc<RwLock<Box<dyn Relation>>>>,
+    schemas: HashMap<RelationId, RelationSchema>,
 }
 
 impl Sources {
-    pub(crate) fn apply(&self, input: &mut VecDeque<Tuple>) -> Result<bool> {
+    /// Inserts the input tuples into their relations. When given somewhere to
+    /// record them, tuples that don't conform to their schemas are rejected.
+    pub(crate) fn apply(
+        &self,
+        input: &mut VecDeque<Tuple>,
+        rejections: Option<&Rejections>,
+    ) -> Result<bool> {
         let mut has_new_facts = false;
 
         while let Some(tuple) = input.pop_front() {
             let mut bindings: Vec<(ColId, Val)> = Vec::default();
             for col_id in tuple.cols() {
-                bindings.push((col_id, <Val>::clone(&tuple.col(&col_id).unwrap())));
+                bindings.push((col_id.clone(), <Val>::clone(&tuple.col(&col_id).unwrap())));
             }
 
             let id = tuple.id();
+
+            if let (Some(rejections), Some(schema)) = (rejections, self.schemas.get(&id)) {
+                match schema.conform(bindings) {
+                    Ok(conformed) => bindings = conformed,
+                    Err(err) => {
+                        rejections
+                            .lock()
+                            .or_else(|_| {
+                                error(Error::InternalRhizomeError(
+                                    "rejections lock poisoned".to_owned(),
+                                ))
+                            })?
+                            .push((tuple, err));
+
+                        continue;
+                    }
+                }
+            }
+
             let relation = self
                 .relations
                 .get(&id)
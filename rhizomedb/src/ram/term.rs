@@ -4,18 +4,50 @@ use pretty::RcDoc;
 use crate::{
     id::{ColId, RelationId},
     pretty::Pretty,
+    types::Type,
     value::Val,
     var::Var,
 };
 
 use super::AliasId;
 
+/// The index of a binding within the bindings of an insert statement.
+pub type Slot = usize;
+
 #[derive(Clone, Debug, From, IsVariant, TryInto)]
 pub enum Term {
     Lit(Val),
-    Col(RelationId, Option<AliasId>, ColId),
-    Cid(RelationId, Option<AliasId>),
-    Agg(RelationId, Option<AliasId>, Var),
+    Col(RelationId, Option<AliasId>, ColId, Slot),
+    Cid(RelationId, Option<AliasId>, Slot),
+    Agg(RelationId, Option<AliasId>, Var, Slot),
+    /// A term whose value is widened to the given type.
+    Cast(Box<Term>, Type),
+}
+
+impl Term {
+    /// The slot that the term is bound to, unless it's a literal.
+    pub fn slot(&self) -> Option<Slot> {
+        match self {
+            Term::Lit(_) => None,
+            Term::Col(_, _, _, slot) | Term::Cid(_, _, slot) | Term::Agg(_, _, _, slot) => {
+                Some(*slot)
+            }
+            Term::Cast(inner, _) => inner.slot(),
+        }
+    }
+
+    /// Widens a value bound to the term, leaving values that can't be widened, such
+    /// as nulls, unchanged.
+    pub(crate) fn cast(&self, val: Val) -> Val {
+        match self {
+            Term::Cast(inner, typ) => {
+                let val = inner.cast(val);
+
+                val.clone().widen(*typ).unwrap_or(val)
+            }
+            _ => val,
+        }
+    }
 }
 
 impl Pretty for Term {
@@ -35,17 +67,17 @@ impl Pretty for Term {
         }
 
         match self {
-            Term::Col(relation_id, alias_id, col_id) => RcDoc::concat([
+            Term::Col(relation_id, alias_id, col_id, _) => RcDoc::concat([
                 relation_binding(relation_id, alias_id),
                 RcDoc::text("."),
                 RcDoc::as_string(col_id),
             ]),
-            Term::Cid(relation_id, alias_id) => RcDoc::concat([
+            Term::Cid(relation_id, alias_id, _) => RcDoc::concat([
                 RcDoc::text("cid("),
                 relation_binding(relation_id, alias_id),
                 RcDoc::text(")"),
             ]),
-            Term::Agg(relation_id, alias_id, var) => RcDoc::concat([
+            Term::Agg(relation_id, alias_id, var, _) => RcDoc::concat([
                 RcDoc::text("("),
                 relation_binding(relation_id, alias_id),
                 RcDoc::text("."),
@@ -53,6 +85,13 @@ impl Pretty for Term {
                 RcDoc::text(")"),
             ]),
             Term::Lit(value) => RcDoc::as_string(value),
+            Term::Cast(inner, typ) => RcDoc::concat([
+                RcDoc::text("("),
+                inner.to_doc(),
+                RcDoc::text(" as "),
+                RcDoc::as_string(typ),
+                RcDoc::text(")"),
+            ]),
         }
     }
 }
@@ -103,6 +103,15 @@ where
         Ok(())
     }
 
+    pub(crate) fn remove(&mut self, bindings: Vec<(ColId, Val)>, val: &T) {
+        let (Some(f), Some(t)) = Self::bindings_to_cols(bindings) else {
+            return;
+        };
+
+        Self::index_remove(&mut self.ft, (f.clone(), t.clone()), val);
+        Self::index_remove(&mut self.tf, (t, f), val);
+    }
+
     pub(crate) fn search(&self, bindings: Vec<(ColId, Val)>) -> BTreeSet<&T> {
         let (f, t) = Self::bindings_to_cols(bindings);
 
@@ -175,6 +184,30 @@ where
         };
     }
 
+    fn index_remove<K1, K2>(index: &mut Index<K1, K2, T>, keys: (K1, K2), val: &T)
+    where
+        K1: Key,
+        K2: Key,
+    {
+        let (k1, k2) = keys;
+
+        let Some(v1) = index.get_mut(&k1) else {
+            return;
+        };
+
+        if let Some(v2) = v1.get_mut(&k2) {
+            v2.remove(val);
+
+            if v2.is_empty() {
+                v1.remove(&k2);
+            }
+        }
+
+        if v1.is_empty() {
+            index.remove(&k1);
+        }
+    }
+
     fn index_search_0<K1, K2>(index: &Index<K1, K2, T>) -> BTreeSet<&T>
     where
         K1: Key,
@@ -299,6 +332,10 @@ impl Relation for Bistore<Tuple> {
         self.insert(bindings, val).unwrap()
     }
 
+    fn remove(&mut self, bindings: Vec<(ColId, Val)>, val: &Tuple) {
+        self.remove(bindings, val)
+    }
+
     fn merge(&mut self, rhs: &dyn Relation) {
         if let Some(rhs) = rhs.downcast_ref::<Self>() {
             self.merge(rhs)
@@ -158,6 +158,19 @@ where
         Ok(())
     }
 
+    pub(crate) fn remove(&mut self, bindings: Vec<(ColId, Val)>, val: &T) {
+        let (Some(e), Some(a), Some(v)) = Self::bindings_to_cols(bindings) else {
+            return;
+        };
+
+        Self::index_remove(&mut self.eav, (e.clone(), a.clone(), v.clone()), val);
+        Self::index_remove(&mut self.eva, (e.clone(), v.clone(), a.clone()), val);
+        Self::index_remove(&mut self.aev, (a.clone(), e.clone(), v.clone()), val);
+        Self::index_remove(&mut self.ave, (a.clone(), v.clone(), e.clone()), val);
+        Self::index_remove(&mut self.vea, (v.clone(), e.clone(), a.clone()), val);
+        Self::index_remove(&mut self.vae, (v, a, e), val);
+    }
+
     pub(crate) fn search(&self, bindings: Vec<(ColId, Val)>) -> BTreeSet<&T> {
         let (e, a, v) = Self::bindings_to_cols(bindings);
 
@@ -272,6 +285,37 @@ where
         };
     }
 
+    fn index_remove<K1, K2, K3>(index: &mut Index<K1, K2, K3, T>, keys: (K1, K2, K3), val: &T)
+    where
+        K1: Key,
+        K2: Key,
+        K3: Key,
+    {
+        let (k1, k2, k3) = keys;
+
+        let Some(v1) = index.get_mut(&k1) else {
+            return;
+        };
+
+        if let Some(v2) = v1.get_mut(&k2) {
+            if let Some(v3) = v2.get_mut(&k3) {
+                v3.remove(val);
+
+                if v3.is_empty() {
+                    v2.remove(&k3);
+                }
+            }
+
+            if v2.is_empty() {
+                v1.remove(&k2);
+            }
+        }
+
+        if v1.is_empty() {
+            index.remove(&k1);
+        }
+    }
+
     fn index_search_0<K1, K2, K3>(index: &Index<K1, K2, K3, T>) -> BTreeSet<&T>
     where
         K1: Key,
@@ -426,6 +470,10 @@ impl Relation for Hexastore<Tuple> {
         self.insert(bindings, val).unwrap()
     }
 
+    fn remove(&mut self, bindings: Vec<(ColId, Val)>, val: &Tuple) {
+        self.remove(bindings, val)
+    }
+
     fn merge(&mut self, rhs: &dyn Relation) {
         if let Some(rhs) = rhs.downcast_ref::<Self>() {
             self.merge(rhs)
@@ -546,6 +594,40 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_remove() -> Result<()> {
+        let mut hexastore = Hexastore::<usize>::default();
+
+        let bindings = |entity: i64, value: &str| {
+            vec![
+                (ColId::new("entity"), entity.into()),
+                (ColId::new("attribute"), "name".into()),
+                (ColId::new("value"), value.into()),
+            ]
+        };
+
+        hexastore.insert(bindings(0, "quinn"), 0)?;
+        hexastore.insert(bindings(1, "rowan"), 1)?;
+
+        hexastore.remove(bindings(0, "quinn"), &0);
+
+        assert_eq!(hexastore.len(), 1);
+        assert_eq!(
+            hexastore.contains(vec![(ColId::new("value"), "quinn".into())]),
+            false
+        );
+        assert_eq!(
+            hexastore.search(vec![(ColId::new("attribute"), "name".into())]),
+            BTreeSet::from_iter([&1])
+        );
+
+        hexastore.remove(bindings(1, "rowan"), &1);
+
+        assert_eq!(hexastore.is_empty(), true);
+
+        Ok(())
+    }
+
     #[test]
     fn test_contains() -> Result<()> {
         let mut hexastore = Hexastore::<usize>::default();
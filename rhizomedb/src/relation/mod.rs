@@ -11,12 +11,10 @@ use std::{fmt::Debug, hash::Hash};
 
 pub(crate) mod bistore;
 pub(crate) mod hexastore;
-pub(crate) mod immutable_ord_set;
 pub(crate) mod ord_set;
 
 pub use bistore::Bistore;
 pub use hexastore::Hexastore;
-pub use immutable_ord_set::ImmutableOrdSetRelation;
 pub use ord_set::OrdSetRelation;
 
 pub(crate) type DefaultRelation = OrdSetRelation;
@@ -58,6 +56,7 @@ pub trait Relation: Debug + DynClone + Send + Sync + AsAny + 'static {
 
     fn purge(&mut self);
     fn insert(&mut self, bindings: Vec<(ColId, Val)>, val: Tuple);
+    fn remove(&mut self, bindings: Vec<(ColId, Val)>, val: &Tuple);
     fn merge(&mut self, rhs: &dyn Relation);
 }
 
@@ -88,6 +87,10 @@ impl Relation for Box<dyn Relation> {
         (**self).insert(bindings, val)
     }
 
+    fn remove(&mut self, bindings: Vec<(ColId, Val)>, val: &Tuple) {
+        (**self).remove(bindings, val)
+    }
+
     fn merge(&mut self, rhs: &dyn Relation) {
         (**self).merge(rhs)
     }
@@ -96,7 +99,7 @@ impl Relation for Box<dyn Relation> {
 impl Pretty for RelationKey {
     fn to_doc(&self) -> pretty::RcDoc<'_, ()> {
         RcDoc::concat([
-            RcDoc::as_string(self.0),
+            RcDoc::as_string(&self.0),
             RcDoc::text("_"),
             RcDoc::as_string(self.1),
         ])
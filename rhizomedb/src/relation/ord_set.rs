@@ -47,6 +47,10 @@ impl Relation for OrdSetRelation {
         self.inner.insert(val);
     }
 
+    fn remove(&mut self, _bindings: Vec<(ColId, Val)>, val: &Tuple) {
+        self.inner.remove(val);
+    }
+
     fn merge(&mut self, rhs: &dyn Relation) {
         if let Some(rhs) = rhs.downcast_ref::<Self>() {
             self.inner.extend(rhs.inner.iter().cloned());
@@ -0,0 +1,399 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::Result;
+
+use crate::{
+    error::{error, Error},
+    id::ColId,
+    ram::{
+        operation::{project::Project, search::Search, Operation},
+        Aggregation, Formula, Rejections, Slot, Term,
+    },
+    relation::Relation,
+    tuple::Tuple,
+    value::Val,
+};
+
+/// The number of rows a batch holds before it is passed on to the next operation.
+const BATCH_SIZE: usize = 1024;
+
+/// Rows of bindings, stored column by column, with one column per slot.
+#[derive(Debug)]
+struct Batch {
+    columns: Vec<Vec<Option<Val>>>,
+    len: usize,
+}
+
+impl Batch {
+    fn new(width: usize) -> Self {
+        Self {
+            columns: vec![Vec::with_capacity(BATCH_SIZE); width],
+            len: 0,
+        }
+    }
+
+    /// A batch with a single row, in which nothing is bound.
+    fn unit(width: usize) -> Self {
+        Self {
+            columns: vec![vec![None]; width],
+            len: 1,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn is_full(&self) -> bool {
+        self.len >= BATCH_SIZE
+    }
+
+    fn get(&self, row: usize, slot: usize) -> Option<&Val> {
+        self.columns[slot][row].as_ref()
+    }
+
+    /// Appends a copy of a row from another batch, with some slots overwritten.
+    fn push_extended(&mut self, from: &Batch, row: usize, updates: &[(usize, Val)]) {
+        for (slot, column) in self.columns.iter_mut().enumerate() {
+            column.push(from.columns[slot][row].clone());
+        }
+
+        for (slot, val) in updates {
+            self.columns[*slot][self.len] = Some(val.clone());
+        }
+
+        self.len += 1;
+    }
+
+    fn pop(&mut self) {
+        for column in self.columns.iter_mut() {
+            column.pop();
+        }
+
+        self.len -= 1;
+    }
+
+    fn take(&mut self) -> Self {
+        let width = self.columns.len();
+
+        std::mem::replace(self, Self::new(width))
+    }
+}
+
+/// Evaluates the operation of an insert statement over batches of bindings,
+/// rather than one set of bindings at a time.
+pub(crate) struct BatchEvaluator<'a> {
+    projected: &'a AtomicUsize,
+    rejections: Option<&'a Rejections>,
+    slots: usize,
+}
+
+impl<'a> BatchEvaluator<'a> {
+    pub(crate) fn new(
+        projected: &'a AtomicUsize,
+        rejections: Option<&'a Rejections>,
+        slots: usize,
+    ) -> Self {
+        Self {
+            projected,
+            rejections,
+            slots,
+        }
+    }
+
+    pub(crate) fn handle_operation(&self, operation: &Operation) -> Result<()> {
+        self.do_handle_operation(operation, Batch::unit(self.slots))
+    }
+
+    fn do_handle_operation(&self, operation: &Operation, input: Batch) -> Result<()> {
+        if input.is_empty() {
+            return Ok(());
+        }
+
+        match operation {
+            Operation::Search(inner) => self.handle_search(inner, input),
+            Operation::Project(inner) => self.handle_project(inner, input),
+            Operation::Aggregation(inner) => self.handle_aggregation(inner, input),
+        }
+    }
+
+    fn handle_search(&self, search: &Search, input: Batch) -> Result<()> {
+        let relation = search.relation().read().or_else(|_| {
+            error(Error::InternalRhizomeError(
+                "relation lock poisoned".to_owned(),
+            ))
+        })?;
+
+        let mut output = Batch::new(self.slots);
+        let mut updates = Vec::default();
+
+        for row in 0..input.len() {
+            let mut bound_cols = vec![];
+            for (col_id, term) in search.bindings() {
+                let resolved = self.resolve(&input, row, term)?.ok_or_else(|| {
+                    Error::InternalRhizomeError("expected binding not found".to_owned())
+                })?;
+
+                bound_cols.push((col_id.clone(), resolved));
+            }
+
+            let mut is_matched = false;
+
+            for fact in relation.search(bound_cols) {
+                updates.clear();
+
+                if let (Some(slot), Some(cid)) = (search.cid(), fact.cid()) {
+                    updates.push((slot, Val::Cid(cid)));
+                }
+
+                for (col_id, slot) in search.cols() {
+                    let v = fact.col(col_id).ok_or_else(|| {
+                        Error::InternalRhizomeError("expected column not found".to_owned())
+                    })?;
+
+                    updates.push((*slot, v));
+                }
+
+                output.push_extended(&input, row, &updates);
+
+                if !self.is_satisfied(&output, output.len() - 1, search.when())? {
+                    output.pop();
+                    continue;
+                }
+
+                is_matched = true;
+
+                if !self.is_satisfied(&output, output.len() - 1, search.filter())? {
+                    output.pop();
+                }
+
+                if output.is_full() {
+                    self.do_handle_operation(search.operation(), output.take())?;
+                }
+            }
+
+            if search.is_outer() && !is_matched {
+                updates.clear();
+                updates.extend(search.null_slots().map(|slot| (slot, Val::Null)));
+
+                output.push_extended(&input, row, &updates);
+
+                if !self.is_satisfied(&output, output.len() - 1, search.filter())? {
+                    output.pop();
+                }
+
+                if output.is_full() {
+                    self.do_handle_operation(search.operation(), output.take())?;
+                }
+            }
+        }
+
+        self.do_handle_operation(search.operation(), output)
+    }
+
+    fn handle_project(&self, project: &Project, input: Batch) -> Result<()> {
+        let mut facts = Vec::default();
+
+        'rows: for row in 0..input.len() {
+            if !self.is_satisfied(&input, row, project.formulae())? {
+                continue;
+            }
+
+            let mut bound: Vec<(ColId, Val)> = Vec::default();
+
+            for (id, term) in project.cols() {
+                if let Some(val) = self.resolve(&input, row, term)? {
+                    bound.push((id.clone(), val));
+                } else {
+                    continue 'rows;
+                }
+            }
+
+            if let Some(fact) = project.conform(bound, self.rejections)? {
+                facts.push(fact);
+            }
+        }
+
+        self.projected.fetch_add(input.len(), Ordering::Relaxed);
+
+        let mut relation = project.relation().write().or_else(|_| {
+            error(Error::InternalRhizomeError(
+                "relation lock poisoned".to_owned(),
+            ))
+        })?;
+
+        for (bound, fact) in facts {
+            project.insert(&mut relation, bound, fact)?;
+        }
+
+        Ok(())
+    }
+
+    fn handle_aggregation(&self, agg: &Aggregation, input: Batch) -> Result<()> {
+        let mut output = Batch::new(self.slots);
+
+        for row in 0..input.len() {
+            let mut group_by_vals: Vec<(ColId, Val)> = Vec::default();
+            for (col_id, col_term) in agg.group_by_cols() {
+                let col_val = self.resolve(&input, row, col_term)?.ok_or_else(|| {
+                    Error::InternalRhizomeError(format!(
+                        "expected term to resolve for col: {}",
+                        col_id
+                    ))
+                })?;
+
+                group_by_vals.push((col_id.clone(), col_val));
+            }
+
+            let rows = match agg.probe(&group_by_vals)? {
+                Some(rows) => rows,
+                None => self.scan_group(agg, &input, row, group_by_vals)?,
+            };
+
+            for result in rows {
+                let extended = agg
+                    .targets()
+                    .iter()
+                    .map(|(_, slot)| *slot)
+                    .zip(result)
+                    .collect::<Vec<_>>();
+
+                output.push_extended(&input, row, &extended);
+
+                if output.is_full() {
+                    self.do_handle_operation(agg.operation(), output.take())?;
+                }
+            }
+        }
+
+        self.do_handle_operation(agg.operation(), output)
+    }
+
+    /// Aggregates the group of a row by searching the relation for its facts.
+    fn scan_group(
+        &self,
+        agg: &Aggregation,
+        input: &Batch,
+        row: usize,
+        group_by_vals: Vec<(ColId, Val)>,
+    ) -> Result<Vec<Vec<Val>>> {
+        let relation = agg.relation().read().or_else(|_| {
+            error(Error::InternalRhizomeError(
+                "relation lock poisoned".to_owned(),
+            ))
+        })?;
+
+        let mut result = agg.agg().init();
+        for fact in relation.search(group_by_vals) {
+            let mut args = Vec::default();
+            for term in agg.args() {
+                let resolved = self
+                    .resolve_in_fact(input, row, term, fact, agg.cols())?
+                    .ok_or_else(|| {
+                        Error::InternalRhizomeError(
+                            "argument to aggregation failed to resolve".to_owned(),
+                        )
+                    })?;
+
+                args.push(resolved);
+            }
+
+            result.step(args);
+        }
+
+        Ok(result.finalize())
+    }
+
+    fn resolve(&self, batch: &Batch, row: usize, term: &Term) -> Result<Option<Val>> {
+        match term {
+            Term::Lit(val) => Ok(Some(val.clone())),
+            _ => Ok(term
+                .slot()
+                .and_then(|slot| batch.get(row, slot))
+                .map(|val| term.cast(val.clone()))),
+        }
+    }
+
+    /// Resolves a term as though the columns of a fact were bound on top of a row.
+    fn resolve_in_fact(
+        &self,
+        batch: &Batch,
+        row: usize,
+        term: &Term,
+        fact: &Tuple,
+        cols: &[(ColId, Slot)],
+    ) -> Result<Option<Val>> {
+        if let Some(slot) = term.slot() {
+            if let Some((col_id, _)) = cols.iter().find(|(_, s)| *s == slot) {
+                if let Some(val) = fact.col(col_id) {
+                    return Ok(Some(term.cast(val)));
+                }
+            }
+        }
+
+        self.resolve(batch, row, term)
+    }
+
+    fn is_satisfied(&self, batch: &Batch, row: usize, formulae: &[Formula]) -> Result<bool> {
+        for formula in formulae {
+            if !self.is_formula_satisfied(batch, row, formula)? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn is_formula_satisfied(&self, batch: &Batch, row: usize, formula: &Formula) -> Result<bool> {
+        match formula {
+            Formula::Equality(inner) => {
+                let left = self.resolve(batch, row, inner.left())?;
+                let right = self.resolve(batch, row, inner.right())?;
+
+                Ok(left == right)
+            }
+            Formula::NotIn(inner) => {
+                let mut bound: Vec<(ColId, Val)> = Vec::default();
+
+                for (id, term) in inner.cols() {
+                    if let Some(val) = self.resolve(batch, row, term)? {
+                        bound.push((id.clone(), val));
+                    } else {
+                        return error(Error::InternalRhizomeError(format!(
+                            "failed to resolve term for column: {}",
+                            id
+                        )));
+                    }
+                }
+
+                Ok(!inner
+                    .relation()
+                    .read()
+                    .or_else(|_| {
+                        error(Error::InternalRhizomeError(
+                            "relation lock poisoned".to_owned(),
+                        ))
+                    })?
+                    .contains(bound))
+            }
+            Formula::Predicate(inner) => {
+                let mut args = Vec::default();
+                for term in inner.args() {
+                    let resolved = self.resolve(batch, row, term)?.ok_or_else(|| {
+                        Error::InternalRhizomeError(
+                            "argument to predicate failed to resolve".to_owned(),
+                        )
+                    })?;
+
+                    args.push(resolved);
+                }
+
+                inner.is_satisfied(args)
+            }
+        }
+    }
+}
@@ -70,7 +70,7 @@ impl Client {
             .send(ClientCommand::RegisterSink(id, f, tx))
             .await?;
 
-        rx.await?;
+        rx.await??;
 
         Ok(())
     }
@@ -14,7 +14,11 @@ use crate::{
 pub mod client;
 pub mod epoch;
 pub mod reactor;
-mod vm;
+
+mod batch;
+pub(crate) mod vm;
+
+pub use vm::{Executor, Limits, StepBudget};
 
 pub type TupleStream = Box<dyn Stream<Item = InputTuple>>;
 pub type TupleSink = Box<dyn Sink<Tuple, Error = Error>>;
@@ -43,13 +47,22 @@ where
     T: Timestamp,
 {
     ReachedFixedpoint(T, Cid),
+    /// An epoch exceeded the configured limits and was rolled back.
+    EpochAborted(Cid, Error),
+    /// A tuple didn't conform to the schema of its relation in strict mode, and so
+    /// wasn't inserted.
+    TupleRejected(Tuple, Error),
 }
 
 pub enum ClientCommand {
     Flush(oneshot::Sender<()>),
     InsertTuple(Box<InputTuple>, oneshot::Sender<()>),
     RegisterStream(RelationId, Box<dyn CreateStream>, oneshot::Sender<()>),
-    RegisterSink(RelationId, Box<dyn CreateSink>, oneshot::Sender<()>),
+    RegisterSink(
+        RelationId,
+        Box<dyn CreateSink>,
+        oneshot::Sender<Result<(), Error>>,
+    ),
     RewindEpoch(oneshot::Sender<()>),
     ReplayEpoch(oneshot::Sender<()>),
 }
@@ -72,8 +85,10 @@ impl Debug for ClientCommand {
 #[cfg(test)]
 mod tests {
     use std::{
+        collections::BTreeSet,
         marker::PhantomData,
         ops::{Add, AddAssign},
+        sync::Arc,
     };
 
     use anyhow::Result;
@@ -84,13 +99,15 @@ mod tests {
     use crate::{
         aggregation::Aggregate,
         assert_derives,
+        decimal::Decimal,
         kernel::{self, math},
         predicate::Predicate,
+        storage::memory::MemoryBlockstore,
         types::RhizomeType,
-        value::{Any, Val},
+        value::{self, Any, List, Val},
     };
 
-    use super::*;
+    use super::{vm::VM, *};
 
     #[test]
     fn test_step_epoch_transitive_closure() {
@@ -763,6 +780,930 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_count_distinct() {
+        assert_derives!(
+            |p| {
+                p.output("score", |h| {
+                    h.column::<i32>("player").column::<i32>("points")
+                })?;
+                p.output("count", |h| h.column::<i32>("n"))?;
+
+                p.fact("score", |f| f.bind((("player", 1), ("points", 10))))?;
+                p.fact("score", |f| f.bind((("player", 2), ("points", 10))))?;
+                p.fact("score", |f| f.bind((("player", 3), ("points", 20))))?;
+                p.fact("score", |f| f.bind((("player", 4), ("points", 30))))?;
+
+                p.rule::<(i32, i32)>("count", &|h, b, (count, points)| {
+                    h.bind((("n", count),))?;
+                    b.group_by(
+                        count,
+                        "score",
+                        (("points", points),),
+                        math::count_distinct(points),
+                    )?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [("count", [Tuple::new("count", [("n", 3),], None),]),]
+        );
+    }
+
+    #[test]
+    fn test_median() {
+        assert_derives!(
+            |p| {
+                p.output("num", |h| h.column::<i32>("n"))?;
+                p.output("even", |h| h.column::<i32>("n"))?;
+                p.output("median", |h| h.column::<i32>("n"))?;
+                p.output("even_median", |h| h.column::<i32>("n"))?;
+
+                for n in [5, 1, 4, 2, 3] {
+                    p.fact("num", |f| f.bind((("n", n),)))?;
+                }
+
+                for n in [6, 2, 8, 4] {
+                    p.fact("even", |f| f.bind((("n", n),)))?;
+                }
+
+                p.rule::<(i32, i32)>("median", &|h, b, (median, n)| {
+                    h.bind((("n", median),))?;
+                    b.group_by(median, "num", (("n", n),), math::median(n))?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(i32, i32)>("even_median", &|h, b, (median, n)| {
+                    h.bind((("n", median),))?;
+                    b.group_by(median, "even", (("n", n),), math::median(n))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [
+                ("median", [Tuple::new("median", [("n", 3),], None),]),
+                (
+                    "even_median",
+                    [Tuple::new("even_median", [("n", 4),], None),]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_percentile() {
+        assert_derives!(
+            |p| {
+                p.output("num", |h| h.column::<i32>("n"))?;
+                p.output("p0", |h| h.column::<i32>("n"))?;
+                p.output("p90", |h| h.column::<i32>("n"))?;
+                p.output("p100", |h| h.column::<i32>("n"))?;
+
+                for n in 1..=10 {
+                    p.fact("num", |f| f.bind((("n", n),)))?;
+                }
+
+                for (id, fraction) in [("p0", 0.0), ("p90", 0.9), ("p100", 1.0)] {
+                    p.rule::<(i32, i32)>(id, &|h, b, (percentile, n)| {
+                        h.bind((("n", percentile),))?;
+                        b.group_by(
+                            percentile,
+                            "num",
+                            (("n", n),),
+                            math::percentile(n, fraction),
+                        )?;
+
+                        Ok(())
+                    })?;
+                }
+
+                Ok(p)
+            },
+            [
+                ("p0", [Tuple::new("p0", [("n", 1),], None),]),
+                ("p90", [Tuple::new("p90", [("n", 9),], None),]),
+                ("p100", [Tuple::new("p100", [("n", 10),], None),]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_median_f64() {
+        assert_derives!(
+            |p| {
+                p.output("sample", |h| h.column::<i32>("id").column::<f64>("x"))?;
+                p.output("median", |h| h.column::<f64>("x"))?;
+                p.output("p75", |h| h.column::<f64>("x"))?;
+
+                for (id, x) in [2.5, -1.0, 0.25, 10.0].into_iter().enumerate() {
+                    p.fact("sample", |f| f.bind((("id", id as i32), ("x", x))))?;
+                }
+
+                p.rule::<(f64, f64)>("median", &|h, b, (median, x)| {
+                    h.bind((("x", median),))?;
+                    b.group_by(median, "sample", (("x", x),), math::median(x))?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(f64, f64)>("p75", &|h, b, (percentile, x)| {
+                    h.bind((("x", percentile),))?;
+                    b.group_by(percentile, "sample", (("x", x),), math::percentile(x, 0.75))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [
+                ("median", [Tuple::new("median", [("x", 0.25),], None),]),
+                ("p75", [Tuple::new("p75", [("x", 2.5),], None),]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_variance() {
+        assert_derives!(
+            |p| {
+                p.output("sample", |h| h.column::<i32>("id").column::<i32>("x"))?;
+                p.output("variance", |h| h.column::<f64>("x"))?;
+                p.output("stddev", |h| h.column::<f64>("x"))?;
+
+                for (id, x) in [2, 4, 4, 4, 5, 5, 7, 9].into_iter().enumerate() {
+                    p.fact("sample", |f| f.bind((("id", id as i32), ("x", x))))?;
+                }
+
+                p.rule::<(f64, i32)>("variance", &|h, b, (variance, x)| {
+                    h.bind((("x", variance),))?;
+                    b.group_by(variance, "sample", (("x", x),), math::variance(x))?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(f64, i32)>("stddev", &|h, b, (stddev, x)| {
+                    h.bind((("x", stddev),))?;
+                    b.group_by(stddev, "sample", (("x", x),), math::stddev(x))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [
+                ("variance", [Tuple::new("variance", [("x", 4.0),], None),]),
+                ("stddev", [Tuple::new("stddev", [("x", 2.0),], None),]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_any_all() {
+        assert_derives!(
+            |p| {
+                p.output("check", |h| {
+                    h.column::<&str>("suite")
+                        .column::<i32>("id")
+                        .column::<bool>("passed")
+                })?;
+                p.output("any", |h| {
+                    h.column::<&str>("suite").column::<bool>("passed")
+                })?;
+                p.output("all", |h| {
+                    h.column::<&str>("suite").column::<bool>("passed")
+                })?;
+
+                for (suite, id, passed) in [
+                    ("a", 1, true),
+                    ("a", 2, true),
+                    ("b", 1, false),
+                    ("b", 2, true),
+                ] {
+                    p.fact("check", |f| {
+                        f.bind((("suite", suite), ("id", id), ("passed", passed)))
+                    })?;
+                }
+
+                p.rule::<(&str, bool, bool)>("any", &|h, b, (suite, any, passed)| {
+                    h.bind((("suite", suite), ("passed", any)))?;
+                    b.search("check", (("suite", suite),))?;
+                    b.group_by(
+                        any,
+                        "check",
+                        (("suite", suite), ("passed", passed)),
+                        math::any(passed),
+                    )?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(&str, bool, bool)>("all", &|h, b, (suite, all, passed)| {
+                    h.bind((("suite", suite), ("passed", all)))?;
+                    b.search("check", (("suite", suite),))?;
+                    b.group_by(
+                        all,
+                        "check",
+                        (("suite", suite), ("passed", passed)),
+                        math::all(passed),
+                    )?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [
+                (
+                    "any",
+                    [
+                        Tuple::new(
+                            "any",
+                            [("suite", Val::from("a")), ("passed", Val::from(true))],
+                            None
+                        ),
+                        Tuple::new(
+                            "any",
+                            [("suite", Val::from("b")), ("passed", Val::from(true))],
+                            None
+                        ),
+                    ]
+                ),
+                (
+                    "all",
+                    [
+                        Tuple::new(
+                            "all",
+                            [("suite", Val::from("a")), ("passed", Val::from(true))],
+                            None
+                        ),
+                        Tuple::new(
+                            "all",
+                            [("suite", Val::from("b")), ("passed", Val::from(false))],
+                            None
+                        ),
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect() {
+        assert_derives!(
+            |p| {
+                p.output("link", |h| {
+                    h.column::<i32>("from")
+                        .column::<i32>("to")
+                        .column::<&str>("label")
+                })?;
+                p.output("list", |h| h.column::<i32>("to").column::<List>("from"))?;
+                p.output("set", |h| h.column::<i32>("to").column::<List>("label"))?;
+                p.output("labels", |h| h.column::<i32>("to").column::<&str>("label"))?;
+
+                for (from, to, label) in [(3, 1, "b"), (2, 1, "a"), (4, 1, "b"), (3, 2, "c")] {
+                    p.fact("link", |f| {
+                        f.bind((("from", from), ("to", to), ("label", label)))
+                    })?;
+                }
+
+                p.rule::<(i32, List, i32)>("list", &|h, b, (to, list, from)| {
+                    h.bind((("to", to), ("from", list)))?;
+                    b.search("link", (("to", to),))?;
+                    b.group_by(
+                        list,
+                        "link",
+                        (("to", to), ("from", from)),
+                        math::collect_list(from),
+                    )?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(i32, List, Arc<str>)>("set", &|h, b, (to, set, label)| {
+                    h.bind((("to", to), ("label", set)))?;
+                    b.search("link", (("to", to),))?;
+                    b.group_by(
+                        set,
+                        "link",
+                        (("to", to), ("label", label)),
+                        math::collect_set(label),
+                    )?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(i32, Arc<str>, Arc<str>, i32)>("labels", &|h,
+                                                                     b,
+                                                                     (
+                    to,
+                    labels,
+                    label,
+                    from,
+                )| {
+                    h.bind((("to", to), ("label", labels)))?;
+                    b.search("link", (("to", to),))?;
+                    b.group_by(
+                        labels,
+                        "link",
+                        (("to", to), ("from", from), ("label", label)),
+                        math::string_agg(label, ", "),
+                    )?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [
+                (
+                    "list",
+                    [
+                        Tuple::new(
+                            "list",
+                            [
+                                ("to", Val::from(1)),
+                                (
+                                    "from",
+                                    Val::from(vec![Val::from(2), Val::from(3), Val::from(4)])
+                                ),
+                            ],
+                            None
+                        ),
+                        Tuple::new(
+                            "list",
+                            [
+                                ("to", Val::from(2)),
+                                ("from", Val::from(vec![Val::from(3)]))
+                            ],
+                            None
+                        ),
+                    ]
+                ),
+                (
+                    "set",
+                    [
+                        Tuple::new(
+                            "set",
+                            [
+                                ("to", Val::from(1)),
+                                ("label", Val::from(vec![Val::from("a"), Val::from("b")])),
+                            ],
+                            None
+                        ),
+                        Tuple::new(
+                            "set",
+                            [
+                                ("to", Val::from(2)),
+                                ("label", Val::from(vec![Val::from("c")]))
+                            ],
+                            None
+                        ),
+                    ]
+                ),
+                (
+                    "labels",
+                    [
+                        Tuple::new(
+                            "labels",
+                            [("to", Val::from(1)), ("label", Val::from("a, b, b"))],
+                            None
+                        ),
+                        Tuple::new(
+                            "labels",
+                            [("to", Val::from(2)), ("label", Val::from("c"))],
+                            None
+                        ),
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select() {
+        assert_derives!(
+            |p| {
+                p.output("player", |h| {
+                    h.column::<&str>("team")
+                        .column::<&str>("name")
+                        .column::<i32>("score")
+                })?;
+
+                for id in ["best", "worst", "top"] {
+                    p.output(id, |h| {
+                        h.column::<&str>("team")
+                            .column::<&str>("name")
+                            .column::<i32>("score")
+                    })?;
+                }
+
+                for (team, name, score) in [
+                    ("red", "ann", 7),
+                    ("red", "cat", 9),
+                    ("red", "bob", 9),
+                    ("blue", "dan", 3),
+                    ("blue", "eve", 5),
+                ] {
+                    p.fact("player", |f| {
+                        f.bind((("team", team), ("name", name), ("score", score)))
+                    })?;
+                }
+
+                p.rule::<(Arc<str>, Arc<str>, i32, Arc<str>, i32)>("best", &|h,
+                                                                             b,
+                                                                             (
+                    team,
+                    name,
+                    score,
+                    n,
+                    s,
+                )| {
+                    h.bind((("team", team), ("name", name), ("score", score)))?;
+                    b.search("player", (("team", team),))?;
+                    b.select(
+                        (name, score),
+                        "player",
+                        (("team", team), ("name", n), ("score", s)),
+                        math::arg_max(s, (n, s)),
+                    )?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(Arc<str>, Arc<str>, i32, Arc<str>, i32)>("worst", &|h,
+                                                                              b,
+                                                                              (
+                    team,
+                    name,
+                    score,
+                    n,
+                    s,
+                )| {
+                    h.bind((("team", team), ("name", name), ("score", score)))?;
+                    b.search("player", (("team", team),))?;
+                    b.select(
+                        (name, score),
+                        "player",
+                        (("team", team), ("name", n), ("score", s)),
+                        math::arg_min(s, (n, s)),
+                    )?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(Arc<str>, Arc<str>, i32, Arc<str>, i32)>("top", &|h,
+                                                                            b,
+                                                                            (
+                    team,
+                    name,
+                    score,
+                    n,
+                    s,
+                )| {
+                    h.bind((("team", team), ("name", name), ("score", score)))?;
+                    b.search("player", (("team", team),))?;
+                    b.select(
+                        (name, score),
+                        "player",
+                        (("team", team), ("name", n), ("score", s)),
+                        math::top_k(2, s, (n, s)),
+                    )?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [
+                (
+                    "best",
+                    vec![
+                        Tuple::new(
+                            "best",
+                            [
+                                ("team", Val::from("blue")),
+                                ("name", Val::from("eve")),
+                                ("score", Val::from(5))
+                            ],
+                            None
+                        ),
+                        Tuple::new(
+                            "best",
+                            [
+                                ("team", Val::from("red")),
+                                ("name", Val::from("bob")),
+                                ("score", Val::from(9))
+                            ],
+                            None
+                        ),
+                    ]
+                ),
+                (
+                    "worst",
+                    vec![
+                        Tuple::new(
+                            "worst",
+                            [
+                                ("team", Val::from("blue")),
+                                ("name", Val::from("dan")),
+                                ("score", Val::from(3))
+                            ],
+                            None
+                        ),
+                        Tuple::new(
+                            "worst",
+                            [
+                                ("team", Val::from("red")),
+                                ("name", Val::from("ann")),
+                                ("score", Val::from(7))
+                            ],
+                            None
+                        ),
+                    ]
+                ),
+                (
+                    "top",
+                    vec![
+                        Tuple::new(
+                            "top",
+                            [
+                                ("team", Val::from("blue")),
+                                ("name", Val::from("dan")),
+                                ("score", Val::from(3))
+                            ],
+                            None
+                        ),
+                        Tuple::new(
+                            "top",
+                            [
+                                ("team", Val::from("blue")),
+                                ("name", Val::from("eve")),
+                                ("score", Val::from(5))
+                            ],
+                            None
+                        ),
+                        Tuple::new(
+                            "top",
+                            [
+                                ("team", Val::from("red")),
+                                ("name", Val::from("bob")),
+                                ("score", Val::from(9))
+                            ],
+                            None
+                        ),
+                        Tuple::new(
+                            "top",
+                            [
+                                ("team", Val::from("red")),
+                                ("name", Val::from("cat")),
+                                ("score", Val::from(9))
+                            ],
+                            None
+                        ),
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_by_many() {
+        assert_derives!(
+            |p| {
+                p.output("score", |h| {
+                    h.column::<&str>("team").column::<i32>("points")
+                })?;
+                p.output("stats", |h| {
+                    h.column::<&str>("team")
+                        .column::<i32>("count")
+                        .column::<i32>("total")
+                        .column::<i32>("best")
+                })?;
+
+                for (team, points) in [("red", 3), ("red", 5), ("red", 4), ("blue", 2)] {
+                    p.fact("score", |f| f.bind((("team", team), ("points", points))))?;
+                }
+
+                p.rule::<(&str, i32, i32, i32, i32)>("stats", &|h,
+                                                                b,
+                                                                (
+                    team,
+                    count,
+                    total,
+                    best,
+                    points,
+                )| {
+                    h.bind((
+                        ("team", team),
+                        ("count", count),
+                        ("total", total),
+                        ("best", best),
+                    ))?;
+                    b.search("score", (("team", team),))?;
+                    b.group_by_many("score", (("team", team), ("points", points)), |g| {
+                        g.aggregate(count, math::count())?;
+                        g.aggregate(total, math::sum(points))?;
+                        g.aggregate(best, math::max(points))?;
+
+                        Ok(())
+                    })?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [(
+                "stats",
+                [
+                    Tuple::new(
+                        "stats",
+                        [
+                            ("team", Val::from("blue")),
+                            ("count", Val::from(1)),
+                            ("total", Val::from(2)),
+                            ("best", Val::from(2)),
+                        ],
+                        None
+                    ),
+                    Tuple::new(
+                        "stats",
+                        [
+                            ("team", Val::from("red")),
+                            ("count", Val::from(3)),
+                            ("total", Val::from(12)),
+                            ("best", Val::from(5)),
+                        ],
+                        None
+                    ),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_group_by_join() {
+        assert_derives!(
+            |p| {
+                p.output("order", |h| {
+                    h.column::<&str>("customer")
+                        .column::<&str>("product")
+                        .column::<i32>("qty")
+                })?;
+                p.output("price", |h| {
+                    h.column::<&str>("product").column::<i32>("price")
+                })?;
+                p.output("discontinued", |h| h.column::<&str>("product"))?;
+                p.output("spend", |h| {
+                    h.column::<&str>("customer").column::<i32>("total")
+                })?;
+
+                for (customer, product, qty) in [
+                    ("ann", "apple", 2),
+                    ("ann", "pear", 1),
+                    ("ann", "plum", 4),
+                    ("bob", "pear", 3),
+                    ("bob", "fig", 2),
+                ] {
+                    p.fact("order", |f| {
+                        f.bind((("customer", customer), ("product", product), ("qty", qty)))
+                    })?;
+                }
+
+                for (product, price) in [("apple", 3), ("pear", 5), ("plum", 7), ("fig", 5)] {
+                    p.fact("price", |f| {
+                        f.bind((("product", product), ("price", price)))
+                    })?;
+                }
+
+                p.fact("discontinued", |f| f.bind((("product", "plum"),)))?;
+
+                p.rule::<(&str, i32, &str, i32, i32)>("spend", &|h, b, (c, total, p, pr, q)| {
+                    h.bind((("customer", c), ("total", total)))?;
+                    b.search("order", (("customer", c),))?;
+                    b.build_group_by(total, "order", math::sum(pr), |s| {
+                        s.bind_one(("customer", c))?;
+                        s.bind_one(("product", p))?;
+                        s.bind_one(("qty", q))?;
+                        s.search("price", (("product", p), ("price", pr)))?;
+                        s.except("discontinued", (("product", p),))?;
+                        s.predicate(kernel::when((q,), |(q,)| q > 1))?;
+
+                        Ok(())
+                    })?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [(
+                "spend",
+                [
+                    Tuple::new(
+                        "spend",
+                        [("customer", Val::from("ann")), ("total", Val::from(3))],
+                        None
+                    ),
+                    Tuple::new(
+                        "spend",
+                        [("customer", Val::from("bob")), ("total", Val::from(10))],
+                        None
+                    ),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_group_by_join_duplicates() {
+        assert_derives!(
+            |p| {
+                p.output("order", |h| {
+                    h.column::<i32>("id")
+                        .column::<&str>("customer")
+                        .column::<&str>("product")
+                        .column::<i32>("qty")
+                })?;
+                p.output("price", |h| {
+                    h.column::<&str>("product").column::<i32>("price")
+                })?;
+                p.output("spend", |h| {
+                    h.column::<&str>("customer").column::<i32>("total")
+                })?;
+
+                // Orders 0 and 1 differ only by their unbound ids
+                for (id, customer, product, qty) in [
+                    (0, "ann", "apple", 2),
+                    (1, "ann", "apple", 2),
+                    (2, "bob", "pear", 1),
+                ] {
+                    p.fact("order", |f| {
+                        f.bind((
+                            ("id", id),
+                            ("customer", customer),
+                            ("product", product),
+                            ("qty", qty),
+                        ))
+                    })?;
+                }
+
+                for (product, price) in [("apple", 3), ("pear", 5)] {
+                    p.fact("price", |f| {
+                        f.bind((("product", product), ("price", price)))
+                    })?;
+                }
+
+                p.rule::<(&str, i32, &str, i32)>("spend", &|h, b, (c, total, p, q)| {
+                    h.bind((("customer", c), ("total", total)))?;
+                    b.search("order", (("customer", c),))?;
+                    b.build_group_by(total, "order", math::sum(q), |s| {
+                        s.bind_one(("customer", c))?;
+                        s.bind_one(("product", p))?;
+                        s.bind_one(("qty", q))?;
+                        s.search("price", (("product", p),))?;
+
+                        Ok(())
+                    })?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [(
+                "spend",
+                [
+                    Tuple::new(
+                        "spend",
+                        [("customer", Val::from("ann")), ("total", Val::from(4))],
+                        None
+                    ),
+                    Tuple::new(
+                        "spend",
+                        [("customer", Val::from("bob")), ("total", Val::from(1))],
+                        None
+                    ),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_group_by_join_domain_name() -> Result<()> {
+        let program = crate::build(|p| {
+            p.output("order", |h| {
+                h.column::<&str>("product").column::<i32>("qty")
+            })?;
+            p.output("price", |h| {
+                h.column::<&str>("product").column::<i32>("price")
+            })?;
+            p.output("total", |h| h.column::<i32>("qty"))?;
+
+            p.fact("order", |f| f.bind((("product", "apple"), ("qty", 2))))?;
+            p.fact("price", |f| f.bind((("product", "apple"), ("price", 3))))?;
+
+            p.rule::<(i32, &str, i32)>("total", &|h, b, (total, p, q)| {
+                h.bind((("qty", total),))?;
+                b.build_group_by(total, "order", math::sum(q), |s| {
+                    s.bind_one(("product", p))?;
+                    s.bind_one(("qty", q))?;
+                    s.search("price", (("product", p),))?;
+
+                    Ok(())
+                })?;
+
+                Ok(())
+            })?;
+
+            // Declared after the rule whose body is flattened
+            p.output("domain_0", |h| h.column::<&str>("name"))?;
+            p.fact("domain_0", |f| f.bind((("name", "user"),)))?;
+
+            Ok(p)
+        })?;
+
+        let bs = MemoryBlockstore::default();
+        let mut vm = <VM>::new(program);
+        vm.step_epoch(&bs)?;
+
+        let mut sunk = Vec::default();
+        while let Some(tuple) = vm.pop()? {
+            if ["total", "domain_0"]
+                .map(RelationId::new)
+                .contains(&tuple.id())
+            {
+                sunk.push(tuple);
+            } else {
+                assert!(
+                    ["order", "price"]
+                        .map(RelationId::new)
+                        .contains(&tuple.id()),
+                    "sunk {}",
+                    tuple.id()
+                );
+            }
+        }
+
+        sunk.sort_by_key(|tuple| tuple.id().to_string());
+        assert_eq!(
+            sunk,
+            vec![
+                Tuple::new("domain_0", [("name", Val::from("user"))], None),
+                Tuple::new("total", [("qty", Val::from(2))], None),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_choose() {
+        assert_derives!(
+            |p| {
+                p.output("child", |h| {
+                    h.column::<&str>("parent")
+                        .column::<&str>("name")
+                        .column::<i32>("age")
+                })?;
+                p.output("first", |h| {
+                    h.column::<&str>("parent").column::<&str>("name")
+                })?;
+
+                // Derived in order of age, so that the least name isn't derived first
+                for (parent, name, age) in
+                    [("a", "z", 1), ("a", "q", 2), ("a", "m", 3), ("b", "k", 1)]
+                {
+                    p.fact("child", |f| {
+                        f.bind((("parent", parent), ("name", name), ("age", age)))
+                    })?;
+                }
+
+                p.rule::<(&str, &str, i32)>("first", &|h, b, (parent, name, age)| {
+                    h.bind((("parent", parent), ("name", name)))?;
+                    h.choose(["parent"], ["name"])?;
+                    b.search("child", (("parent", parent), ("name", name), ("age", age)))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [(
+                "first",
+                [
+                    Tuple::new("first", [("parent", "a"), ("name", "m")], None),
+                    Tuple::new("first", [("parent", "b"), ("name", "k")], None),
+                ]
+            )]
+        );
+    }
+
     #[test]
     fn test_multi_arity_reduce() {
         assert_derives!(
@@ -935,6 +1876,235 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_wide_aggregates() {
+        assert_derives!(
+            |p| {
+                p.output("trade", |h| {
+                    h.column::<i128>("id")
+                        .column::<Decimal>("price")
+                        .column::<value::Timestamp>("at")
+                })?;
+                p.output("total", |h| h.column::<Decimal>("price"))?;
+                p.output("mean", |h| h.column::<i128>("id"))?;
+                p.output("latest", |h| h.column::<value::Timestamp>("at"))?;
+
+                p.fact("trade", |f| {
+                    f.bind((
+                        ("id", 1_i128 << 100),
+                        ("price", Decimal::new(1050, 2)),
+                        ("at", value::Timestamp::from_nanos(10)),
+                    ))
+                })?;
+                p.fact("trade", |f| {
+                    f.bind((
+                        ("id", (1_i128 << 100) + 4),
+                        ("price", Decimal::new(-25, 1)),
+                        ("at", value::Timestamp::from_nanos(30)),
+                    ))
+                })?;
+
+                p.rule::<(Decimal, Decimal)>("total", &|h, b, (total, price)| {
+                    h.bind((("price", total),))?;
+                    b.group_by(total, "trade", (("price", price),), math::sum(price))?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(i128, i128)>("mean", &|h, b, (mean, id)| {
+                    h.bind((("id", mean),))?;
+                    b.group_by(mean, "trade", (("id", id),), math::mean(id))?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(value::Timestamp, value::Timestamp)>("latest", &|h, b, (latest, at)| {
+                    h.bind((("at", latest),))?;
+                    b.group_by(latest, "trade", (("at", at),), math::max(at))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [
+                (
+                    "total",
+                    [Tuple::new("total", [("price", Decimal::new(8, 0))], None)]
+                ),
+                (
+                    "mean",
+                    [Tuple::new("mean", [("id", (1_i128 << 100) + 2)], None)]
+                ),
+                (
+                    "latest",
+                    [Tuple::new(
+                        "latest",
+                        [("at", value::Timestamp::from_nanos(30))],
+                        None
+                    )]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decimal_overflow() {
+        assert_derives!(
+            |p| {
+                p.output("trade", |h| {
+                    h.column::<i32>("id").column::<Decimal>("price")
+                })?;
+                p.output("total", |h| h.column::<Decimal>("price"))?;
+                p.output("mean", |h| h.column::<Decimal>("price"))?;
+
+                p.fact("trade", |f| {
+                    f.bind((("id", 1), ("price", Decimal::new(i128::MAX, 0))))
+                })?;
+                p.fact("trade", |f| f.bind((("id", 2), ("price", Decimal::ONE))))?;
+
+                p.rule::<(Decimal, Decimal)>("total", &|h, b, (total, price)| {
+                    h.bind((("price", total),))?;
+                    b.group_by(total, "trade", (("price", price),), math::sum(price))?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(Decimal, Decimal)>("mean", &|h, b, (mean, price)| {
+                    h.bind((("price", mean),))?;
+                    b.group_by(mean, "trade", (("price", price),), math::mean(price))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [("total", Vec::<Tuple>::default()), ("mean", Vec::default())]
+        );
+    }
+
+    #[test]
+    fn test_search_optional() {
+        assert_derives!(
+            |p| {
+                p.output("owner", |h| h.column::<i32>("id").column::<&str>("name"))?;
+                p.output("pet", |h| h.column::<i32>("owner").column::<&str>("name"))?;
+                p.output("listing", |h| {
+                    h.column::<&str>("owner").optional_column::<&str>("pet")
+                })?;
+
+                p.fact("owner", |f| f.bind((("id", 1), ("name", "ann"))))?;
+                p.fact("owner", |f| f.bind((("id", 2), ("name", "bob"))))?;
+                p.fact("pet", |f| f.bind((("owner", 1), ("name", "rex"))))?;
+                p.fact("listing", |f| f.bind((("owner", "cat"),)))?;
+
+                p.rule::<(i32, &str, &str)>("listing", &|h, b, (id, owner, pet)| {
+                    h.bind((("owner", owner), ("pet", pet)))?;
+                    b.search("owner", (("id", id), ("name", owner)))?;
+                    b.search_optional("pet", (("owner", id), ("name", pet)))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            },
+            [(
+                "listing",
+                [
+                    Tuple::new("listing", [("owner", "ann"), ("pet", "rex")], None),
+                    Tuple::new(
+                        "listing",
+                        [("owner", Val::from("bob")), ("pet", Val::Null)],
+                        None
+                    ),
+                    Tuple::new(
+                        "listing",
+                        [("owner", Val::from("cat")), ("pet", Val::Null)],
+                        None
+                    ),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_search_optional_cid() -> Result<()> {
+        let ann = InputTuple::new(1, "name", "ann", []);
+        let bob = InputTuple::new(2, "name", "bob", []);
+        let missing = InputTuple::new(3, "name", "cat", []);
+
+        let evac = |input: &InputTuple| -> Result<Tuple> {
+            Ok(Tuple::new(
+                "evac",
+                [
+                    ("entity", input.entity()),
+                    ("attribute", input.attr()),
+                    ("value", input.val()),
+                ],
+                Some(input.cid()?),
+            ))
+        };
+
+        for executor in [Executor::Tuple, Executor::Batch] {
+            let program = crate::build(|p| {
+                p.output("named", |h| {
+                    h.column::<Cid>("id").optional_column::<&str>("name")
+                })?;
+
+                p.rule::<(Cid, &str)>("named", &|h, b, (id, name)| {
+                    h.bind((("id", id), ("name", name)))?;
+                    b.search("evac", (("attribute", "wants"), ("value", id)))?;
+                    b.search_optional_cid("evac", id, (("value", name),))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            })?;
+
+            let bs = MemoryBlockstore::default();
+            let mut vm = <VM>::new(program).with_executor(executor);
+
+            // Optional searches only read facts that arrived in earlier epochs
+            vm.push(evac(&ann)?)?;
+            vm.push(evac(&bob)?)?;
+            vm.step_epoch(&bs)?;
+
+            for (entity, wanted) in [(0, &ann), (1, &missing)] {
+                let input = InputTuple::new(entity, "wants", wanted.cid()?, []);
+
+                vm.push(evac(&input)?)?;
+            }
+            vm.step_epoch(&bs)?;
+
+            let mut named = BTreeSet::default();
+            while let Some(tuple) = vm.pop()? {
+                if tuple.id() == "named".into() {
+                    named.insert(tuple);
+                }
+            }
+
+            assert_eq!(
+                named,
+                BTreeSet::from([
+                    Tuple::new(
+                        "named",
+                        [("id", Val::Cid(ann.cid()?)), ("name", Val::from("ann"))],
+                        None
+                    ),
+                    Tuple::new(
+                        "named",
+                        [("id", Val::Cid(missing.cid()?)), ("name", Val::Null)],
+                        None
+                    ),
+                ]),
+                "executor = {executor:?}"
+            );
+        }
+
+        Ok(())
+    }
+
     #[derive(Debug)]
     #[allow(unreachable_pub)]
     pub struct Product<T: RhizomeType + AddAssign + WrappingMul + Zero>(T);
@@ -1,7 +1,10 @@
 use anyhow::Result;
 use cid::Cid;
-use rhizomedb_runtime::Runtime;
-use std::{collections::HashMap, fmt::Debug};
+use rhizomedb_runtime::{yield_now, MaybeSync, Runtime};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Debug,
+};
 
 use futures::{
     channel::{
@@ -24,10 +27,14 @@ use crate::{
         DefaultCodec, DEFAULT_MULTIHASH,
     },
     timestamp::{DefaultTimestamp, Timestamp},
-    tuple::InputTuple,
+    tuple::{InputTuple, Tuple},
 };
 
-use super::{epoch::Epoch, vm::VM, ClientCommand, ClientEvent, SinkCommand, StreamEvent};
+use super::{
+    epoch::Epoch,
+    vm::{Executor, Limits, StepBudget, VM},
+    ClientCommand, ClientEvent, SinkCommand, StreamEvent,
+};
 
 pub struct Reactor<T = DefaultTimestamp, BS = BufferedBlockstore<MemoryBlockstore>>
 where
@@ -38,6 +45,16 @@ where
     staging_epoch: Epoch,
     active_epoch: Epoch,
     epoch_stack: Vec<Cid>,
+    step_budget: StepBudget,
+    limits: Limits,
+    executor: Executor,
+    is_strict: bool,
+    // The tuples already reported as rejected by the epochs computed so far, which
+    // are rejected again whenever those epochs are reloaded or rewound to, and so
+    // aren't reported twice
+    rejected: BTreeSet<Tuple>,
+    // The relations whose tuples the program sinks, which excludes any pruned
+    outputs: HashSet<RelationId>,
     sinks: HashMap<RelationId, Vec<mpsc::Sender<SinkCommand>>>,
     command_rx: mpsc::Receiver<ClientCommand>,
     event_tx: mpsc::Sender<ClientEvent<T>>,
@@ -45,10 +62,12 @@ where
     stream_tx: mpsc::Sender<StreamEvent>,
 }
 
+// Blockstores must be `Sync` on native targets, since statements are evaluated
+// in parallel there
 impl<T, BS> Reactor<T, BS>
 where
     T: Timestamp,
-    BS: Buffered + Default,
+    BS: Buffered + Default + MaybeSync,
 {
     pub fn new(command_rx: Receiver<ClientCommand>, event_tx: Sender<ClientEvent<T>>) -> Self
 where {
@@ -62,6 +81,12 @@ where {
             staging_epoch: active_epoch.step_epoch().unwrap(),
             active_epoch,
             epoch_stack: Default::default(),
+            step_budget: Default::default(),
+            limits: Default::default(),
+            executor: Default::default(),
+            is_strict: false,
+            rejected: Default::default(),
+            outputs: Default::default(),
             sinks: Default::default(),
             command_rx,
             event_tx,
@@ -70,13 +95,51 @@ where {
         }
     }
 
+    /// Sets the work performed between yields to the executor while computing an epoch.
+    pub fn with_step_budget(mut self, step_budget: StepBudget) -> Self {
+        self.step_budget = step_budget;
+
+        self
+    }
+
+    /// Sets the limits beyond which an epoch is aborted and rolled back.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+
+        self
+    }
+
+    /// Sets how the operations of the program are evaluated.
+    pub fn with_executor(mut self, executor: Executor) -> Self {
+        self.executor = executor;
+
+        self
+    }
+
+    /// Checks every tuple inserted into a relation against its schema, reporting
+    /// those that don't conform with [`ClientEvent::TupleRejected`], rather than
+    /// inserting them.
+    pub fn with_strict(mut self, is_strict: bool) -> Self {
+        self.is_strict = is_strict;
+
+        self
+    }
+
     pub async fn async_run<F>(mut self, f: F) -> Result<()>
     where
         F: FnOnce(ProgramBuilder) -> Result<ProgramBuilder>,
     {
         let program = build(f)?;
         let is_monotonic = program.is_monotonic();
-        let mut vm = VM::<T>::new(program);
+        self.outputs = program.outputs().clone();
+        let mut vm = VM::<T>::new(program)
+            .with_limits(self.limits)
+            .with_executor(self.executor)
+            .with_strict(self.is_strict);
+
+        // Set when the relations of the VM were purged by an aborted epoch, and so
+        // must be repopulated from every epoch, even for monotonic programs.
+        let mut should_reload = false;
 
         loop {
             // Poll for any future and then run all ready futures
@@ -101,6 +164,13 @@ where {
                 }
             }
 
+            let mut rollback_epoch = None;
+
+            // Set when the relations are reloaded, to the tuples of the epoch being
+            // computed for the first time, if any. Rejections of any other tuples that
+            // were already reported are replays.
+            let mut fresh = None;
+
             // We are at the head epoch, so we can simply step the epoch as normal
             if self.epoch_stack.is_empty() {
                 // If there are no tuples pending, then continue to the next iteration of the loop
@@ -110,6 +180,8 @@ where {
                     continue;
                 }
 
+                rollback_epoch = Some(self.active_epoch.clone());
+
                 self.active_epoch = self.staging_epoch;
                 self.staging_epoch = self.active_epoch.step_epoch()?;
 
@@ -121,7 +193,7 @@ where {
 
                 self.blockstore.flush(&self.active_epoch.cid()?)?;
 
-                if is_monotonic {
+                if is_monotonic && !should_reload {
                     self.active_epoch.with_tuples(
                         &self.blockstore,
                         &mut |input_tuple: InputTuple| {
@@ -135,6 +207,21 @@ where {
                 } else {
                     vm.reset_relations()?;
 
+                    if self.is_strict {
+                        let mut inserted = BTreeSet::default();
+
+                        self.active_epoch.with_tuples(
+                            &self.blockstore,
+                            &mut |input_tuple: InputTuple| {
+                                inserted.extend(input_tuple.normalize_as_tuples()?);
+
+                                Ok(())
+                            },
+                        )?;
+
+                        fresh = Some(inserted);
+                    }
+
                     self.active_epoch.with_tuples_rec(
                         &self.blockstore,
                         &mut |input_tuple: InputTuple| {
@@ -150,6 +237,7 @@ where {
                 // We are rewound to a previous epoch, so we need to reset the relations,
                 // and load the tuples observed as of that epoch.
                 vm.reset_relations()?;
+                fresh = Some(BTreeSet::default());
 
                 self.active_epoch.with_tuples_rec(
                     &self.blockstore,
@@ -168,7 +256,52 @@ where {
             // of the VM falling out of sync with the timetamp of the reactor. Then a cleaner
             // interface might be to expose VM::compute_at_epoch(epoch), which can handle all of
             // the above setup.
-            vm.step_epoch(&self.blockstore)?;
+            should_reload = false;
+
+            let computed = loop {
+                match vm.step_slice(&self.blockstore, self.step_budget) {
+                    Ok(false) => yield_now().await,
+                    result => break result,
+                }
+            };
+
+            if let Err(e) = computed {
+                let err = e.downcast::<Error>()?;
+
+                if !err.is_limit_exceeded() {
+                    return Err(err.into());
+                }
+
+                let aborted_epoch = self.active_epoch.cid()?;
+
+                vm.abort_epoch()?;
+                should_reload = true;
+
+                // Discard the tuples staged for the aborted epoch, so that it isn't retried
+                if let Some(epoch) = rollback_epoch {
+                    self.staging_epoch = epoch.step_epoch()?;
+                    self.active_epoch = epoch;
+                }
+
+                self.event_tx
+                    .send(ClientEvent::EpochAborted(aborted_epoch, err))
+                    .await?;
+
+                continue;
+            }
+
+            for (tuple, err) in vm.take_rejected()? {
+                let is_replayed = self.rejected.contains(&tuple)
+                    && matches!(&fresh, Some(fresh) if !fresh.contains(&tuple));
+
+                if !is_replayed {
+                    self.event_tx
+                        .send(ClientEvent::TupleRejected(tuple.clone(), err))
+                        .await?;
+
+                    self.rejected.insert(tuple);
+                }
+            }
 
             while let Ok(Some(tuple)) = vm.pop() {
                 if let Some(sinks) = self.sinks.get_mut(&tuple.id()) {
@@ -242,6 +375,12 @@ where {
                     .map_err(|_| Error::InternalRhizomeError("client channel closed".to_owned()))?;
             }
             ClientCommand::RegisterSink(id, create_sink, sender) => {
+                if !self.outputs.contains(&id) {
+                    return sender.send(Err(Error::UnrecognizedSink(id))).map_err(|_| {
+                        Error::InternalRhizomeError("client channel closed".to_owned()).into()
+                    });
+                }
+
                 let (tx, mut rx) = mpsc::channel(100);
                 let create_task = move || async move {
                     let mut sink = Box::into_pin(create_sink());
@@ -263,7 +402,7 @@ where {
                 self.sinks.entry(id).or_default().push(tx);
 
                 sender
-                    .send(())
+                    .send(Ok(()))
                     .map_err(|_| Error::InternalRhizomeError("client channel closed".to_owned()))?;
             }
             ClientCommand::RewindEpoch(sender) => {
@@ -1,27 +1,155 @@
 use core::fmt::Debug;
-use std::{collections::VecDeque, sync::Arc};
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    mem,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 
 use anyhow::Result;
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+use rhizomedb_runtime::{Instant, MaybeSync};
 
 use crate::{
     error::{error, Error},
     ram::{
-        operation::{project::Project, search::Search, Operation},
+        operation::{
+            project::{Project, ProjectedFact},
+            search::Search,
+            Operation,
+        },
         program::Program,
         statement::{
-            exit::Exit, insert::Insert, merge::Merge, purge::Purge, recursive::Loop, sinks::Sinks,
-            sources::Sources, swap::Swap, Statement,
+            exit::Exit,
+            insert::Insert,
+            merge::Merge,
+            purge::Purge,
+            recursive::{Loop, ParallelInserts},
+            sinks::Sinks,
+            sources::Sources,
+            swap::Swap,
+            Statement,
         },
-        Aggregation, Bindings,
+        Aggregation, Bindings, Rejections,
     },
+    relation::Relation,
     storage::blockstore::Blockstore,
     timestamp::{DefaultTimestamp, Timestamp},
     tuple::Tuple,
 };
 
+use super::batch::BatchEvaluator;
+
+/// The minimum size of a relation for the outermost search of a rule over it to be
+/// split into chunks that are evaluated in parallel.
+const PARALLEL_SEARCH_THRESHOLD: usize = 4096;
+
+/// How the operations of insert statements are evaluated.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Executor {
+    /// Evaluates operations one set of bindings at a time.
+    #[default]
+    Tuple,
+    /// Evaluates operations over batches of bindings, stored by column.
+    Batch,
+}
+
+/// Limits on the work performed by a single slice of an epoch.
+///
+/// Statements are never interrupted part way through, so a slice may overrun
+/// its tuple budget by the tuples projected by its final statement.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct StepBudget {
+    statements: Option<usize>,
+    tuples: Option<usize>,
+}
+
+impl StepBudget {
+    pub fn unbounded() -> Self {
+        Self {
+            statements: None,
+            tuples: None,
+        }
+    }
+
+    pub fn statements(self, statements: usize) -> Self {
+        Self {
+            statements: Some(statements.max(1)),
+            ..self
+        }
+    }
+
+    pub fn tuples(self, tuples: usize) -> Self {
+        Self {
+            tuples: Some(tuples.max(1)),
+            ..self
+        }
+    }
+
+    fn is_exhausted(&self, statements: usize, tuples: usize) -> bool {
+        self.statements.map_or(false, |limit| statements >= limit)
+            || self.tuples.map_or(false, |limit| tuples >= limit)
+    }
+}
+
+impl Default for StepBudget {
+    fn default() -> Self {
+        Self::unbounded().statements(1024).tuples(65536)
+    }
+}
+
+/// Limits on the work performed while computing a single epoch, beyond which
+/// the epoch is aborted.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Limits {
+    iterations: Option<usize>,
+    tuples: Option<usize>,
+    duration: Option<Duration>,
+}
+
+impl Limits {
+    /// The maximum number of iterations of any loop.
+    pub fn iterations(self, iterations: usize) -> Self {
+        Self {
+            iterations: Some(iterations),
+            ..self
+        }
+    }
+
+    /// The maximum number of tuples in any relation.
+    pub fn tuples(self, tuples: usize) -> Self {
+        Self {
+            tuples: Some(tuples),
+            ..self
+        }
+    }
+
+    /// The maximum wall time spent computing an epoch, including time spent
+    /// yielded between slices.
+    pub fn duration(self, duration: Duration) -> Self {
+        Self {
+            duration: Some(duration),
+            ..self
+        }
+    }
+}
+
 pub(crate) struct VM<T = DefaultTimestamp> {
     timestamp: T,
     pc: (usize, Option<usize>),
+    epoch_start: Option<(T, Instant)>,
+    iteration: usize,
+    limits: Limits,
+    projected: AtomicUsize,
+    parallel_search_threshold: usize,
+    executor: Executor,
+    // Set in strict mode, to collect the tuples that don't conform to their schemas
+    rejections: Option<Rejections>,
     input: VecDeque<Tuple>,
     output: VecDeque<Tuple>,
     program: Program,
@@ -48,6 +176,13 @@ where
         Self {
             timestamp: T::default(),
             pc: (0, None),
+            epoch_start: None,
+            iteration: 0,
+            limits: Limits::default(),
+            projected: AtomicUsize::new(0),
+            parallel_search_threshold: PARALLEL_SEARCH_THRESHOLD,
+            executor: Executor::default(),
+            rejections: None,
             input: VecDeque::default(),
             output: VecDeque::default(),
             program,
@@ -55,6 +190,24 @@ where
         }
     }
 
+    pub(crate) fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+
+        self
+    }
+
+    pub(crate) fn with_executor(mut self, executor: Executor) -> Self {
+        self.executor = executor;
+
+        self
+    }
+
+    pub(crate) fn with_strict(mut self, is_strict: bool) -> Self {
+        self.rejections = is_strict.then(Mutex::default);
+
+        self
+    }
+
     pub(crate) fn timestamp(&self) -> &T {
         &self.timestamp
     }
@@ -71,59 +224,128 @@ where
         Ok(tuple)
     }
 
+    /// Takes the tuples rejected in strict mode, along with why they were rejected.
+    pub(crate) fn take_rejected(&mut self) -> Result<Vec<(Tuple, Error)>> {
+        let Some(rejections) = &mut self.rejections else {
+            return Ok(Vec::default());
+        };
+
+        let rejections = rejections.get_mut().or_else(|_| {
+            error(Error::InternalRhizomeError(
+                "rejections lock poisoned".to_owned(),
+            ))
+        })?;
+
+        Ok(mem::take(rejections))
+    }
+
     pub(crate) fn reset_relations(&mut self) -> Result<()> {
         self.program.relations().iter().for_each(|(_, relation)| {
             relation.write().unwrap().purge();
         });
 
+        self.program.reset_aggregations();
         self.should_insert_ground_facts = true;
 
         Ok(())
     }
 
+    #[cfg(test)]
     pub(crate) fn step_epoch<BS>(&mut self, blockstore: &BS) -> Result<()>
     where
-        BS: Blockstore,
+        BS: Blockstore + MaybeSync,
+    {
+        while !self.step_slice(blockstore, StepBudget::unbounded())? {}
+
+        Ok(())
+    }
+
+    /// Runs the current epoch until it completes or the budget is exhausted,
+    /// returning whether the epoch completed. An incomplete epoch resumes from
+    /// where it left off on the next call.
+    pub(crate) fn step_slice<BS>(&mut self, blockstore: &BS, budget: StepBudget) -> Result<bool>
+    where
+        BS: Blockstore + MaybeSync,
     {
-        debug_assert!(self.timestamp == self.timestamp.epoch_start());
+        let (start, started_at) = match self.epoch_start {
+            Some(start) => start,
+            None => {
+                debug_assert!(self.timestamp == self.timestamp.epoch_start());
+
+                let start = (self.timestamp, Instant::now());
+
+                // The relations aggregated over may have changed since the last epoch
+                self.program.reset_aggregations();
+
+                self.epoch_start = Some(start);
+                start
+            }
+        };
 
-        let start = self.timestamp;
+        let mut statements = 0;
+        self.projected.store(0, Ordering::Relaxed);
 
         loop {
             if !self.step(blockstore)? || self.timestamp.epoch() != start.epoch() {
                 self.should_insert_ground_facts = false;
+                self.epoch_start = None;
 
-                break;
+                return Ok(true);
             };
+
+            if let Some(limit) = self.limits.duration {
+                if started_at.elapsed() > limit {
+                    return error(Error::EpochTimeLimitExceeded(limit));
+                }
+            }
+
+            statements += 1;
+
+            if budget.is_exhausted(statements, self.projected.load(Ordering::Relaxed)) {
+                return Ok(false);
+            }
         }
+    }
 
-        Ok(())
+    /// Abandons the epoch in progress, rolling the timestamp back to the start of
+    /// the epoch and purging all relations, which must then be repopulated from
+    /// every epoch observed so far.
+    pub(crate) fn abort_epoch(&mut self) -> Result<()> {
+        if let Some((start, _)) = self.epoch_start.take() {
+            self.timestamp = start;
+        }
+
+        self.pc = (0, None);
+        self.iteration = 0;
+        self.input.clear();
+        self.output.clear();
+
+        // Tuples rejected during the epoch are rejected again when it's recomputed
+        if let Some(rejections) = &mut self.rejections {
+            rejections
+                .get_mut()
+                .or_else(|_| {
+                    error(Error::InternalRhizomeError(
+                        "rejections lock poisoned".to_owned(),
+                    ))
+                })?
+                .clear();
+        }
+
+        self.reset_relations()
     }
 
     fn step<BS>(&mut self, blockstore: &BS) -> Result<bool>
     where
-        BS: Blockstore,
+        BS: Blockstore + MaybeSync,
     {
-        let continue_epoch = match &*self.load_statement()? {
-            Statement::Insert(insert) => self.handle_insert(insert, blockstore),
-            Statement::Merge(merge) => self.handle_merge(merge),
-            Statement::Swap(swap) => self.handle_swap(swap),
-            Statement::Purge(purge) => self.handle_purge(purge),
-            Statement::Exit(exit) => {
-                debug_assert!(self.pc.1.is_some());
-
-                self.handle_exit(exit)
-            }
-            Statement::Sources(sources) => self.handle_sources(sources),
-            Statement::Sinks(sinks) => self.handle_sinks(sinks),
-            Statement::Loop(Loop { .. }) => {
-                return error(Error::InternalRhizomeError(
-                    "nested loop encountered".to_owned(),
-                ));
-            }
-        }?;
+        if let Some(parallel) = self.parallel_inserts()? {
+            self.handle_parallel_inserts(parallel.groups(), blockstore)?;
 
-        if !continue_epoch {
+            // Skip to the last insert of the run, so that stepping the PC moves past it
+            let len = parallel.len();
+            self.pc.1 = self.pc.1.map(|inner| inner + len - 1);
+        } else if !self.handle_statement(blockstore)? {
             return Ok(false);
         }
 
@@ -133,11 +355,44 @@ where
             self.timestamp = self.timestamp.advance_epoch();
         } else if self.pc.1 == Some(0) {
             self.timestamp = self.timestamp.advance_iteration();
+            self.iteration += 1;
+
+            if let Some(limit) = self.limits.iterations {
+                if self.iteration > limit {
+                    return error(Error::IterationLimitExceeded(limit));
+                }
+            }
         };
 
+        if self.pc.1.is_none() {
+            self.iteration = 0;
+        }
+
         Ok(true)
     }
 
+    fn handle_statement<BS>(&mut self, blockstore: &BS) -> Result<bool>
+    where
+        BS: Blockstore + MaybeSync,
+    {
+        match &*self.load_statement()? {
+            Statement::Insert(insert) => self.handle_insert(insert, blockstore),
+            Statement::Merge(merge) => self.handle_merge(merge),
+            Statement::Swap(swap) => self.handle_swap(swap),
+            Statement::Purge(purge) => self.handle_purge(purge),
+            Statement::Exit(exit) => {
+                debug_assert!(self.pc.1.is_some());
+
+                self.handle_exit(exit)
+            }
+            Statement::Sources(sources) => self.handle_sources(sources),
+            Statement::Sinks(sinks) => self.handle_sinks(sinks),
+            Statement::Loop(Loop { .. }) => error(Error::InternalRhizomeError(
+                "nested loop encountered".to_owned(),
+            )),
+        }
+    }
+
     fn step_pc(&self) -> Result<(usize, Option<usize>)> {
         match self.pc {
             (outer, None) => {
@@ -197,88 +452,92 @@ where
 
     fn handle_insert<BS>(&mut self, insert: &Insert, blockstore: &BS) -> Result<bool>
     where
-        BS: Blockstore,
+        BS: Blockstore + MaybeSync,
     {
         if insert.is_ground() && !self.should_insert_ground_facts {
             Ok(true)
         } else {
-            self.handle_operation(insert.operation(), blockstore)
+            Evaluator::new(
+                blockstore,
+                &self.projected,
+                self.rejections.as_ref(),
+                self.parallel_search_threshold,
+                self.executor,
+            )
+            .handle_insert(insert)
         }
     }
 
-    fn handle_operation<BS>(&mut self, operation: &Operation, blockstore: &BS) -> Result<bool>
-    where
-        BS: Blockstore,
-    {
-        let bindings = Bindings::default();
+    /// Returns the parallel inserts starting at the PC, if it's within a loop body.
+    fn parallel_inserts(&self) -> Result<Option<&ParallelInserts>> {
+        let (Some(inner), Some(outer_statement)) =
+            (self.pc.1, self.program.statements().get(self.pc.0))
+        else {
+            return Ok(None);
+        };
 
-        self.do_handle_operation(operation, blockstore, &bindings)
+        let Statement::Loop(loop_statement) = &**outer_statement else {
+            return error(Error::InternalRhizomeError(
+                "current statement must be a loop".to_owned(),
+            ));
+        };
+
+        Ok(loop_statement.parallel_inserts(inner))
     }
 
-    fn do_handle_operation<BS>(
+    fn handle_parallel_inserts<BS>(
         &self,
-        operation: &Operation,
+        groups: &[Vec<Arc<Statement>>],
         blockstore: &BS,
-        bindings: &Bindings,
     ) -> Result<bool>
     where
-        BS: Blockstore,
+        BS: Blockstore + MaybeSync,
     {
-        match operation {
-            Operation::Search(inner) => self.handle_search(inner, blockstore, bindings),
-            Operation::Project(inner) => self.handle_project(inner, blockstore, bindings),
-            Operation::Aggregation(inner) => self.handle_aggregation(inner, blockstore, bindings),
-        }?;
+        let (projected, rejections, parallel_search_threshold, executor) = (
+            &self.projected,
+            self.rejections.as_ref(),
+            self.parallel_search_threshold,
+            self.executor,
+        );
 
-        Ok(true)
-    }
+        let handle_group = |group: &Vec<Arc<Statement>>| -> Result<()> {
+            let evaluator = Evaluator::new(
+                blockstore,
+                projected,
+                rejections,
+                parallel_search_threshold,
+                executor,
+            );
 
-    fn handle_search<BS>(
-        &self,
-        search: &Search,
-        blockstore: &BS,
-        bindings: &Bindings,
-    ) -> Result<bool>
-    where
-        BS: Blockstore,
-    {
-        search.apply(blockstore, bindings, |next_bindings| {
-            self.do_handle_operation(search.operation(), blockstore, &next_bindings)
-        })
-    }
+            for statement in group {
+                if let Statement::Insert(insert) = &**statement {
+                    evaluator.handle_insert(insert)?;
+                }
+            }
 
-    fn handle_project<BS>(
-        &self,
-        project: &Project,
-        blockstore: &BS,
-        bindings: &Bindings,
-    ) -> Result<bool>
-    where
-        BS: Blockstore,
-    {
-        project.apply(blockstore, bindings)?;
+            Ok(())
+        };
 
-        Ok(true)
-    }
+        #[cfg(not(target_arch = "wasm32"))]
+        groups.par_iter().try_for_each(handle_group)?;
 
-    fn handle_aggregation<BS>(
-        &self,
-        agg: &Aggregation,
-        blockstore: &BS,
-        bindings: &Bindings,
-    ) -> Result<bool>
-    where
-        BS: Blockstore,
-    {
-        if let Some(next_bindings) = agg.apply(blockstore, bindings)? {
-            self.do_handle_operation(agg.operation(), blockstore, &next_bindings)?;
-        }
+        #[cfg(target_arch = "wasm32")]
+        groups.iter().try_for_each(handle_group)?;
 
         Ok(true)
     }
 
     fn handle_merge(&self, merge: &Merge) -> Result<bool> {
-        merge.apply()?;
+        let len = merge.apply()?;
+
+        if let Some(limit) = self.limits.tuples {
+            if len > limit {
+                return error(Error::RelationSizeLimitExceeded(
+                    merge.target_key().0,
+                    limit,
+                ));
+            }
+        }
 
         Ok(true)
     }
@@ -304,7 +563,7 @@ where
     }
 
     fn handle_sources(&mut self, sources: &Sources) -> Result<bool> {
-        Ok(sources.apply(&mut self.input)?
+        Ok(sources.apply(&mut self.input, self.rejections.as_ref())?
             || self.timestamp().epoch_start() == self.timestamp().clock_start())
     }
 
@@ -314,3 +573,681 @@ where
         Ok(true)
     }
 }
+
+/// Evaluates the operations of insert statements, which may happen on several
+/// threads at once.
+struct Evaluator<'a, BS> {
+    blockstore: &'a BS,
+    projected: &'a AtomicUsize,
+    rejections: Option<&'a Rejections>,
+    parallel_search_threshold: usize,
+    executor: Executor,
+    // Facts projected while evaluating one chunk of a parallel search, to be
+    // inserted once every chunk has been evaluated.
+    buffer: Option<RefCell<Vec<ProjectedFact>>>,
+}
+
+impl<'a, BS> Evaluator<'a, BS>
+where
+    BS: Blockstore + MaybeSync,
+{
+    fn new(
+        blockstore: &'a BS,
+        projected: &'a AtomicUsize,
+        rejections: Option<&'a Rejections>,
+        parallel_search_threshold: usize,
+        executor: Executor,
+    ) -> Self {
+        Self {
+            blockstore,
+            projected,
+            rejections,
+            parallel_search_threshold,
+            executor,
+            buffer: None,
+        }
+    }
+
+    fn handle_insert(&self, insert: &Insert) -> Result<bool> {
+        if self.executor == Executor::Batch {
+            BatchEvaluator::new(self.projected, self.rejections, insert.slots())
+                .handle_operation(insert.operation())?;
+
+            return Ok(true);
+        }
+
+        let bindings = Bindings::new(insert.slots());
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Operation::Search(search) = insert.operation() {
+            if !search.is_outer() && search.len()? >= self.parallel_search_threshold {
+                return self.handle_parallel_search(search, &bindings);
+            }
+        }
+
+        self.handle_operation(insert.operation(), &bindings)
+    }
+
+    fn handle_operation(&self, operation: &Operation, bindings: &Bindings) -> Result<bool> {
+        match operation {
+            Operation::Search(inner) => self.handle_search(inner, bindings),
+            Operation::Project(inner) => self.handle_project(inner, bindings),
+            Operation::Aggregation(inner) => self.handle_aggregation(inner, bindings),
+        }?;
+
+        Ok(true)
+    }
+
+    fn handle_search(&self, search: &Search, bindings: &Bindings) -> Result<bool> {
+        search.apply(self.blockstore, bindings, |next_bindings| {
+            self.handle_operation(search.operation(), &next_bindings)
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn handle_parallel_search(&self, search: &Search, bindings: &Bindings) -> Result<bool> {
+        let (blockstore, projected, rejections, parallel_search_threshold, executor) = (
+            self.blockstore,
+            self.projected,
+            self.rejections,
+            self.parallel_search_threshold,
+            self.executor,
+        );
+
+        let buffers = search.with_candidates(blockstore, bindings, |candidates| {
+            let chunk_size = (candidates.len() / rayon::current_num_threads()).max(1);
+
+            candidates
+                .par_chunks(chunk_size)
+                .map(|chunk| {
+                    let evaluator = Self {
+                        blockstore,
+                        projected,
+                        rejections,
+                        parallel_search_threshold,
+                        executor,
+                        buffer: Some(RefCell::default()),
+                    };
+
+                    for fact in chunk {
+                        if let Some(next_bindings) = search.bind(blockstore, bindings, fact)? {
+                            evaluator.handle_operation(search.operation(), &next_bindings)?;
+                        }
+                    }
+
+                    Ok(evaluator.buffer.unwrap_or_default().into_inner())
+                })
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        let project = search.operation().project();
+        let mut target = project.relation().write().or_else(|_| {
+            error(Error::InternalRhizomeError(
+                "relation lock poisoned".to_owned(),
+            ))
+        })?;
+
+        for (bound, fact) in buffers.into_iter().flatten() {
+            project.insert(&mut target, bound, fact)?;
+        }
+
+        Ok(true)
+    }
+
+    fn handle_project(&self, project: &Project, bindings: &Bindings) -> Result<bool> {
+        match &self.buffer {
+            Some(buffer) => {
+                if let Some(projected) =
+                    project.project(self.blockstore, bindings, self.rejections)?
+                {
+                    buffer.borrow_mut().push(projected);
+                }
+            }
+            None => project.apply(self.blockstore, bindings, self.rejections)?,
+        }
+
+        self.projected.fetch_add(1, Ordering::Relaxed);
+
+        Ok(true)
+    }
+
+    fn handle_aggregation(&self, agg: &Aggregation, bindings: &Bindings) -> Result<bool> {
+        for next_bindings in agg.apply(self.blockstore, bindings)? {
+            self.handle_operation(agg.operation(), &next_bindings)?;
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        build,
+        col_val::ColVal,
+        id::RelationId,
+        kernel::math,
+        storage::memory::MemoryBlockstore,
+        types::{ColType, Type},
+        value::{Any, Val},
+    };
+
+    use super::*;
+
+    fn transitive_closure() -> Result<Program> {
+        build(|p| {
+            p.output("edge", |h| h.column::<i32>("from").column::<i32>("to"))?;
+            p.output("path", |h| h.column::<i32>("from").column::<i32>("to"))?;
+
+            for i in 0..8 {
+                p.fact("edge", |f| f.bind((("from", i), ("to", i + 1))))?;
+            }
+
+            p.rule::<(i32, i32)>("path", &|h, b, (x, y)| {
+                h.bind((("from", x), ("to", y)))?;
+                b.search("edge", (("from", x), ("to", y)))?;
+
+                Ok(())
+            })?;
+
+            p.rule::<(i32, i32, i32)>("path", &|h, b, (x, y, z)| {
+                h.bind((("from", x), ("to", z)))?;
+
+                b.search("edge", (("from", x), ("to", y)))?;
+                b.search("path", (("from", y), ("to", z)))?;
+
+                Ok(())
+            })?;
+
+            Ok(p)
+        })
+    }
+
+    #[test]
+    fn test_step_slice() -> Result<()> {
+        let program = transitive_closure;
+
+        let bs = MemoryBlockstore::default();
+
+        let mut expected_vm = <VM>::new(program()?);
+        expected_vm.step_epoch(&bs)?;
+
+        let mut expected = BTreeSet::default();
+        while let Some(tuple) = expected_vm.pop()? {
+            expected.insert(tuple);
+        }
+
+        let mut vm = <VM>::new(program()?);
+        let mut slices = 1;
+
+        while !vm.step_slice(&bs, StepBudget::unbounded().statements(1))? {
+            slices += 1;
+        }
+
+        let mut actual = BTreeSet::default();
+        while let Some(tuple) = vm.pop()? {
+            actual.insert(tuple);
+        }
+
+        assert!(slices > 1);
+        assert_eq!(expected, actual);
+        assert_eq!(expected_vm.timestamp(), vm.timestamp());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_limits() -> Result<()> {
+        let bs = MemoryBlockstore::default();
+
+        let mut vm = <VM>::new(transitive_closure()?).with_limits(Limits::default().iterations(2));
+        let err = vm.step_epoch(&bs).unwrap_err().downcast::<Error>()?;
+
+        assert_eq!(Error::IterationLimitExceeded(2), err);
+
+        let mut vm = <VM>::new(transitive_closure()?).with_limits(Limits::default().tuples(16));
+        let err = vm.step_epoch(&bs).unwrap_err().downcast::<Error>()?;
+
+        assert_eq!(
+            Error::RelationSizeLimitExceeded(RelationId::new("path"), 16),
+            err
+        );
+
+        vm.abort_epoch()?;
+        vm.limits = Limits::default();
+        vm.step_epoch(&bs)?;
+
+        let mut paths = BTreeSet::default();
+        while let Some(tuple) = vm.pop()? {
+            if tuple.id() == RelationId::new("path") {
+                paths.insert(tuple);
+            }
+        }
+
+        assert_eq!(36, paths.len());
+        assert_eq!(&DefaultTimestamp::default().advance_epoch(), vm.timestamp());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_inserts() -> Result<()> {
+        let program = build(|p| {
+            p.output("edge", |h| h.column::<i32>("from").column::<i32>("to"))?;
+            p.output("odd", |h| h.column::<i32>("from").column::<i32>("to"))?;
+            p.output("even", |h| h.column::<i32>("from").column::<i32>("to"))?;
+
+            for i in 0..4 {
+                p.fact("edge", |f| f.bind((("from", i), ("to", i + 1))))?;
+            }
+
+            p.rule::<(i32, i32)>("odd", &|h, b, (x, y)| {
+                h.bind((("from", x), ("to", y)))?;
+                b.search("edge", (("from", x), ("to", y)))?;
+
+                Ok(())
+            })?;
+
+            p.rule::<(i32, i32, i32)>("odd", &|h, b, (x, y, z)| {
+                h.bind((("from", x), ("to", z)))?;
+
+                b.search("even", (("from", x), ("to", y)))?;
+                b.search("edge", (("from", y), ("to", z)))?;
+
+                Ok(())
+            })?;
+
+            p.rule::<(i32, i32, i32)>("even", &|h, b, (x, y, z)| {
+                h.bind((("from", x), ("to", z)))?;
+
+                b.search("odd", (("from", x), ("to", y)))?;
+                b.search("edge", (("from", y), ("to", z)))?;
+
+                Ok(())
+            })?;
+
+            Ok(p)
+        })?;
+
+        let bs = MemoryBlockstore::default();
+        let mut vm = <VM>::new(program);
+        let mut parallel = false;
+
+        loop {
+            parallel |= vm.parallel_inserts()?.is_some();
+
+            if vm.step_slice(&bs, StepBudget::unbounded().statements(1))? {
+                break;
+            }
+        }
+
+        let mut actual = BTreeSet::default();
+        while let Some(tuple) = vm.pop()? {
+            if tuple.id() != RelationId::new("edge") {
+                actual.insert(tuple);
+            }
+        }
+
+        let mut expected = BTreeSet::default();
+        for x in 0..4 {
+            for z in x + 1..=4 {
+                let id = if (z - x) % 2 == 1 { "odd" } else { "even" };
+
+                expected.insert(Tuple::new(id, [("from", x), ("to", z)], None));
+            }
+        }
+
+        assert!(parallel);
+        assert_eq!(expected, actual);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_search() -> Result<()> {
+        let bs = MemoryBlockstore::default();
+
+        let mut expected_vm = <VM>::new(transitive_closure()?);
+        expected_vm.parallel_search_threshold = usize::MAX;
+        expected_vm.step_epoch(&bs)?;
+
+        let mut expected = BTreeSet::default();
+        while let Some(tuple) = expected_vm.pop()? {
+            expected.insert(tuple);
+        }
+
+        let mut vm = <VM>::new(transitive_closure()?);
+        vm.parallel_search_threshold = 1;
+        vm.step_epoch(&bs)?;
+
+        let mut actual = BTreeSet::default();
+        while let Some(tuple) = vm.pop()? {
+            actual.insert(tuple);
+        }
+
+        assert_eq!(expected, actual);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_executor() -> Result<()> {
+        // Enough pairs that the batches fill up before the searches complete
+        let program = || {
+            build(|p| {
+                p.output("node", |h| h.column::<i32>("id"))?;
+                p.output("pair", |h| h.column::<i32>("left").column::<i32>("right"))?;
+
+                for i in 0..48 {
+                    p.fact("node", |f| f.bind((("id", i),)))?;
+                }
+
+                p.rule::<(i32, i32)>("pair", &|h, b, (x, y)| {
+                    h.bind((("left", x), ("right", y)))?;
+
+                    b.search("node", (("id", x),))?;
+                    b.search("node", (("id", y),))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            })
+        };
+
+        let bs = MemoryBlockstore::default();
+
+        let mut expected_vm = <VM>::new(program()?);
+        expected_vm.step_epoch(&bs)?;
+
+        let mut expected = BTreeSet::default();
+        while let Some(tuple) = expected_vm.pop()? {
+            expected.insert(tuple);
+        }
+
+        let mut vm = <VM>::new(program()?).with_executor(Executor::Batch);
+        vm.step_epoch(&bs)?;
+
+        let mut actual = BTreeSet::default();
+        while let Some(tuple) = vm.pop()? {
+            actual.insert(tuple);
+        }
+
+        assert_eq!(expected.len(), 48 + 48 * 48);
+        assert_eq!(expected, actual);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_strict() -> Result<()> {
+        let program = || {
+            build(|p| {
+                p.input("p", |h| h.column::<i64>("x"))?;
+                p.input("r", |h| h.column::<Any>("v"))?;
+                p.output("q", |h| h.column::<i64>("x"))?;
+                p.output("s", |h| h.column::<i32>("v"))?;
+
+                p.rule::<(i64,)>("q", &|h, b, (x,)| {
+                    h.bind((("x", x),))?;
+                    b.search("p", (("x", x),))?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(i32,)>("s", &|h, b, (v,)| {
+                    h.bind((("v", v),))?;
+                    b.search("r", (("v", v),))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            })
+        };
+
+        let bs = MemoryBlockstore::default();
+
+        for executor in [Executor::Tuple, Executor::Batch] {
+            let mut vm = <VM>::new(program()?)
+                .with_executor(executor)
+                .with_strict(true);
+
+            vm.push(Tuple::new("p", [("x", 1)], None))?;
+            vm.push(Tuple::new("p", [("x", "a")], None))?;
+            vm.push(Tuple::new("p", [("y", 2_i64)], None))?;
+            vm.push(Tuple::new("r", [("v", 3)], None))?;
+            vm.push(Tuple::new("r", [("v", "b")], None))?;
+            vm.step_epoch(&bs)?;
+
+            let mut actual = BTreeSet::default();
+            while let Some(tuple) = vm.pop()? {
+                actual.insert(tuple);
+            }
+
+            assert_eq!(
+                BTreeSet::from_iter([
+                    Tuple::new("q", [("x", 1_i64)], None),
+                    Tuple::new("s", [("v", 3)], None),
+                ]),
+                actual
+            );
+
+            assert_eq!(
+                vec![
+                    (
+                        Tuple::new("p", [("x", "a")], None),
+                        Error::ColumnValueTypeConflict(
+                            "p".into(),
+                            "x".into(),
+                            ColVal::Lit(Val::from("a")),
+                            ColType::Type(Type::S64)
+                        )
+                    ),
+                    (
+                        Tuple::new("p", [("y", 2_i64)], None),
+                        Error::UnrecognizedColumnBinding("p".into(), "y".into())
+                    ),
+                    (
+                        Tuple::new("s", [("v", "b")], None),
+                        Error::ColumnValueTypeConflict(
+                            "s".into(),
+                            "v".into(),
+                            ColVal::Lit(Val::from("b")),
+                            ColType::Type(Type::S32)
+                        )
+                    ),
+                ],
+                vm.take_rejected()?
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_aggregation_across_epochs() -> Result<()> {
+        let program = || {
+            build(|p| {
+                p.input("score", |h| {
+                    h.column::<&str>("team").column::<i32>("points")
+                })?;
+                p.output("entry", |h| {
+                    h.column::<&str>("team").column::<i32>("points")
+                })?;
+                p.output("total", |h| {
+                    h.column::<&str>("team").column::<i32>("points")
+                })?;
+
+                p.rule::<(&str, i32)>("entry", &|h, b, (team, points)| {
+                    h.bind((("team", team), ("points", points)))?;
+                    b.search("score", (("team", team), ("points", points)))?;
+
+                    Ok(())
+                })?;
+
+                p.rule::<(&str, i32, i32)>("total", &|h, b, (team, total, points)| {
+                    h.bind((("team", team), ("points", total)))?;
+                    b.search("entry", (("team", team),))?;
+                    b.group_by(
+                        total,
+                        "entry",
+                        (("team", team), ("points", points)),
+                        math::sum(points),
+                    )?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            })
+        };
+
+        let bs = MemoryBlockstore::default();
+
+        for executor in [Executor::Tuple, Executor::Batch] {
+            let mut vm = <VM>::new(program()?).with_executor(executor);
+
+            let epoch = |vm: &mut VM, tuples: Vec<Tuple>| -> Result<BTreeSet<Tuple>> {
+                for tuple in tuples {
+                    vm.push(tuple)?;
+                }
+
+                vm.step_epoch(&bs)?;
+
+                let mut actual = BTreeSet::default();
+                while let Some(tuple) = vm.pop()? {
+                    if tuple.id() == RelationId::new("total") {
+                        actual.insert(tuple);
+                    }
+                }
+
+                Ok(actual)
+            };
+
+            assert_eq!(
+                BTreeSet::from_iter([
+                    Tuple::new(
+                        "total",
+                        [("team", Val::from("red")), ("points", 7.into())],
+                        None
+                    ),
+                    Tuple::new(
+                        "total",
+                        [("team", Val::from("blue")), ("points", 1.into())],
+                        None
+                    ),
+                ]),
+                epoch(
+                    &mut vm,
+                    vec![
+                        Tuple::new(
+                            "score",
+                            [("team", Val::from("red")), ("points", 3.into())],
+                            None
+                        ),
+                        Tuple::new(
+                            "score",
+                            [("team", Val::from("red")), ("points", 4.into())],
+                            None
+                        ),
+                        Tuple::new(
+                            "score",
+                            [("team", Val::from("blue")), ("points", 1.into())],
+                            None
+                        ),
+                    ]
+                )?
+            );
+
+            // The groups are rebuilt from the relation as it is in the new epoch
+            assert_eq!(
+                BTreeSet::from_iter([Tuple::new(
+                    "total",
+                    [("team", Val::from("red")), ("points", 12.into())],
+                    None
+                )]),
+                epoch(
+                    &mut vm,
+                    vec![Tuple::new(
+                        "score",
+                        [("team", Val::from("red")), ("points", 5.into())],
+                        None
+                    )]
+                )?
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_choice_across_epochs() -> Result<()> {
+        let program = || {
+            build(|p| {
+                p.input("child", |h| {
+                    h.column::<&str>("parent").column::<&str>("name")
+                })?;
+                p.output("first", |h| {
+                    h.column::<&str>("parent").column::<&str>("name")
+                })?;
+
+                p.rule::<(&str, &str)>("first", &|h, b, (parent, name)| {
+                    h.bind((("parent", parent), ("name", name)))?;
+                    h.choose(["parent"], ["name"])?;
+                    b.search("child", (("parent", parent), ("name", name)))?;
+
+                    Ok(())
+                })?;
+
+                Ok(p)
+            })
+        };
+
+        let bs = MemoryBlockstore::default();
+
+        for executor in [Executor::Tuple, Executor::Batch] {
+            let mut vm = <VM>::new(program()?).with_executor(executor);
+
+            let epoch = |vm: &mut VM, tuples: Vec<Tuple>| -> Result<BTreeSet<Tuple>> {
+                for tuple in tuples {
+                    vm.push(tuple)?;
+                }
+
+                vm.step_epoch(&bs)?;
+
+                let mut actual = BTreeSet::default();
+                while let Some(tuple) = vm.pop()? {
+                    if tuple.id() == RelationId::new("first") {
+                        actual.insert(tuple);
+                    }
+                }
+
+                Ok(actual)
+            };
+
+            assert_eq!(
+                BTreeSet::from_iter([Tuple::new("first", [("parent", "a"), ("name", "m")], None)]),
+                epoch(
+                    &mut vm,
+                    vec![Tuple::new("child", [("parent", "a"), ("name", "m")], None)]
+                )?
+            );
+
+            // A lesser name doesn't replace the one chosen in an earlier epoch
+            assert_eq!(
+                BTreeSet::default(),
+                epoch(
+                    &mut vm,
+                    vec![Tuple::new("child", [("parent", "a"), ("name", "c")], None)]
+                )?
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -65,7 +65,7 @@ pub trait Blockstore {
     }
 }
 
-impl<'a, T: Blockstore> Blockstore for &'a T {
+impl<T: Blockstore> Blockstore for &T {
     fn has(&self, k: &Cid) -> Result<bool> {
         (**self).has(k)
     }
@@ -79,7 +79,7 @@ impl<'a, T: Blockstore> Blockstore for &'a T {
     }
 }
 
-impl<'a, T: Blockstore> Blockstore for &'a mut T {
+impl<T: Blockstore> Blockstore for &mut T {
     fn has(&self, k: &Cid) -> Result<bool> {
         (**self).has(k)
     }
@@ -3,16 +3,19 @@
 // differences in the API for our blockstore.
 
 use std::{
-    cell::RefCell,
     collections::HashMap,
     io::{Cursor, Read, Seek},
+    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
 };
 
 use anyhow::{anyhow, Result};
 use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
 use cid::Cid;
 
-use crate::storage::codec::{Codec, DagCbor};
+use crate::{
+    error::{error, Error},
+    storage::codec::{Codec, DagCbor},
+};
 
 use super::blockstore::Blockstore;
 
@@ -20,10 +23,10 @@ pub trait Buffered: Blockstore {
     fn flush(&self, root: &Cid) -> Result<()>;
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Default)]
 pub struct BufferedBlockstore<BS> {
     inner: BS,
-    write: RefCell<HashMap<Cid, Vec<u8>>>,
+    write: RwLock<HashMap<Cid, Vec<u8>>>,
 }
 
 impl<BS> BufferedBlockstore<BS>
@@ -40,6 +43,40 @@ where
     pub fn into_inner(self) -> BS {
         self.inner
     }
+
+    fn read_buffer(&self) -> Result<RwLockReadGuard<'_, HashMap<Cid, Vec<u8>>>> {
+        self.write.read().or_else(|_| {
+            error(Error::InternalRhizomeError(
+                "blockstore lock poisoned".to_owned(),
+            ))
+        })
+    }
+
+    fn write_buffer(&self) -> Result<RwLockWriteGuard<'_, HashMap<Cid, Vec<u8>>>> {
+        self.write.write().or_else(|_| {
+            error(Error::InternalRhizomeError(
+                "blockstore lock poisoned".to_owned(),
+            ))
+        })
+    }
+}
+
+impl<BS> Clone for BufferedBlockstore<BS>
+where
+    BS: Clone,
+{
+    fn clone(&self) -> Self {
+        let write = self
+            .write
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+
+        Self {
+            inner: self.inner.clone(),
+            write: RwLock::new(write),
+        }
+    }
 }
 
 impl<BS> Buffered for BufferedBlockstore<BS>
@@ -48,7 +85,7 @@ where
 {
     fn flush(&self, root: &Cid) -> Result<()> {
         let mut buffer = Vec::new();
-        let write = self.write.borrow_mut();
+        let write = self.read_buffer()?;
 
         copy_rec(&write, *root, &mut buffer)?;
         self.inner.put_many_keyed(buffer)?;
@@ -204,7 +241,7 @@ where
     BS: Blockstore,
 {
     fn has(&self, k: &Cid) -> Result<bool> {
-        if self.write.borrow().contains_key(k) {
+        if self.read_buffer()?.contains_key(k) {
             Ok(true)
         } else {
             Ok(self.inner.has(k)?)
@@ -212,7 +249,7 @@ where
     }
 
     fn get(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
-        if let Some(data) = self.write.borrow().get(k) {
+        if let Some(data) = self.read_buffer()?.get(k) {
             Ok(Some(data.clone()))
         } else {
             Ok(self.inner.get(k)?)
@@ -220,7 +257,7 @@ where
     }
 
     fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
-        self.write.borrow_mut().insert(*k, block.to_vec());
+        self.write_buffer()?.insert(*k, block.to_vec());
 
         Ok(())
     }
@@ -230,8 +267,7 @@ where
         D: AsRef<[u8]>,
         I: IntoIterator<Item = (Cid, D)>,
     {
-        self.write
-            .borrow_mut()
+        self.write_buffer()?
             .extend(blocks.into_iter().map(|(k, v)| (k, v.as_ref().into())));
 
         Ok(())
@@ -240,7 +276,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
+    use std::rc::Rc;
 
     use serde::{Deserialize, Serialize};
 
@@ -250,8 +286,8 @@ mod tests {
 
     #[test]
     fn test_buffered_store() {
-        let mem = Arc::new(MemoryBlockstore::default());
-        let buf_store = BufferedBlockstore::new(Arc::clone(&mem));
+        let mem = Rc::new(MemoryBlockstore::default());
+        let buf_store = BufferedBlockstore::new(Rc::clone(&mem));
 
         let cid = buf_store
             .put(cid::multihash::Code::Sha2_256, &Block::new(DagCbor, &[8]))
@@ -274,8 +310,8 @@ mod tests {
 
     #[test]
     fn test_buffered_store_with_links() {
-        let mem = Arc::new(MemoryBlockstore::default());
-        let buf_store = BufferedBlockstore::new(Arc::clone(&mem));
+        let mem = Rc::new(MemoryBlockstore::default());
+        let buf_store = BufferedBlockstore::new(Rc::clone(&mem));
 
         let str_val = String::from("value");
         let value = 8u8;
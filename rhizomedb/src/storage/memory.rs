@@ -1,33 +1,68 @@
 use anyhow::Result;
 use cid::Cid;
-use std::{cell::RefCell, collections::HashMap};
+use std::{
+    collections::HashMap,
+    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use crate::error::{error, Error};
 
 use super::blockstore::Blockstore;
 
-#[derive(Clone, Debug, Default)]
+#[derive(Debug, Default)]
 pub struct MemoryBlockstore {
-    blocks: RefCell<HashMap<Cid, Vec<u8>>>,
+    blocks: RwLock<HashMap<Cid, Vec<u8>>>,
 }
 
 impl MemoryBlockstore {
     pub fn new() -> Self {
         Self {
-            blocks: RefCell::default(),
+            blocks: RwLock::default(),
+        }
+    }
+
+    fn read(&self) -> Result<RwLockReadGuard<'_, HashMap<Cid, Vec<u8>>>> {
+        self.blocks.read().or_else(|_| {
+            error(Error::InternalRhizomeError(
+                "blockstore lock poisoned".to_owned(),
+            ))
+        })
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, HashMap<Cid, Vec<u8>>>> {
+        self.blocks.write().or_else(|_| {
+            error(Error::InternalRhizomeError(
+                "blockstore lock poisoned".to_owned(),
+            ))
+        })
+    }
+}
+
+impl Clone for MemoryBlockstore {
+    fn clone(&self) -> Self {
+        let blocks = self
+            .blocks
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+
+        Self {
+            blocks: RwLock::new(blocks),
         }
     }
 }
 
 impl Blockstore for MemoryBlockstore {
     fn has(&self, k: &Cid) -> Result<bool> {
-        Ok(self.blocks.borrow().contains_key(k))
+        Ok(self.read()?.contains_key(k))
     }
 
     fn get(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
-        Ok(self.blocks.borrow().get(k).cloned())
+        Ok(self.read()?.get(k).cloned())
     }
 
     fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
-        self.blocks.borrow_mut().insert(*k, block.into());
+        self.write()?.insert(*k, block.into());
 
         Ok(())
     }
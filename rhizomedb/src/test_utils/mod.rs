@@ -39,84 +39,97 @@ macro_rules! assert_derives {
         );
     };
     ($program_closure:expr, $edb:expr, $expected:expr) => {
-        let program = match $crate::build($program_closure) {
-            std::result::Result::Ok(v) => v,
-            std::result::Result::Err(e) => {
-                panic!("Failed to build program: {:?}", e);
-            }
-        };
-
-        let mut b = Vec::default();
-        $crate::pretty::Pretty::to_doc(&program)
-            .render(80, &mut b)
-            .unwrap();
-
-        let pretty = String::from_utf8(b).unwrap();
-
-        let mut bs = $crate::storage::memory::MemoryBlockstore::default();
-        let mut vm = <$crate::runtime::vm::VM>::new(program);
-
-        for input_tuple in $edb {
-            $crate::storage::blockstore::Blockstore::put_serializable(
-                &mut bs,
-                &input_tuple,
-                #[allow(unknown_lints, clippy::default_constructed_unit_structs)]
-                $crate::storage::DefaultCodec::default(),
-                $crate::storage::DEFAULT_MULTIHASH,
-            )
-            .unwrap();
-
-            let cid = input_tuple.cid().unwrap();
-            let tuple = $crate::tuple::Tuple::new(
-                "evac",
-                [
-                    ("entity", input_tuple.entity()),
-                    ("attribute", input_tuple.attr()),
-                    ("value", input_tuple.val()),
-                ],
-                Some(cid),
-            );
-
-            vm.push(tuple).unwrap();
-
-            for link in input_tuple.links() {
-                let tuple = Tuple::new("links", [("from", cid), ("to", *link)], None);
+        let edb = Vec::<$crate::tuple::InputTuple>::from_iter($edb);
+
+        for executor in [
+            $crate::runtime::Executor::Tuple,
+            $crate::runtime::Executor::Batch,
+        ] {
+            let program = match $crate::build($program_closure) {
+                std::result::Result::Ok(v) => v,
+                std::result::Result::Err(e) => {
+                    panic!("Failed to build program: {:?}", e);
+                }
+            };
+
+            let mut b = Vec::default();
+            $crate::pretty::Pretty::to_doc(&program)
+                .render(80, &mut b)
+                .unwrap();
+
+            let pretty = String::from_utf8(b).unwrap();
+
+            let mut bs = $crate::storage::memory::MemoryBlockstore::default();
+            let mut vm = <$crate::runtime::vm::VM>::new(program).with_executor(executor);
+
+            for input_tuple in edb.clone() {
+                $crate::storage::blockstore::Blockstore::put_serializable(
+                    &mut bs,
+                    &input_tuple,
+                    #[allow(unknown_lints, clippy::default_constructed_unit_structs)]
+                    $crate::storage::DefaultCodec::default(),
+                    $crate::storage::DEFAULT_MULTIHASH,
+                )
+                .unwrap();
+
+                let cid = input_tuple.cid().unwrap();
+                let tuple = $crate::tuple::Tuple::new(
+                    "evac",
+                    [
+                        ("entity", input_tuple.entity()),
+                        ("attribute", input_tuple.attr()),
+                        ("value", input_tuple.val()),
+                    ],
+                    Some(cid),
+                );
 
                 vm.push(tuple).unwrap();
-            }
-        }
 
-        match vm.step_epoch(&bs) {
-            std::result::Result::Ok(v) => v,
-            std::result::Result::Err(e) => {
-                panic!("Failed to run program: {:?}", e);
+                for link in input_tuple.links() {
+                    let tuple = Tuple::new("links", [("from", cid), ("to", *link)], None);
+
+                    vm.push(tuple).unwrap();
+                }
             }
-        };
 
-        let mut tuples = std::collections::BTreeMap::default();
+            match vm.step_epoch(&bs) {
+                std::result::Result::Ok(v) => v,
+                std::result::Result::Err(e) => {
+                    panic!("Failed to run program: {:?}", e);
+                }
+            };
 
-        for (relation, _) in &$expected {
-            tuples.insert(
-                $crate::id::RelationId::new(relation),
-                std::collections::BTreeSet::default(),
-            );
-        }
+            let mut tuples = std::collections::BTreeMap::default();
 
-        while let Ok(Some(tuple)) = vm.pop() {
-            if let Some(relation) = tuples.get_mut(&tuple.id()) {
-                relation.insert(tuple);
+            for (relation, _) in &$expected {
+                tuples.insert(
+                    $crate::id::RelationId::new(relation),
+                    std::collections::BTreeSet::default(),
+                );
             }
-        }
-
-        for (relation, expected) in $expected {
-            let actual = tuples
-                .get(&$crate::id::RelationId::new(relation))
-                .unwrap()
-                .clone();
 
-            let expected = std::collections::BTreeSet::from_iter(expected.clone());
+            while let Ok(Some(tuple)) = vm.pop() {
+                if let Some(relation) = tuples.get_mut(&tuple.id()) {
+                    relation.insert(tuple);
+                }
+            }
 
-            pretty_assertions::assert_eq!(actual, expected, "program = \n{}", pretty);
+            for (relation, expected) in &$expected {
+                let actual = tuples
+                    .get(&$crate::id::RelationId::new(relation))
+                    .unwrap()
+                    .clone();
+
+                let expected = std::collections::BTreeSet::from_iter(expected.clone());
+
+                pretty_assertions::assert_eq!(
+                    actual,
+                    expected,
+                    "executor = {:?}, program = \n{}",
+                    executor,
+                    pretty
+                );
+            }
         }
     };
 }
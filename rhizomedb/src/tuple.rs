@@ -103,7 +103,7 @@ impl Tuple {
     }
 
     pub fn id(&self) -> RelationId {
-        self.id
+        self.id.clone()
     }
 
     pub fn col(&self, id: &ColId) -> Option<Val> {
@@ -111,7 +111,7 @@ impl Tuple {
     }
 
     pub fn cols(&self) -> Vec<ColId> {
-        self.cols.keys().copied().collect()
+        self.cols.keys().cloned().collect()
     }
 
     pub fn cid(&self) -> Option<Cid> {
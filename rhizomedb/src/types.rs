@@ -5,8 +5,9 @@ use std::{
 };
 
 use crate::{
+    decimal::Decimal,
     error::{error, Error},
-    value::{Any, Val},
+    value::{Any, Bytes, List, Map, Timestamp, Val},
 };
 use anyhow::Result;
 use cid::Cid;
@@ -50,6 +51,15 @@ impl ColType {
         }
     }
 
+    /// Whether values of this type can be stored in the other without loss. Values
+    /// of unknown type are assumed to, and checked once they're known.
+    pub fn widens_to(&self, other: &ColType) -> bool {
+        match (self, other) {
+            (ColType::Type(t1), ColType::Type(t2)) => t1.widens_to(t2),
+            _ => true,
+        }
+    }
+
     #[allow(dead_code)]
     fn inner(&self) -> Option<&Type> {
         match self {
@@ -135,6 +145,30 @@ impl IntoColType for f64 {
     }
 }
 
+impl IntoColType for i128 {
+    fn into_col_type() -> ColType {
+        ColType::Type(Type::S128)
+    }
+}
+
+impl IntoColType for u128 {
+    fn into_col_type() -> ColType {
+        ColType::Type(Type::U128)
+    }
+}
+
+impl IntoColType for Decimal {
+    fn into_col_type() -> ColType {
+        ColType::Type(Type::Decimal)
+    }
+}
+
+impl IntoColType for Timestamp {
+    fn into_col_type() -> ColType {
+        ColType::Type(Type::Timestamp)
+    }
+}
+
 impl IntoColType for char {
     fn into_col_type() -> ColType {
         ColType::Type(Type::Char)
@@ -159,6 +193,33 @@ impl IntoColType for Cid {
     }
 }
 
+impl IntoColType for Bytes {
+    fn into_col_type() -> ColType {
+        ColType::Type(Type::Bytes)
+    }
+}
+
+impl IntoColType for List {
+    fn into_col_type() -> ColType {
+        ColType::Type(Type::List)
+    }
+}
+
+impl IntoColType for Map {
+    fn into_col_type() -> ColType {
+        ColType::Type(Type::Map)
+    }
+}
+
+impl<T> IntoColType for Option<T>
+where
+    T: IntoColType,
+{
+    fn into_col_type() -> ColType {
+        T::into_col_type()
+    }
+}
+
 impl Display for ColType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -171,6 +232,7 @@ impl Display for ColType {
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum Type {
     Dyn,
+    Null,
     Bool,
     S8,
     U8,
@@ -182,23 +244,61 @@ pub enum Type {
     S64,
     U64,
     F64,
+    S128,
+    U128,
+    Decimal,
+    Timestamp,
     Char,
     String,
     Cid,
+    Bytes,
+    List,
+    Map,
 }
 
 impl Type {
     pub fn check(&self, value: &Val) -> Result<()> {
-        let other = &value.type_of();
+        let other = value.type_of();
 
-        self.unify(other).and(Ok(()))
+        if other.widens_to(self) {
+            Ok(())
+        } else {
+            error(Error::TypeMismatch(*self, other))
+        }
     }
 
+    /// Whether values of this type can be losslessly widened into the other, along
+    /// the signed, unsigned and floating point chains.
+    pub fn widens_to(&self, other: &Type) -> bool {
+        use Type::*;
+
+        self == other
+            || *self == Dyn
+            || *other == Dyn
+            || matches!(
+                (self, other),
+                (S8, S16 | S32 | S64 | S128)
+                    | (S16, S32 | S64 | S128)
+                    | (S32, S64 | S128)
+                    | (S64, S128)
+                    | (U8, U16 | U32 | U64 | U128)
+                    | (U16, U32 | U64 | U128)
+                    | (U32, U64 | U128)
+                    | (U64, U128)
+                    | (F32, F64)
+            )
+    }
+
+    /// Returns the narrowest type that values of both types widen to.
     pub fn unify(&self, other: &Type) -> Result<Type> {
         if self == other {
             Ok(*self)
         } else if *self == Type::Dyn || *other == Type::Dyn {
             Ok(Type::Dyn)
+        } else if self.widens_to(other) {
+            Ok(*other)
+        } else if other.widens_to(self) {
+            Ok(*self)
         } else if mem::discriminant(self) != mem::discriminant(other) {
             error(Error::TypeMismatch(*self, *other))
         } else {
@@ -213,6 +313,7 @@ impl Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match self {
             Type::Dyn => "dyn",
+            Type::Null => "null",
             Type::Bool => "bool",
             Type::S8 => "s8",
             Type::U8 => "u8",
@@ -224,9 +325,16 @@ impl Display for Type {
             Type::S64 => "s64",
             Type::U64 => "u64",
             Type::F64 => "f64",
+            Type::S128 => "s128",
+            Type::U128 => "u128",
+            Type::Decimal => "decimal",
+            Type::Timestamp => "timestamp",
             Type::Char => "char",
             Type::String => "string",
             Type::Cid => "CID",
+            Type::Bytes => "bytes",
+            Type::List => "list",
+            Type::Map => "map",
         };
 
         f.write_str(s)
@@ -247,5 +355,13 @@ impl RhizomeType for u64 {}
 impl RhizomeType for f64 {}
 impl RhizomeType for char {}
 impl RhizomeType for Arc<str> {}
+impl RhizomeType for i128 {}
+impl RhizomeType for u128 {}
+impl RhizomeType for Decimal {}
+impl RhizomeType for Timestamp {}
 impl RhizomeType for Cid {}
+impl RhizomeType for Bytes {}
+impl RhizomeType for List {}
+impl RhizomeType for Map {}
+impl<T> RhizomeType for Option<T> where T: RhizomeType {}
 impl RhizomeType for Any {}
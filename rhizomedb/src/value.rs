@@ -1,53 +1,60 @@
 use std::{
+    cmp::Ordering,
+    collections::{hash_map::DefaultHasher, BTreeMap},
     fmt::{self, Display},
-    hash::Hash,
-    sync::{Arc, Mutex},
+    hash::{Hash, Hasher},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use cid::Cid;
-use id_arena::{Arena, Id};
-use once_cell::sync::Lazy;
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 
-use crate::types::Type;
+use crate::{decimal::Decimal, types::Type};
 
-static ANY_ARENA: Lazy<Mutex<Arena<Val>>> = Lazy::new(|| Mutex::new(Arena::new()));
-
-#[derive(Debug, Clone, Copy, Eq)]
+/// A value of any type. Values aren't interned: each is reference counted and freed
+/// along with its last clone, and its hash is computed once, so that values that differ
+/// are usually told apart without comparing them, and without taking any lock.
+#[derive(Debug, Clone)]
 pub struct Any {
-    idx: Id<Val>,
+    inner: Arc<AnyVal>,
 }
 
-impl PartialEq for Any {
-    fn eq(&self, other: &Self) -> bool {
-        let arena = ANY_ARENA.lock().expect("Any Arena lock poisoned");
+#[derive(Debug)]
+struct AnyVal {
+    hash: u64,
+    val: Val,
+}
 
-        let Some(lhs) = arena.get(self.idx) else {
-            panic!("Any contained invalid index")
-        };
+impl AnyVal {
+    fn new(val: Val) -> Self {
+        let mut hasher = DefaultHasher::new();
+        val.hash(&mut hasher);
 
-        let Some(rhs) = arena.get(other.idx) else {
-            panic!("Any contained invalid index")
-        };
+        Self {
+            hash: hasher.finish(),
+            val,
+        }
+    }
+}
 
-        PartialEq::eq(lhs, rhs)
+impl PartialEq for Any {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+            || (self.inner.hash == other.inner.hash && self.inner.val == other.inner.val)
     }
 }
 
+impl Eq for Any {}
+
 impl Ord for Any {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        let arena = ANY_ARENA.lock().expect("Any Arena lock poisoned");
-
-        let Some(lhs) = arena.get(self.idx) else {
-            panic!("Any contained invalid index")
-        };
-
-        let Some(rhs) = arena.get(other.idx) else {
-            panic!("Any contained invalid index")
-        };
+        if Arc::ptr_eq(&self.inner, &other.inner) {
+            return std::cmp::Ordering::Equal;
+        }
 
-        Ord::cmp(lhs, rhs)
+        Ord::cmp(&self.inner.val, &other.inner.val)
     }
 }
 
@@ -58,19 +65,14 @@ impl PartialOrd for Any {
 }
 
 impl Hash for Any {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        let arena = ANY_ARENA.lock().expect("Any Arena lock poisoned");
-
-        let Some(val) = arena.get(self.idx) else {
-            panic!("Any contained invalid index")
-        };
-
-        Hash::hash(val, state);
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.inner.hash);
     }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum Val {
+    Null,
     Bool(bool),
     S8(i8),
     U8(u8),
@@ -82,14 +84,104 @@ pub enum Val {
     S64(i64),
     U64(u64),
     F64(OrderedFloat<f64>),
+    S128(#[serde(with = "wide")] i128),
+    U128(#[serde(with = "wide")] u128),
+    Decimal(Decimal),
+    Timestamp(Timestamp),
     Char(char),
     String(Arc<str>),
     Cid(Cid),
+    Bytes(#[serde(with = "bytes")] Bytes),
+    List(List),
+    Map(Map),
+}
+
+pub type Bytes = Arc<[u8]>;
+pub type List = Arc<[Val]>;
+pub type Map = Arc<BTreeMap<Arc<str>, Val>>;
+
+/// A wall-clock time, in nanoseconds since the Unix epoch.
+#[derive(
+    Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    pub fn from_nanos(nanos: i64) -> Self {
+        Self(nanos)
+    }
+
+    pub fn now() -> Self {
+        Self::from(SystemTime::now())
+    }
+
+    pub fn nanos(&self) -> i64 {
+        self.0
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(value: SystemTime) -> Self {
+        let nanos = match value.duration_since(UNIX_EPOCH) {
+            Ok(after) => i64::try_from(after.as_nanos()).unwrap_or(i64::MAX),
+            Err(before) => {
+                i64::try_from(before.duration().as_nanos()).map_or(i64::MIN, |nanos| -nanos)
+            }
+        };
+
+        Self(nanos)
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(value: Timestamp) -> Self {
+        let offset = Duration::from_nanos(value.0.unsigned_abs());
+
+        if value.0 < 0 {
+            UNIX_EPOCH - offset
+        } else {
+            UNIX_EPOCH + offset
+        }
+    }
+}
+
+/// Formats the timestamp as an RFC 3339 date and time, in UTC.
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const NANOS_PER_DAY: i64 = 86_400_000_000_000;
+
+        let days = self.0.div_euclid(NANOS_PER_DAY);
+        let nanos = self.0.rem_euclid(NANOS_PER_DAY);
+
+        // Converts days since the epoch into a civil date, following
+        // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        let secs = nanos / 1_000_000_000;
+
+        f.write_fmt(format_args!(
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:09}Z",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60,
+            nanos % 1_000_000_000
+        ))
+    }
 }
 
 impl Val {
     pub fn type_of(&self) -> Type {
         match self {
+            Val::Null => Type::Null,
             Val::Bool(_) => Type::Bool,
             Val::S8(_) => Type::S8,
             Val::U8(_) => Type::U8,
@@ -101,11 +193,58 @@ impl Val {
             Val::S64(_) => Type::S64,
             Val::U64(_) => Type::U64,
             Val::F64(_) => Type::F64,
+            Val::S128(_) => Type::S128,
+            Val::U128(_) => Type::U128,
+            Val::Decimal(_) => Type::Decimal,
+            Val::Timestamp(_) => Type::Timestamp,
             Val::Char(_) => Type::Char,
             Val::String(_) => Type::String,
             Val::Cid(_) => Type::Cid,
+            Val::Bytes(_) => Type::Bytes,
+            Val::List(_) => Type::List,
+            Val::Map(_) => Type::Map,
         }
     }
+
+    /// Converts the value to a wider type, if the conversion is lossless.
+    pub fn widen(self, to: Type) -> Option<Val> {
+        let widened = match (self, to) {
+            (v, t) if v.type_of() == t => v,
+            (Val::S8(v), Type::S16) => Val::S16(v.into()),
+            (Val::S8(v), Type::S32) => Val::S32(v.into()),
+            (Val::S8(v), Type::S64) => Val::S64(v.into()),
+            (Val::S8(v), Type::S128) => Val::S128(v.into()),
+            (Val::S16(v), Type::S32) => Val::S32(v.into()),
+            (Val::S16(v), Type::S64) => Val::S64(v.into()),
+            (Val::S16(v), Type::S128) => Val::S128(v.into()),
+            (Val::S32(v), Type::S64) => Val::S64(v.into()),
+            (Val::S32(v), Type::S128) => Val::S128(v.into()),
+            (Val::S64(v), Type::S128) => Val::S128(v.into()),
+            (Val::U8(v), Type::U16) => Val::U16(v.into()),
+            (Val::U8(v), Type::U32) => Val::U32(v.into()),
+            (Val::U8(v), Type::U64) => Val::U64(v.into()),
+            (Val::U8(v), Type::U128) => Val::U128(v.into()),
+            (Val::U16(v), Type::U32) => Val::U32(v.into()),
+            (Val::U16(v), Type::U64) => Val::U64(v.into()),
+            (Val::U16(v), Type::U128) => Val::U128(v.into()),
+            (Val::U32(v), Type::U64) => Val::U64(v.into()),
+            (Val::U32(v), Type::U128) => Val::U128(v.into()),
+            (Val::U64(v), Type::U128) => Val::U128(v.into()),
+            (Val::F32(v), Type::F64) => Val::F64(f64::from(*v).into()),
+            _ => return None,
+        };
+
+        Some(widened)
+    }
+
+    /// Compares two values of compatible types, widening the narrower of them,
+    /// rather than ordering values of different types by their variant, as `Ord` does.
+    pub fn compare(&self, other: &Val) -> anyhow::Result<Ordering> {
+        let typ = self.type_of().unify(&other.type_of())?;
+        let widen = |val: &Val| val.clone().widen(typ).unwrap_or_else(|| val.clone());
+
+        Ok(widen(self).cmp(&widen(other)))
+    }
 }
 
 impl From<bool> for Val {
@@ -174,6 +313,30 @@ impl From<f64> for Val {
     }
 }
 
+impl From<i128> for Val {
+    fn from(value: i128) -> Self {
+        Self::S128(value)
+    }
+}
+
+impl From<u128> for Val {
+    fn from(value: u128) -> Self {
+        Self::U128(value)
+    }
+}
+
+impl From<Decimal> for Val {
+    fn from(value: Decimal) -> Self {
+        Self::Decimal(value)
+    }
+}
+
+impl From<Timestamp> for Val {
+    fn from(value: Timestamp) -> Self {
+        Self::Timestamp(value)
+    }
+}
+
 impl From<char> for Val {
     fn from(value: char) -> Self {
         Self::Char(value)
@@ -192,21 +355,72 @@ impl From<String> for Val {
     }
 }
 
+impl From<Arc<str>> for Val {
+    fn from(value: Arc<str>) -> Self {
+        Self::String(value)
+    }
+}
+
 impl From<Cid> for Val {
     fn from(value: Cid) -> Self {
         Self::Cid(value)
     }
 }
 
+impl From<&[u8]> for Val {
+    fn from(value: &[u8]) -> Self {
+        Self::Bytes(Arc::from(value))
+    }
+}
+
+impl From<Vec<u8>> for Val {
+    fn from(value: Vec<u8>) -> Self {
+        Self::Bytes(Arc::from(value))
+    }
+}
+
+impl From<Bytes> for Val {
+    fn from(value: Bytes) -> Self {
+        Self::Bytes(value)
+    }
+}
+
+impl From<Vec<Val>> for Val {
+    fn from(value: Vec<Val>) -> Self {
+        Self::List(Arc::from(value))
+    }
+}
+
+impl From<List> for Val {
+    fn from(value: List) -> Self {
+        Self::List(value)
+    }
+}
+
+impl From<BTreeMap<Arc<str>, Val>> for Val {
+    fn from(value: BTreeMap<Arc<str>, Val>) -> Self {
+        Self::Map(Arc::new(value))
+    }
+}
+
+impl From<Map> for Val {
+    fn from(value: Map) -> Self {
+        Self::Map(value)
+    }
+}
+
+impl<T> From<Option<T>> for Val
+where
+    Val: From<T>,
+{
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Val::Null, Val::from)
+    }
+}
+
 impl From<Any> for Val {
     fn from(value: Any) -> Self {
-        let arena = ANY_ARENA.lock().expect("Any Arena lock poisoned");
-
-        if let Some(val) = arena.get(value.idx) {
-            val.clone()
-        } else {
-            panic!("Any contained invalid index")
-        }
+        value.inner.val.clone()
     }
 }
 
@@ -331,6 +545,50 @@ impl TryFrom<Val> for f64 {
     }
 }
 
+impl TryFrom<Val> for i128 {
+    type Error = ();
+
+    fn try_from(value: Val) -> Result<Self, Self::Error> {
+        match value {
+            Val::S128(v) => Ok(v),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<Val> for u128 {
+    type Error = ();
+
+    fn try_from(value: Val) -> Result<Self, Self::Error> {
+        match value {
+            Val::U128(v) => Ok(v),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<Val> for Decimal {
+    type Error = ();
+
+    fn try_from(value: Val) -> Result<Self, Self::Error> {
+        match value {
+            Val::Decimal(v) => Ok(v),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<Val> for Timestamp {
+    type Error = ();
+
+    fn try_from(value: Val) -> Result<Self, Self::Error> {
+        match value {
+            Val::Timestamp(v) => Ok(v),
+            _ => Err(()),
+        }
+    }
+}
+
 impl TryFrom<Val> for char {
     type Error = ();
 
@@ -375,22 +633,67 @@ impl TryFrom<Val> for Cid {
     }
 }
 
-impl TryFrom<Val> for Any {
+impl TryFrom<Val> for Bytes {
     type Error = ();
 
     fn try_from(value: Val) -> Result<Self, Self::Error> {
-        let idx = ANY_ARENA
-            .lock()
-            .expect("Any Arena lock poisoned")
-            .alloc(value);
+        match value {
+            Val::Bytes(v) => Ok(v),
+            _ => Err(()),
+        }
+    }
+}
 
-        Ok(Self { idx })
+impl TryFrom<Val> for List {
+    type Error = ();
+
+    fn try_from(value: Val) -> Result<Self, Self::Error> {
+        match value {
+            Val::List(v) => Ok(v),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<Val> for Map {
+    type Error = ();
+
+    fn try_from(value: Val) -> Result<Self, Self::Error> {
+        match value {
+            Val::Map(v) => Ok(v),
+            _ => Err(()),
+        }
+    }
+}
+
+impl<T> TryFrom<Val> for Option<T>
+where
+    T: TryFrom<Val, Error = ()>,
+{
+    type Error = ();
+
+    fn try_from(value: Val) -> Result<Self, Self::Error> {
+        match value {
+            Val::Null => Ok(None),
+            v => T::try_from(v).map(Some),
+        }
+    }
+}
+
+impl TryFrom<Val> for Any {
+    type Error = ();
+
+    fn try_from(value: Val) -> Result<Self, Self::Error> {
+        Ok(Self {
+            inner: Arc::new(AnyVal::new(value)),
+        })
     }
 }
 
 impl Display for Val {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Val::Null => f.write_str("null"),
             Val::Bool(v) => Display::fmt(v, f),
             Val::S8(v) => Display::fmt(v, f),
             Val::U8(v) => Display::fmt(v, f),
@@ -402,9 +705,271 @@ impl Display for Val {
             Val::S64(v) => Display::fmt(v, f),
             Val::U64(v) => Display::fmt(v, f),
             Val::F64(v) => Display::fmt(v, f),
+            Val::S128(v) => Display::fmt(v, f),
+            Val::U128(v) => Display::fmt(v, f),
+            Val::Decimal(v) => Display::fmt(v, f),
+            Val::Timestamp(v) => f.write_fmt(format_args!("t\"{v}\"")),
             Val::Char(v) => f.write_fmt(format_args!("{v:?}")),
             Val::String(v) => f.write_fmt(format_args!("{v:?}")),
             Val::Cid(v) => f.write_fmt(format_args!("\"{v}\"")),
+            Val::Bytes(v) => {
+                f.write_str("h'")?;
+
+                for byte in v.iter() {
+                    f.write_fmt(format_args!("{byte:02x}"))?;
+                }
+
+                f.write_str("'")
+            }
+            Val::List(v) => {
+                f.write_str("[")?;
+
+                for (i, val) in v.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    Display::fmt(val, f)?;
+                }
+
+                f.write_str("]")
+            }
+            Val::Map(v) => {
+                f.write_str("{")?;
+
+                for (i, (key, val)) in v.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    f.write_fmt(format_args!("{key:?}: {val}"))?;
+                }
+
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+/// Encodes bytes as a byte string, rather than as a sequence of integers.
+mod bytes {
+    use std::{fmt, sync::Arc};
+
+    use serde::{
+        de::{self, SeqAccess, Visitor},
+        Deserializer, Serializer,
+    };
+
+    pub(super) fn serialize<S>(bytes: &Arc<[u8]>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(bytes)
+    }
+
+    pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<Arc<[u8]>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+
+    struct BytesVisitor;
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Arc<[u8]>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a byte string")
+        }
+
+        fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(Arc::from(v))
+        }
+
+        fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(Arc::from(v))
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+
+            while let Some(byte) = seq.next_element()? {
+                bytes.push(byte);
+            }
+
+            Ok(Arc::from(bytes))
+        }
+    }
+}
+
+/// Encodes 128-bit integers as 16 big-endian bytes, since DAG-CBOR integers are
+/// limited to 64 bits.
+mod wide {
+    use serde::{de, Deserializer, Serializer};
+
+    pub(super) trait Wide: Sized {
+        fn to_be_bytes(&self) -> [u8; 16];
+        fn from_be_bytes(bytes: [u8; 16]) -> Self;
+    }
+
+    impl Wide for i128 {
+        fn to_be_bytes(&self) -> [u8; 16] {
+            i128::to_be_bytes(*self)
+        }
+
+        fn from_be_bytes(bytes: [u8; 16]) -> Self {
+            i128::from_be_bytes(bytes)
+        }
+    }
+
+    impl Wide for u128 {
+        fn to_be_bytes(&self) -> [u8; 16] {
+            u128::to_be_bytes(*self)
         }
+
+        fn from_be_bytes(bytes: [u8; 16]) -> Self {
+            u128::from_be_bytes(bytes)
+        }
+    }
+
+    pub(super) fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Wide,
+    {
+        serializer.serialize_bytes(&value.to_be_bytes())
+    }
+
+    pub(super) fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Wide,
+    {
+        let bytes = super::bytes::deserialize(deserializer)?;
+        let bytes = <[u8; 16]>::try_from(&*bytes)
+            .map_err(|_| de::Error::invalid_length(bytes.len(), &"16 bytes"))?;
+
+        Ok(T::from_be_bytes(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cmp::Ordering, collections::BTreeMap, sync::Arc};
+
+    use libipld::Ipld;
+
+    use crate::{decimal::Decimal, error::Error, types::Type};
+
+    use super::{Any, Timestamp, Val};
+
+    #[test]
+    fn test_any() {
+        let a = Any::try_from(Val::from("a")).unwrap();
+        let b = Any::try_from(Val::from("a")).unwrap();
+        let c = Any::try_from(Val::from(1)).unwrap();
+
+        assert_eq!(a, b);
+        assert_eq!(a, a.clone());
+        assert_ne!(a, c);
+        assert_eq!(Ordering::Greater, a.cmp(&c));
+    }
+
+    #[test]
+    fn test_compound_round_trip() {
+        let val = Val::from(vec![
+            Val::from(vec![0xde_u8, 0xad]),
+            Val::from(BTreeMap::from([(Arc::from("k"), Val::from(1))])),
+            Val::from(vec![Val::from("nested")]),
+        ]);
+
+        let bytes = serde_ipld_dagcbor::to_vec(&val).unwrap();
+        let decoded: Val = serde_ipld_dagcbor::from_slice(&bytes).unwrap();
+
+        assert_eq!(val, decoded);
+        assert_eq!("[h'dead', {\"k\": 1}, [\"nested\"]]", val.to_string());
+    }
+
+    #[test]
+    fn test_wide_round_trip() {
+        let vals = [
+            Val::from(i128::MIN),
+            Val::from(u128::MAX),
+            Val::from("-1234.5678".parse::<Decimal>().unwrap()),
+            Val::from(Timestamp::from_nanos(1_700_000_000_123_456_789)),
+        ];
+
+        for val in vals {
+            let bytes = serde_ipld_dagcbor::to_vec(&val).unwrap();
+            let decoded: Val = serde_ipld_dagcbor::from_slice(&bytes).unwrap();
+
+            assert_eq!(val, decoded);
+        }
+
+        let bytes = serde_ipld_dagcbor::to_vec(&Val::from(1_u128)).unwrap();
+        let ipld: Ipld = serde_ipld_dagcbor::from_slice(&bytes).unwrap();
+
+        assert_eq!(
+            Ipld::Map(BTreeMap::from([(
+                "U128".to_owned(),
+                Ipld::Bytes([[0; 15].as_slice(), &[1]].concat())
+            )])),
+            ipld
+        );
+    }
+
+    #[test]
+    fn test_timestamp_display() {
+        assert_eq!(
+            "t\"2023-11-14T22:13:20.123456789Z\"",
+            Val::from(Timestamp::from_nanos(1_700_000_000_123_456_789)).to_string()
+        );
+        assert_eq!(
+            "1969-12-31T23:59:59.000000000Z",
+            Timestamp::from_nanos(-1_000_000_000).to_string()
+        );
+    }
+
+    #[test]
+    fn test_compare() {
+        let before = Val::from(Timestamp::from_nanos(1));
+        let after = Val::from(Timestamp::from_nanos(2));
+
+        assert_eq!(Ordering::Less, before.compare(&after).unwrap());
+        assert_eq!(
+            Ordering::Greater,
+            Val::from(2_i8).compare(&Val::from(1_i64)).unwrap()
+        );
+        assert_eq!(
+            Some(&Error::TypeMismatch(Type::Timestamp, Type::S64)),
+            before
+                .compare(&Val::from(2_i64))
+                .unwrap_err()
+                .downcast_ref()
+        );
+    }
+
+    #[test]
+    fn test_bytes_encoding() {
+        let bytes = serde_ipld_dagcbor::to_vec(&Val::from(vec![1_u8, 2, 3])).unwrap();
+        let ipld: Ipld = serde_ipld_dagcbor::from_slice(&bytes).unwrap();
+
+        assert_eq!(
+            Ipld::Map(BTreeMap::from([(
+                "Bytes".to_owned(),
+                Ipld::Bytes(vec![1, 2, 3])
+            )])),
+            ipld
+        );
     }
 }
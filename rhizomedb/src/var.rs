@@ -32,6 +32,10 @@ impl Var {
     pub fn typ(&self) -> ColType {
         self.typ
     }
+
+    pub(crate) fn with_typ(self, typ: ColType) -> Self {
+        Self { typ, ..self }
+    }
 }
 
 impl<T> From<TypedVar<T>> for Var
@@ -39,7 +43,7 @@ where
     T: IntoColType,
 {
     fn from(value: TypedVar<T>) -> Self {
-        Self::new::<T>(value.id().to_string().as_ref())
+        value.as_var()
     }
 }
 
@@ -49,13 +53,22 @@ impl Display for Var {
     }
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[derive(Debug, Eq, PartialEq, Hash)]
 pub struct TypedVar<T> {
     id: VarId,
     typ: ColType,
     _marker: PhantomData<T>,
 }
 
+// Implemented by hand, since variables are copyable whether or not their type is
+impl<T> Clone for TypedVar<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TypedVar<T> {}
+
 impl<T> TypedVar<T>
 where
     T: IntoColType,
@@ -80,7 +93,10 @@ where
     }
 
     pub fn as_var(&self) -> Var {
-        Var::new::<T>(self.id().to_string().as_ref())
+        Var {
+            id: self.id,
+            typ: self.typ,
+        }
     }
 }
 